@@ -0,0 +1,106 @@
+-- Initial schema of the Starklane indexer.
+-- Block numbers and timestamps are stored as BIGINT, addresses and hashes
+-- as normalized hexadecimal strings.
+
+CREATE TABLE requests (
+    hash TEXT PRIMARY KEY,
+    chain_src TEXT NOT NULL,
+    from_address TEXT NOT NULL,
+    to_address TEXT NOT NULL,
+    collection_src TEXT NOT NULL,
+    collection_dst TEXT NOT NULL,
+    content TEXT NOT NULL
+);
+
+CREATE INDEX requests_to_address_idx ON requests (to_address);
+
+CREATE TABLE events (
+    req_hash TEXT NOT NULL,
+    label TEXT NOT NULL,
+    -- Chain where the event was emitted, deduced from the label.
+    chain TEXT NOT NULL,
+    block_timestamp BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    price_gas BIGINT,
+    price_usd TEXT,
+    finality TEXT,
+    PRIMARY KEY (req_hash, label, tx_hash)
+);
+
+CREATE INDEX events_tx_hash_idx ON events (tx_hash);
+CREATE INDEX events_block_number_idx ON events (chain, block_number);
+
+CREATE TABLE blocks (
+    chain TEXT NOT NULL,
+    block_number BIGINT NOT NULL,
+    insert_timestamp BIGINT NOT NULL,
+    block_hash TEXT,
+    parent_hash TEXT,
+    PRIMARY KEY (chain, block_number)
+);
+
+CREATE TABLE indexed_ranges (
+    chain TEXT NOT NULL,
+    from_block BIGINT NOT NULL,
+    to_block BIGINT NOT NULL,
+    PRIMARY KEY (chain, from_block)
+);
+
+CREATE TABLE cursors (
+    chain TEXT PRIMARY KEY,
+    block_number BIGINT NOT NULL,
+    update_timestamp BIGINT NOT NULL
+);
+
+CREATE TABLE xchain_txs (
+    req_hash TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    chain TEXT NOT NULL,
+    req_content TEXT NOT NULL,
+    -- Empty until the transaction is sent.
+    tx_hash TEXT NOT NULL,
+    PRIMARY KEY (req_hash, kind)
+);
+
+-- One row per token bridged on starknet.
+CREATE TABLE bridged_tokens (
+    tx_hash TEXT NOT NULL,
+    req_hash TEXT NOT NULL,
+    token_id TEXT NOT NULL,
+    collection_src TEXT NOT NULL,
+    from_address TEXT NOT NULL,
+    to_address TEXT NOT NULL,
+    is_checked BOOLEAN NOT NULL,
+    PRIMARY KEY (tx_hash, req_hash, token_id)
+);
+
+CREATE INDEX bridged_tokens_collection_src_idx ON bridged_tokens (collection_src);
+
+CREATE TABLE pending_withdraws (
+    tx_hash TEXT PRIMARY KEY,
+    req_hash TEXT NOT NULL,
+    chain_src TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    message_hash BYTEA NOT NULL
+);
+
+CREATE TABLE collections (
+    address_src TEXT NOT NULL,
+    address_dst TEXT NOT NULL,
+    chain_src TEXT NOT NULL,
+    req_hash TEXT,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (address_src, address_dst)
+);
+
+CREATE TABLE admin_events (
+    chain TEXT NOT NULL,
+    block_number BIGINT NOT NULL,
+    event_index BIGINT NOT NULL,
+    -- The decoded event, as JSON.
+    kind TEXT NOT NULL,
+    block_timestamp BIGINT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    PRIMARY KEY (chain, block_number, event_index)
+);
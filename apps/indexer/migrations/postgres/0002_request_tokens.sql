@@ -0,0 +1,18 @@
+-- Request content decoded at index time.
+ALTER TABLE requests ADD COLUMN collection_type TEXT NOT NULL DEFAULT 'erc721';
+ALTER TABLE requests ADD COLUMN name TEXT NOT NULL DEFAULT '';
+ALTER TABLE requests ADD COLUMN symbol TEXT NOT NULL DEFAULT '';
+ALTER TABLE requests ADD COLUMN base_uri TEXT NOT NULL DEFAULT '';
+
+-- One row per token moved by a request.
+CREATE TABLE request_tokens (
+    req_hash TEXT NOT NULL,
+    -- Position of the token in the request.
+    token_index BIGINT NOT NULL,
+    token_id TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    uri TEXT,
+    PRIMARY KEY (req_hash, token_index)
+);
+
+CREATE INDEX request_tokens_token_id_idx ON request_tokens (token_id);
@@ -0,0 +1,12 @@
+-- Relayer state of the cross chain transactions.
+ALTER TABLE xchain_txs ADD COLUMN status TEXT NOT NULL DEFAULT 'queued';
+ALTER TABLE xchain_txs ADD COLUMN attempts BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE xchain_txs ADD COLUMN nonce BIGINT;
+-- Gas price in wei (decimal).
+ALTER TABLE xchain_txs ADD COLUMN gas_price TEXT;
+ALTER TABLE xchain_txs ADD COLUMN last_error TEXT;
+ALTER TABLE xchain_txs ADD COLUMN next_attempt_at BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE xchain_txs ADD COLUMN submitted_at BIGINT;
+
+-- Transactions sent before the statuses were introduced.
+UPDATE xchain_txs SET status = 'confirmed' WHERE tx_hash <> '';
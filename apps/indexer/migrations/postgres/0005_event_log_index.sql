@@ -0,0 +1,9 @@
+-- Index of the log (ethereum) or of the event (starknet) in the block,
+-- null for the events inserted by the indexer.
+ALTER TABLE events ADD COLUMN log_index BIGINT;
+CREATE UNIQUE INDEX events_log_key ON events (chain, tx_hash, log_index, label)
+    WHERE log_index IS NOT NULL;
+
+-- One transaction may deposit several requests.
+ALTER TABLE pending_withdraws DROP CONSTRAINT pending_withdraws_pkey;
+ALTER TABLE pending_withdraws ADD PRIMARY KEY (req_hash, tx_hash);
@@ -0,0 +1,10 @@
+-- Creation time of the requests (timestamp of the block of their first event),
+-- to sort and paginate the requests of a wallet.
+ALTER TABLE requests ADD COLUMN timestamp BIGINT NOT NULL DEFAULT 0;
+
+UPDATE requests SET timestamp = COALESCE(
+    (SELECT MIN(block_timestamp) FROM events WHERE events.req_hash = requests.hash), 0);
+
+CREATE INDEX requests_from_address_timestamp_idx ON requests (from_address, timestamp, hash);
+CREATE INDEX requests_to_address_timestamp_idx ON requests (to_address, timestamp, hash);
+DROP INDEX requests_to_address_idx;
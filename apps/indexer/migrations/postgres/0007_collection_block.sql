@@ -0,0 +1,6 @@
+-- Chain and block of the event which registered the collection, to remove
+-- the collections registered in orphaned or rejected blocks.
+-- Null for the collections registered before.
+ALTER TABLE collections ADD COLUMN chain TEXT;
+ALTER TABLE collections ADD COLUMN block_number BIGINT;
+CREATE INDEX collections_block_idx ON collections (chain, block_number);
@@ -0,0 +1,6 @@
+-- Decisions of the relayer fee policy.
+ALTER TABLE xchain_txs ADD COLUMN relay_decision TEXT;
+-- Fees in wei (decimal).
+ALTER TABLE xchain_txs ADD COLUMN estimated_fee TEXT;
+ALTER TABLE xchain_txs ADD COLUMN paid_fee TEXT;
+ALTER TABLE xchain_txs ADD COLUMN deferred_since INTEGER;
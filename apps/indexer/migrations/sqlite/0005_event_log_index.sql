@@ -0,0 +1,19 @@
+-- Index of the log (ethereum) or of the event (starknet) in the block,
+-- null for the events inserted by the indexer.
+ALTER TABLE events ADD COLUMN log_index INTEGER;
+CREATE UNIQUE INDEX events_log_key ON events (chain, tx_hash, log_index, label)
+    WHERE log_index IS NOT NULL;
+
+-- One transaction may deposit several requests.
+CREATE TABLE pending_withdraws_new (
+    tx_hash TEXT NOT NULL,
+    req_hash TEXT NOT NULL,
+    chain_src TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    message_hash BLOB NOT NULL,
+    PRIMARY KEY (req_hash, tx_hash)
+);
+INSERT INTO pending_withdraws_new SELECT tx_hash, req_hash, chain_src, timestamp, message_hash
+    FROM pending_withdraws;
+DROP TABLE pending_withdraws;
+ALTER TABLE pending_withdraws_new RENAME TO pending_withdraws;
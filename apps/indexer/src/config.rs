@@ -3,7 +3,6 @@ use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::Read;
 
-///
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainConfig {
     pub rpc_url: String,
@@ -15,24 +14,63 @@ pub struct ChainConfig {
     pub messaging_timeout: Option<u64>,
     /// For auto withdraw
     pub account_address: Option<String>,
+    /// Plaintext private key, prefer `signer` outside of local development.
     pub account_private_key: Option<String>,
+    /// Signer of the relayer transactions, replacing `account_private_key`.
+    #[serde(default)]
+    pub signer: Option<SignerConfig>,
     #[serde(default = "cooling_down_default")]
     pub cooling_down: u64,
+    /// Number of blocks to wait before a block is considered
+    /// final enough to be indexed.
+    #[serde(default)]
+    pub confirmation_depth: u64,
 }
 
 const fn cooling_down_default() -> u64 {
     20
 }
 
-///
+/// Source of the key signing the relayer transactions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// Encrypted JSON keystore, with its password read from a file.
+    Keystore { path: String, password_file: String },
+    /// HTTP remote signer, holding the key identified by `key_id`.
+    /// The requests are authenticated with the bearer token read from `auth_token_file`.
+    Remote {
+        url: String,
+        key_id: String,
+        #[serde(default)]
+        auth_token_file: Option<String>,
+    },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct XchainTxConfig {
     pub enabled: bool,
     pub sn_min_block: u64,
     pub eth_min_block: u64,
+    /// Time in seconds a withdraw auto transaction too expensive
+    /// for the fee paid is deferred, before being marked as unprofitable.
+    #[serde(default = "max_defer_default")]
+    pub max_defer: u64,
+    /// Fee in wei paid by the users for each withdraw auto request. When not set,
+    /// the withdraw auto transactions are always relayed.
+    #[serde(default)]
+    pub request_fee: Option<String>,
+    /// Queues a `burn_auto` transaction on starknet for the requests withdrawn on L1
+    /// which ask the escrowed tokens to be burnt. Disabled by default, the transactions
+    /// are not sent until the starknet bridge exposes a `burn_auto` entrypoint.
+    #[serde(default)]
+    pub burn_auto: bool,
+}
+
+const fn max_defer_default() -> u64 {
+    86_400
 }
 
-///
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StarklaneIndexerConfig {
     pub ethereum: ChainConfig,
@@ -5,13 +5,14 @@ use anyhow::{anyhow, Result};
 use ethers::prelude::*;
 use ethers::providers::{Http, Provider};
 use ethers::types::{Address, BlockNumber, Log};
-use k256::ecdsa::SigningKey;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::sync::Mutex as AsyncMutex;
 use tokio::time::{self, Duration};
 
 use crate::config::ChainConfig;
+use crate::signer::ethereum::EthereumSigner;
 
 abigen!(
     StarklaneBridge,
@@ -35,58 +36,137 @@ abigen!(
 // Starklane logs are usually small (data < 50 bytes).
 const BLOCKS_MAX_RANGE: u64 = 200;
 
-///
+// Minimum increase (in percent) of the gas price to replace a pending transaction.
+const GAS_PRICE_BUMP_PERCENT: u64 = 25;
+
 pub struct EthereumClient {
     provider: Provider<Http>,
-    provider_signer: Option<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
+    provider_signer: Option<SignerMiddleware<Provider<Http>, EthereumSigner>>,
     bridge_address: Address,
     messaging_address: Address,
+    // Next nonce of the signer, fetched from the node when unknown.
+    nonce: AsyncMutex<Option<U256>>,
 }
 
 impl EthereumClient {
-    ///
     pub async fn new(config: ChainConfig) -> Result<EthereumClient> {
-        let provider = Provider::<Http>::try_from(&config.rpc_url)?;
+        let mut client = EthereumClient::read_only(&config)?;
 
-        let chain_id = provider
+        let chain_id = client
+            .provider
             .get_chainid()
             .await
             .expect("Failed to retrieve ChainId");
 
-        let provider_signer = if let Some(pk) = &config.account_private_key {
-            let wallet: LocalWallet = pk.parse::<LocalWallet>()?.with_chain_id(chain_id.as_u32());
-            Some(SignerMiddleware::new(provider.clone(), wallet.clone()))
-        } else {
-            None
-        };
+        client.provider_signer = EthereumSigner::from_config(&config, chain_id.as_u64())
+            .await?
+            .map(|signer| SignerMiddleware::new(client.provider.clone(), signer));
+
+        Ok(client)
+    }
+
+    /// Returns a client without signer, only reading from the chain,
+    /// to not load the key of the relayer.
+    pub fn read_only(config: &ChainConfig) -> Result<EthereumClient> {
+        let provider = Provider::<Http>::try_from(&config.rpc_url)?;
 
         let bridge_address = Address::from_str(&config.bridge_address)?;
-        let messaging_address = Address::from_str(&config.messaging_address.unwrap())?;
+        let messaging_address = match &config.messaging_address {
+            Some(a) => Address::from_str(a)?,
+            None => return Err(anyhow!("A messaging address is required on ethereum")),
+        };
+
         Ok(EthereumClient {
             provider,
-            provider_signer,
+            provider_signer: None,
             bridge_address,
             messaging_address,
+            nonce: AsyncMutex::new(None),
         })
     }
 
-    ///
     pub fn get_bridge_caller(&self) -> StarklaneBridge<Provider<Http>> {
         StarklaneBridge::new(self.bridge_address, Arc::new(self.provider.clone()))
     }
 
-    ///
     pub fn get_bridge_sender(
         &self,
-    ) -> StarklaneBridge<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>> {
-        let signer = self.provider_signer
+    ) -> StarklaneBridge<SignerMiddleware<Provider<Http>, EthereumSigner>> {
+        let signer = self
+            .provider_signer
             .clone()
-            .expect("Bridge sender requested but not initialized. Did you provide a private key in the config?");
+            .expect("Bridge sender requested but not initialized. Did you configure a signer?");
 
         StarklaneBridge::new(self.bridge_address, Arc::new(signer))
     }
 
-    ///
+    /// Returns the nonce to use for the next transaction of the signer.
+    pub async fn next_nonce(&self) -> Result<U256> {
+        let signer = match &self.provider_signer {
+            Some(s) => s,
+            None => anyhow::bail!("A signer is required to send transaction on ethereum!"),
+        };
+
+        let mut nonce = self.nonce.lock().await;
+
+        let next = match *nonce {
+            Some(n) => n,
+            None => {
+                self.provider
+                    .get_transaction_count(signer.address(), Some(BlockNumber::Pending.into()))
+                    .await?
+            }
+        };
+
+        *nonce = Some(next + 1);
+        Ok(next)
+    }
+
+    /// Returns the nonce of the signer in the latest block, which is
+    /// the number of transactions of the signer already mined.
+    pub async fn mined_nonce(&self) -> Result<U256> {
+        let signer = match &self.provider_signer {
+            Some(s) => s,
+            None => anyhow::bail!("A signer is required to send transaction on ethereum!"),
+        };
+
+        Ok(self
+            .provider
+            .get_transaction_count(signer.address(), Some(BlockNumber::Latest.into()))
+            .await?)
+    }
+
+    /// Forgets the nonce of the signer, to fetch it again from the node
+    /// after a transaction failed to be sent.
+    pub async fn reset_nonce(&self) {
+        *self.nonce.lock().await = None;
+    }
+
+    pub async fn get_gas_price(&self) -> Result<U256> {
+        Ok(self.provider.get_gas_price().await?)
+    }
+
+    /// Returns the gas estimated to withdraw the request with the given content.
+    pub async fn estimate_withdraw_gas(&self, req_content: Vec<U256>) -> Result<U256> {
+        Ok(self
+            .get_bridge_sender()
+            .withdraw_tokens(req_content)
+            .estimate_gas()
+            .await?)
+    }
+
+    /// Returns the execution status of the transaction (true on success),
+    /// or `None` if the transaction is not mined yet.
+    pub async fn tx_receipt_status(&self, transaction_hash: &str) -> Result<Option<bool>> {
+        let tx_hash: TxHash = H256::from_str(transaction_hash)?;
+
+        Ok(self
+            .provider
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .map(|r| r.status == Some(1.into())))
+    }
+
     pub async fn get_block_number(&self) -> Result<u64> {
         match self.provider.get_block_number().await {
             Ok(v) => Ok(v.try_into().unwrap()),
@@ -94,10 +174,22 @@ impl EthereumClient {
         }
     }
 
+    /// Returns the hash and the parent hash of the given block,
+    /// or `None` if the block is not known by the node.
+    pub async fn get_block_hashes(&self, block_id: u64) -> Result<Option<(String, String)>> {
+        match self.provider.get_block(block_id).await {
+            Ok(block) => Ok(block.and_then(|b| {
+                b.hash
+                    .map(|h| (format!("{:#064x}", h), format!("{:#064x}", b.parent_hash)))
+            })),
+            Err(e) => Err(anyhow!("Eth retrieving block hashes {}: {:?}", block_id, e)),
+        }
+    }
+
     pub async fn get_block_timestamp(&self, block_id: u64) -> Result<u64> {
         let block = self.provider.get_block(block_id).await;
-        if block.is_ok() {
-            match block.unwrap() {
+        if let Ok(block) = block {
+            match block {
                 None => Ok(0),
                 Some(block) => match block.timestamp.try_into() {
                     Ok(v) => Ok(v),
@@ -115,90 +207,50 @@ impl EthereumClient {
         }
     }
 
-    /// Fetches logs for the given block options.
+    /// Fetches the logs of the next slice of blocks, starting at `from_block`
+    /// and containing at most `BLOCKS_MAX_RANGE` blocks, without going beyond `to_block`.
     ///
     /// There is not pagination in ethereum, and no hard limit on block range.
-    /// To avoid too large requests and error from RPC, only safe range of blocks
-    /// are fetched. We then iterate on those ranges to fullfill the inital range requested.
+    /// To avoid too large requests and error from RPC, only a safe range of blocks
+    /// is fetched. The caller is expected to process the returned logs and
+    /// to call this function again from the block following the last block fetched,
+    /// to keep the memory usage bounded.
     ///
-    /// Note: This version can be very RAM consuming, especially if the block range is very
-    /// big.
-    /// TODO: implement a `fetch_logs_safe` which is only fecthing a safe range limit,
-    /// and return the last block fetched (Result<(Vec<Log>, u64)>) to let the caller
-    /// iterate as it needs.
+    /// Returns the logs grouped by block number, and the last block fetched.
     pub async fn fetch_logs(
         &self,
         from_block: u64,
         to_block: u64,
-    ) -> Result<HashMap<u64, Vec<Log>>> {
+    ) -> Result<(BTreeMap<u64, Vec<Log>>, u64)> {
         // TODO: add this to configuration as if we use a local node,
         // this constraint may be removed.
         time::sleep(Duration::from_millis(500)).await;
 
-        log::info!("Eth fetching blocks {} - {}", from_block, to_block);
-        let mut from_block = from_block;
-        let mut diff = to_block - from_block;
-        let mut range = BLOCKS_MAX_RANGE;
-
-        let mut to_block_range = from_block + range - 1;
-        if to_block_range > to_block {
-            to_block_range = to_block;
-        }
+        let last_block = to_block.min(from_block + BLOCKS_MAX_RANGE - 1);
 
-        let mut logs: HashMap<u64, Vec<Log>> = HashMap::new();
+        log::info!("Eth fetching blocks {} - {}", from_block, last_block);
 
-        let mut filters = Filter {
+        let filters = Filter {
             block_option: FilterBlockOption::Range {
                 from_block: Some(BlockNumber::Number(from_block.into())),
-                to_block: Some(BlockNumber::Number(to_block_range.into())),
+                to_block: Some(BlockNumber::Number(last_block.into())),
             },
             address: Some(ValueOrArray::Value(self.bridge_address)),
             topics: Default::default(),
         };
 
-        // TODO: rework the logic around the block fetching and limits.
-        // Ethereum RPC is returning an error if a block that is after the latest
-        // block is requested in the filters.
-        loop {
-            let range_logs = self.provider.get_logs(&filters).await?;
-
-            range_logs.iter().for_each(|l| {
-                logs.entry(
-                    l.block_number
-                        .expect("Log is expected to have a block number")
-                        .try_into()
-                        .unwrap(),
-                )
-                .and_modify(|v| v.push(l.clone()))
-                .or_insert(vec![l.clone()]);
-            });
-
-            from_block += range;
-            if from_block > to_block {
-                break;
-            }
-
-            if diff >= BLOCKS_MAX_RANGE {
-                diff -= BLOCKS_MAX_RANGE;
-            } else if diff > 0 {
-                range = diff;
-                diff = 0;
-            } else {
-                break;
-            }
-
-            to_block_range = from_block + range - 1;
-            if to_block_range > to_block {
-                to_block_range = to_block;
-            }
+        let mut logs: BTreeMap<u64, Vec<Log>> = BTreeMap::new();
 
-            filters.block_option = FilterBlockOption::Range {
-                from_block: Some(BlockNumber::Number(from_block.into())),
-                to_block: Some(BlockNumber::Number(to_block_range.into())),
+        for l in self.provider.get_logs(&filters).await? {
+            let block_number = match l.block_number {
+                Some(n) => n.as_u64(),
+                None => return Err(anyhow!("Log is expected to have a block number")),
             };
+
+            logs.entry(block_number).or_default().push(l);
         }
 
-        Ok(logs)
+        Ok((logs, last_block))
     }
 
     /// Retrieve message status in StarknetCore messaging contract
@@ -228,3 +280,80 @@ impl EthereumClient {
         }
     }
 }
+
+/// Returns the gas price to replace a pending transaction sent with `previous`,
+/// which must be higher than the previous one to be accepted by the nodes.
+pub fn bump_gas_price(previous: U256, current: U256) -> U256 {
+    let bumped = previous * (100 + GAS_PRICE_BUMP_PERCENT) / 100;
+    bumped.max(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, routing::post, Json, Router, Server};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Answers the JSON-RPC requests, with the pending transaction count of the signer.
+    async fn mock_rpc(State(count): State<Arc<AtomicU64>>, Json(req): Json<Value>) -> Json<Value> {
+        let result = match req["method"].as_str() {
+            Some("eth_chainId") => json!("0x5"),
+            Some("eth_getTransactionCount") => {
+                json!(format!("{:#x}", count.load(Ordering::SeqCst)))
+            }
+            _ => Value::Null,
+        };
+
+        Json(json!({ "jsonrpc": "2.0", "id": req["id"], "result": result }))
+    }
+
+    #[tokio::test]
+    async fn test_nonce_freed() {
+        let count = Arc::new(AtomicU64::new(7));
+        let app = Router::new()
+            .route("/", post(mock_rpc))
+            .with_state(count.clone());
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let rpc_url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(
+            Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        let client = EthereumClient::new(ChainConfig {
+            rpc_url,
+            from_block: String::from("0"),
+            to_block: None,
+            bridge_address: format!("{:#x}", Address::from_low_u64_be(1)),
+            fetch_interval: 1,
+            messaging_address: Some(format!("{:#x}", Address::from_low_u64_be(2))),
+            messaging_timeout: None,
+            account_address: None,
+            account_private_key: Some(String::from(
+                "0x0000000000000000000000000000000000000000000000000000000000001234",
+            )),
+            signer: None,
+            cooling_down: 0,
+            confirmation_depth: 0,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(client.next_nonce().await.unwrap(), 7.into());
+        assert_eq!(client.next_nonce().await.unwrap(), 8.into());
+
+        // The transaction sent with the nonce 7 is abandoned, and dropped by the node.
+        client.reset_nonce().await;
+        assert_eq!(client.next_nonce().await.unwrap(), 7.into());
+        assert_eq!(client.next_nonce().await.unwrap(), 8.into());
+    }
+
+    #[test]
+    fn test_bump_gas_price() {
+        assert_eq!(bump_gas_price(100.into(), 90.into()), 125.into());
+        assert_eq!(bump_gas_price(100.into(), 200.into()), 200.into());
+        assert_eq!(bump_gas_price(U256::zero(), 10.into()), 10.into());
+    }
+}
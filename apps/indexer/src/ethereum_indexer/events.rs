@@ -1,16 +1,13 @@
 use crate::{
-    storage::{BridgeChain, CrossChainTx, CrossChainTxKind, Event, EventLabel, Request},
+    storage::{
+        AdminEvent, AdminEventKind, BridgeChain, CollectionContract, CrossChainTx,
+        CrossChainTxKind, CrossChainTxStatus, Event, EventLabel, Request,
+    },
     utils::normalize_hex,
 };
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use ethers::prelude::*;
-use serde_json::{json, Value};
 
-// TODO: refacto this to be common with starknet.
-pub const REQUEST_HEADER_WITHDRAW_AUTO: u128 = 0x01000000;
-pub const REQUEST_HEADER_BURN_AUTO: u128 = 0x010000;
-
-///
 #[derive(Debug, PartialEq, Eq, EthEvent)]
 pub struct DepositRequestInitiated {
     #[ethevent(indexed)]
@@ -19,7 +16,6 @@ pub struct DepositRequestInitiated {
     req_content: Vec<U256>,
 }
 
-///
 #[derive(Debug, PartialEq, Eq, EthEvent)]
 pub struct WithdrawRequestCompleted {
     #[ethevent(indexed)]
@@ -28,15 +24,101 @@ pub struct WithdrawRequestCompleted {
     req_content: Vec<U256>,
 }
 
+#[derive(Debug, PartialEq, Eq, EthEvent)]
+pub struct CancelRequestStarted {
+    #[ethevent(indexed)]
+    hash: U256,
+    block_timestamp: U256,
+}
+
+#[derive(Debug, PartialEq, Eq, EthEvent)]
+pub struct CancelRequestCompleted {
+    #[ethevent(indexed)]
+    hash: U256,
+    block_timestamp: U256,
+}
+
+#[derive(Debug, PartialEq, Eq, EthEvent)]
+pub struct CollectionDeployedFromL2 {
+    #[ethevent(indexed)]
+    req_hash: U256,
+    block_timestamp: U256,
+    l1_address: Address,
+    l2_address: U256,
+}
+
+#[derive(Debug, PartialEq, Eq, EthEvent)]
+pub struct L1L2CollectionMappingUpdated {
+    #[ethevent(indexed)]
+    collection_l1: Address,
+    #[ethevent(indexed)]
+    collection_l2: U256,
+}
+
+#[derive(Debug, PartialEq, Eq, EthEvent)]
+pub struct WhiteListUpdated {
+    enable: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, EthEvent)]
+pub struct CollectionWhiteListUpdated {
+    #[ethevent(indexed)]
+    collection: Address,
+    enable: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, EthEvent)]
+pub struct MinimumGasFeeUpdated {
+    #[ethevent(indexed)]
+    gas_fee: U256,
+}
+
+#[derive(Debug, PartialEq, Eq, EthEvent)]
+pub struct OwnershipTransferred {
+    #[ethevent(indexed)]
+    previous_owner: Address,
+    #[ethevent(indexed)]
+    new_owner: Address,
+}
+
+#[derive(Debug, PartialEq, Eq, EthEvent)]
+pub struct Upgraded {
+    #[ethevent(indexed)]
+    implementation: Address,
+}
+
 const DEPOSIT_REQUEST_INITIATED_SIG: &str =
     "0x4ecaf4a99ef1a36d5c1967133fb3f251e98f89361d2b43ee590c283171051b8c";
 
 const WITHDRAW_REQUEST_COMPLETED_SIG: &str =
     "0x1969477bb1c714c2de347e8b12129f967163d2cdd4bbc4a0d1e0f062211d86ed";
 
+const CANCEL_REQUEST_STARTED_SIG: &str =
+    "0x4be054ecd91565a8f7086a88203517a7c76e417d1ed86a726777068550a77f64";
+
+const CANCEL_REQUEST_COMPLETED_SIG: &str =
+    "0x1cf3498a236660716b548e46b7ffa803539293b995335e2643b01aa6384d2f09";
+
 const COLLECTION_DEPOYED_FROM_L2_SIG: &str =
     "0xf1653c653aee21ff13e04dc08fdab8b953d980fc4d17e032af195883a4623245";
 
+const L1L2_COLLECTION_MAPPING_UPDATED_SIG: &str =
+    "0x6ebc0c21f789fa3b1dd7da613a402cfcb0e0863ad2abb3225bd7ea642854e059";
+
+const WHITE_LIST_UPDATED_SIG: &str =
+    "0x22e404eee584e24f7b35123f593bdf0638a140a1aacd787ad8c54c147e414d6d";
+
+const COLLECTION_WHITE_LIST_UPDATED_SIG: &str =
+    "0x6c05287a0037f4bc41d11655cad3a0051cbfe83db1eecfd239b6a69678f74c52";
+
+const MINIMUM_GAS_FEE_UPDATED_SIG: &str =
+    "0xd94acb5ad15518599bd85d7d924fec1a562ee7c74e43a474c6dc57451b3d2e78";
+
+const OWNERSHIP_TRANSFERRED_SIG: &str =
+    "0x8be0079c531659141344cd1fd0a4f28419497f9722a3daafe3b4186f6b6457e0";
+
+const UPGRADED_SIG: &str = "0xbc7cd75a20ee27fd9adebab32041f755214dbc6bffa90cc0225b39da2e5c2d3b";
+
 // TODO: Check how to rework this get_store_data.
 // We can have an event only, and no associated request (ex: collection deployed).
 
@@ -56,6 +138,8 @@ pub fn get_store_data(log: Log) -> Result<(Option<Request>, Option<Event>, Optio
         block_number: log.block_number.unwrap().try_into().unwrap(),
         tx_hash: format!("{:#x}", log.transaction_hash.unwrap()),
         price: None,
+        finality: None,
+        log_index: log.log_index.map(|i| i.as_u64()),
     };
 
     // TODO: not a fan of the mut here and for event, but as the type of data can change,
@@ -71,7 +155,7 @@ pub fn get_store_data(log: Log) -> Result<(Option<Request>, Option<Event>, Optio
             event.block_timestamp = data.block_timestamp.try_into().unwrap();
 
             request = request_from_log_data(&event.label, data.req_content)?;
-            // TODO: burn txs.
+            // Tokens escrowed on L1 can't be burnt by the indexer.
             tx = None;
         }
         WITHDRAW_REQUEST_COMPLETED_SIG => {
@@ -79,28 +163,41 @@ pub fn get_store_data(log: Log) -> Result<(Option<Request>, Option<Event>, Optio
             event.label = EventLabel::WithdrawCompletedL1;
             event.block_timestamp = data.block_timestamp.try_into().unwrap();
 
-            let h: u128 = data.req_content[0]
-                .try_into()
-                .expect("Can't convert header to u128");
-            let is_withdraw_auto = h & REQUEST_HEADER_WITHDRAW_AUTO == REQUEST_HEADER_WITHDRAW_AUTO;
-
             request = request_from_log_data(&event.label, data.req_content)?;
 
-            if event.label == EventLabel::WithdrawCompletedL1 && is_withdraw_auto {
+            if request.decoded()?.header.withdraw_auto {
                 tx = Some(CrossChainTx {
                     chain: BridgeChain::Ethereum,
                     kind: CrossChainTxKind::WithdrawAuto,
                     req_hash: request.hash.clone(),
                     req_content: request.content.clone(),
                     tx_hash: event.tx_hash.clone(),
+                    // The withdraw is already done on L1.
+                    status: CrossChainTxStatus::Confirmed,
+                    ..Default::default()
                 });
             } else {
                 tx = None;
             }
         }
-        COLLECTION_DEPOYED_FROM_L2_SIG => {
-            // TODO: return event only.
-            log::debug!("Collection deployed from L2 {:?}", log);
+        CANCEL_REQUEST_STARTED_SIG => {
+            let data = <CancelRequestStarted as EthLogDecode>::decode_log(&log.clone().into())?;
+            event.label = EventLabel::CancelStartedL1;
+            event.block_timestamp = data.block_timestamp.try_into().unwrap();
+
+            // The request content is not emitted with cancellation events,
+            // the request is expected to be already indexed from the deposit.
+            return Ok((None, Some(event), None));
+        }
+        CANCEL_REQUEST_COMPLETED_SIG => {
+            let data = <CancelRequestCompleted as EthLogDecode>::decode_log(&log.clone().into())?;
+            event.label = EventLabel::CancelCompletedL1;
+            event.block_timestamp = data.block_timestamp.try_into().unwrap();
+
+            return Ok((None, Some(event), None));
+        }
+        COLLECTION_DEPOYED_FROM_L2_SIG | L1L2_COLLECTION_MAPPING_UPDATED_SIG => {
+            // Handled by `get_collection_data`.
             return Ok((None, None, None));
         }
         _ => {
@@ -114,46 +211,220 @@ pub fn get_store_data(log: Log) -> Result<(Option<Request>, Option<Event>, Optio
     Ok((Some(request), Some(event), tx))
 }
 
-/// From the raw buffer in the Log data, parse the request fields
-/// required to build `Request`.
-fn request_from_log_data(event_label: &EventLabel, data: Vec<U256>) -> Result<Request> {
-    if data.len() < 6 {
-        return Err(anyhow!(
-            "Request can't be extracted from log data: {:?}",
-            data
-        ));
+/// Returns the burn transaction to be sent on starknet, if the request
+/// is withdrawn on L1 and asks the tokens escrowed on L2 to be burnt.
+///
+/// A request which content can't be decoded is logged and skipped.
+pub fn get_xchain_tx_burn(request: &Request, event: &Event) -> Result<Option<CrossChainTx>> {
+    if event.label != EventLabel::WithdrawCompletedL1 {
+        return Ok(None);
     }
 
-    let hex_strings: Vec<String> = data.iter().map(|u256| format!("{:#x}", u256)).collect();
-    let content_array: Vec<Value> = hex_strings.iter().map(|s| json!(s)).collect();
-    let content = serde_json::to_string(&content_array)?;
-
-    let req = match event_label {
-        EventLabel::DepositInitiatedL1 => Request {
-            hash: format!("0x{:032x}{:032x}", data[2], data[1]),
-            chain_src: BridgeChain::Ethereum,
-            collection_src: normalize_hex(hex_strings[3].clone().as_str())?,
-            collection_dst: normalize_hex(hex_strings[4].clone().as_str())?,
-            from: normalize_hex(hex_strings[5].clone().as_str())?,
-            to: normalize_hex(hex_strings[6].clone().as_str())?,
-            content,
-        },
-        EventLabel::WithdrawCompletedL1 => Request {
-            hash: format!("0x{:032x}{:032x}", data[2], data[1]),
-            chain_src: BridgeChain::Starknet,
-            collection_src: normalize_hex(hex_strings[4].clone().as_str())?,
-            collection_dst: normalize_hex(hex_strings[3].clone().as_str())?,
-            from: normalize_hex(hex_strings[6].clone().as_str())?,
-            to: normalize_hex(hex_strings[5].clone().as_str())?,
-            content,
-        },
-        _ => {
-            return Err(anyhow!(
-                "EventLabel {:?} not supposed to generate a request",
-                event_label
-            ))
+    match request.decoded() {
+        Ok(d) if d.header.burn_auto => (),
+        Ok(_) => return Ok(None),
+        Err(e) => {
+            log::warn!("Request {} can't be decoded: {:?}", request.hash, e);
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(CrossChainTx {
+        chain: BridgeChain::Starknet,
+        kind: CrossChainTxKind::BurnAuto,
+        req_hash: request.hash.clone(),
+        req_content: request.content.clone(),
+        tx_hash: String::from(""),
+        ..Default::default()
+    }))
+}
+
+/// Returns the collection registered by the log entry, if any.
+///
+/// The mapping updates don't emit the block timestamp, in this case
+/// the timestamp is 0 and must be set by the caller.
+pub fn get_collection_data(log: &Log) -> Result<Option<CollectionContract>> {
+    if log.topics.is_empty() {
+        return Ok(None);
+    }
+
+    let sig = format!("{:#64x}", log.topics[0]);
+
+    let collection = match sig.as_str() {
+        COLLECTION_DEPOYED_FROM_L2_SIG => {
+            let data = <CollectionDeployedFromL2 as EthLogDecode>::decode_log(&log.clone().into())?;
+            CollectionContract {
+                chain_src: BridgeChain::Starknet,
+                address_src: normalize_hex(&format!("{:#x}", data.l2_address))?,
+                address_dst: normalize_hex(&format!("{:#x}", data.l1_address))?,
+                req_hash: Some(format!("0x{:064x}", data.req_hash)),
+                timestamp: data.block_timestamp.try_into().unwrap(),
+                chain: BridgeChain::Ethereum,
+                block_number: log.block_number.unwrap().try_into().unwrap(),
+            }
+        }
+        L1L2_COLLECTION_MAPPING_UPDATED_SIG => {
+            let data =
+                <L1L2CollectionMappingUpdated as EthLogDecode>::decode_log(&log.clone().into())?;
+            CollectionContract {
+                chain_src: BridgeChain::Ethereum,
+                address_src: normalize_hex(&format!("{:#x}", data.collection_l1))?,
+                address_dst: normalize_hex(&format!("{:#x}", data.collection_l2))?,
+                req_hash: None,
+                timestamp: 0,
+                chain: BridgeChain::Ethereum,
+                block_number: log.block_number.unwrap().try_into().unwrap(),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(collection))
+}
+
+/// Returns the administration event from the log entry, if any.
+///
+/// The block timestamp is not emitted with those events, it must be set by the caller.
+pub fn get_admin_data(log: &Log) -> Result<Option<AdminEvent>> {
+    if log.topics.is_empty() {
+        return Ok(None);
+    }
+
+    let sig = format!("{:#64x}", log.topics[0]);
+    let raw_log = log.clone().into();
+
+    let kind = match sig.as_str() {
+        WHITE_LIST_UPDATED_SIG => {
+            let data = <WhiteListUpdated as EthLogDecode>::decode_log(&raw_log)?;
+            AdminEventKind::WhiteListEnabled {
+                enabled: data.enable,
+            }
+        }
+        COLLECTION_WHITE_LIST_UPDATED_SIG => {
+            let data = <CollectionWhiteListUpdated as EthLogDecode>::decode_log(&raw_log)?;
+            AdminEventKind::CollectionWhiteListUpdated {
+                collection: normalize_hex(&format!("{:#x}", data.collection))?,
+                enabled: data.enable,
+            }
+        }
+        MINIMUM_GAS_FEE_UPDATED_SIG => {
+            let data = <MinimumGasFeeUpdated as EthLogDecode>::decode_log(&raw_log)?;
+            AdminEventKind::MinimumGasFeeUpdated {
+                gas_fee: data.gas_fee.to_string(),
+            }
+        }
+        OWNERSHIP_TRANSFERRED_SIG => {
+            let data = <OwnershipTransferred as EthLogDecode>::decode_log(&raw_log)?;
+            AdminEventKind::OwnershipTransferred {
+                previous_owner: normalize_hex(&format!("{:#x}", data.previous_owner))?,
+                new_owner: normalize_hex(&format!("{:#x}", data.new_owner))?,
+            }
         }
+        UPGRADED_SIG => {
+            let data = <Upgraded as EthLogDecode>::decode_log(&raw_log)?;
+            AdminEventKind::Upgraded {
+                implementation: normalize_hex(&format!("{:#x}", data.implementation))?,
+            }
+        }
+        _ => return Ok(None),
     };
 
-    Ok(req)
+    Ok(Some(AdminEvent {
+        chain: BridgeChain::Ethereum,
+        kind,
+        block_number: log.block_number.unwrap().try_into().unwrap(),
+        event_index: log.log_index.unwrap_or_default().try_into().unwrap(),
+        block_timestamp: 0,
+        tx_hash: format!("{:#x}", log.transaction_hash.unwrap()),
+    }))
+}
+
+/// From the raw buffer in the Log data, decodes the request.
+fn request_from_log_data(event_label: &EventLabel, data: Vec<U256>) -> Result<Request> {
+    let content: Vec<String> = data.iter().map(|u256| format!("{:#x}", u256)).collect();
+
+    Request::from_content(event_label, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collection_signatures() {
+        assert_eq!(
+            format!("{:#64x}", CollectionDeployedFromL2::signature()),
+            COLLECTION_DEPOYED_FROM_L2_SIG
+        );
+        assert_eq!(
+            format!("{:#64x}", L1L2CollectionMappingUpdated::signature()),
+            L1L2_COLLECTION_MAPPING_UPDATED_SIG
+        );
+    }
+
+    #[test]
+    fn test_admin_signatures() {
+        assert_eq!(
+            format!("{:#64x}", WhiteListUpdated::signature()),
+            WHITE_LIST_UPDATED_SIG
+        );
+        assert_eq!(
+            format!("{:#64x}", CollectionWhiteListUpdated::signature()),
+            COLLECTION_WHITE_LIST_UPDATED_SIG
+        );
+        assert_eq!(
+            format!("{:#64x}", MinimumGasFeeUpdated::signature()),
+            MINIMUM_GAS_FEE_UPDATED_SIG
+        );
+        assert_eq!(
+            format!("{:#64x}", OwnershipTransferred::signature()),
+            OWNERSHIP_TRANSFERRED_SIG
+        );
+        assert_eq!(format!("{:#64x}", Upgraded::signature()), UPGRADED_SIG);
+    }
+
+    #[test]
+    fn test_cancel_signatures() {
+        assert_eq!(
+            format!("{:#64x}", CancelRequestStarted::signature()),
+            CANCEL_REQUEST_STARTED_SIG
+        );
+        assert_eq!(
+            format!("{:#64x}", CancelRequestCompleted::signature()),
+            CANCEL_REQUEST_COMPLETED_SIG
+        );
+    }
+
+    #[test]
+    fn test_xchain_tx_burn() {
+        // ERC721 header with burn auto, two tokens.
+        let content: Vec<String> = [
+            "0x10101", "0x2", "0x3", "0x4", "0x5", "0x6", "0x7", "0x0", "0x0", "0x0", "0x0", "0x0",
+            "0x0", "0x0", "0x0", "0x0", "0x2", "0x1", "0x0", "0x2", "0x0", "0x0", "0x0", "0x0",
+        ]
+        .iter()
+        .map(|f| f.to_string())
+        .collect();
+
+        let label = EventLabel::WithdrawCompletedL1;
+        let request = Request::from_content(&label, &content).unwrap();
+        let mut event = Event {
+            req_hash: request.hash.clone(),
+            label,
+            block_timestamp: 0,
+            block_number: 0,
+            tx_hash: String::from("0x1"),
+            price: None,
+            finality: None,
+            log_index: None,
+        };
+
+        let tx = get_xchain_tx_burn(&request, &event).unwrap().unwrap();
+        assert_eq!(tx.chain, BridgeChain::Starknet);
+        assert_eq!(tx.kind, CrossChainTxKind::BurnAuto);
+        assert_eq!(tx.tx_hash, "");
+
+        event.label = EventLabel::DepositInitiatedL1;
+        assert!(get_xchain_tx_burn(&request, &event).unwrap().is_none());
+    }
 }
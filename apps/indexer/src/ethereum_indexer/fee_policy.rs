@@ -0,0 +1,86 @@
+//! Fee policy of the relayer on ethereum.
+//!
+//! A withdraw auto transaction is only sent if the fee paid by the user, which is the
+//! configured request fee, covers the estimated cost of the transaction.
+//! Otherwise, the transaction is deferred until the gas is cheaper, and marked as
+//! unprofitable if it stays too expensive for too long.
+use ethers::types::U256;
+
+use crate::storage::{CrossChainTx, CrossChainTxStatus, RelayDecision};
+
+/// Interval in seconds before a deferred transaction is evaluated again.
+pub const DEFER_INTERVAL: u64 = 300;
+
+/// Decides if the transaction is relayed, and records the decision on the transaction.
+///
+/// Without known fee, the transaction is always relayed. A transaction is deferred
+/// at most `max_defer` seconds before being unprofitable.
+pub fn evaluate(
+    tx: &mut CrossChainTx,
+    estimated_fee: U256,
+    paid_fee: Option<U256>,
+    max_defer: u64,
+    now: u64,
+) -> RelayDecision {
+    let decision = match paid_fee {
+        Some(paid) if estimated_fee > paid => {
+            let deferred_since = tx.deferred_since.unwrap_or(now);
+            if now.saturating_sub(deferred_since) >= max_defer {
+                RelayDecision::Unprofitable
+            } else {
+                RelayDecision::Defer
+            }
+        }
+        _ => RelayDecision::Relay,
+    };
+
+    tx.relay_decision = Some(decision);
+    tx.estimated_fee = Some(estimated_fee.to_string());
+    tx.paid_fee = paid_fee.map(|p| p.to_string());
+
+    match decision {
+        RelayDecision::Relay => (),
+        RelayDecision::Defer => {
+            tx.deferred_since.get_or_insert(now);
+            tx.next_attempt_at = now + DEFER_INTERVAL;
+        }
+        RelayDecision::Unprofitable => {
+            tx.status = CrossChainTxStatus::Unprofitable;
+        }
+    };
+
+    decision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let mut tx = CrossChainTx::default();
+
+        let d = evaluate(&mut tx, 100.into(), None, 60, 10);
+        assert_eq!(d, RelayDecision::Relay);
+        assert_eq!(tx.paid_fee, None);
+
+        let d = evaluate(&mut tx, 100.into(), Some(100.into()), 60, 10);
+        assert_eq!(d, RelayDecision::Relay);
+
+        let d = evaluate(&mut tx, 150.into(), Some(100.into()), 600, 10);
+        assert_eq!(d, RelayDecision::Defer);
+        assert_eq!(tx.deferred_since, Some(10));
+        assert_eq!(tx.next_attempt_at, 10 + DEFER_INTERVAL);
+        assert_eq!(tx.estimated_fee, Some(String::from("150")));
+        assert!(!tx.is_due(10));
+
+        let d = evaluate(&mut tx, 150.into(), Some(100.into()), 600, 400);
+        assert_eq!(d, RelayDecision::Defer);
+        assert_eq!(tx.deferred_since, Some(10));
+
+        let d = evaluate(&mut tx, 150.into(), Some(100.into()), 600, 610);
+        assert_eq!(d, RelayDecision::Unprofitable);
+        assert_eq!(tx.status, CrossChainTxStatus::Unprofitable);
+        assert!(!tx.is_due(u64::MAX));
+    }
+}
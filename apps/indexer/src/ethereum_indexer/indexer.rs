@@ -1,10 +1,17 @@
-use super::client::EthereumClient;
+use super::client::{self, EthereumClient};
 use super::events;
+use super::fee_policy;
+use super::reorg;
 use crate::config::{ChainConfig, XchainTxConfig};
 use crate::price::moralis::MoralisPrice;
 use crate::storage::{
-    store::{BlockStore, CrossChainTxStore, EventStore, PendingWithdrawStore, RequestStore},
-    BlockIndex, BridgeChain, CrossChainTxKind, Event, EventLabel, EventPrice,
+    status,
+    store::{
+        AdminEventStore, BlockStore, CollectionStore, CrossChainTxStore, EventStore,
+        PendingWithdrawStore, RequestStore, TransactionStore,
+    },
+    AdminEvent, BlockIndex, BridgeChain, CollectionContract, CrossChainTx, CrossChainTxKind,
+    CrossChainTxStatus, Event, EventLabel, EventPrice, PendingWithdraw, RelayDecision, Request,
 };
 use crate::utils;
 use crate::ChainsBlocks;
@@ -15,9 +22,26 @@ use std::sync::Arc;
 use tokio::sync::RwLock as AsyncRwLock;
 use tokio::time::{self, Duration};
 
-///
+// Time in seconds after which a submitted transaction without receipt is replaced.
+const STUCK_TX_TIMEOUT: u64 = 180;
+
+/// Data extracted from a log, to be written in the block transaction.
+enum LogData {
+    Admin(AdminEvent),
+    Collection(CollectionContract),
+    Request(Box<(Request, Event, Option<CrossChainTx>)>),
+    // Events not carrying the request content, like cancellations.
+    Event(Event),
+}
+
 pub struct EthereumIndexer<
-    T: RequestStore + EventStore + BlockStore + CrossChainTxStore + PendingWithdrawStore,
+    T: RequestStore
+        + EventStore
+        + BlockStore
+        + CrossChainTxStore
+        + PendingWithdrawStore
+        + CollectionStore
+        + AdminEventStore,
 > {
     client: EthereumClient,
     config: ChainConfig,
@@ -25,21 +49,29 @@ pub struct EthereumIndexer<
     chains_blocks: Arc<AsyncRwLock<ChainsBlocks>>,
     xchain_txor_config: XchainTxConfig,
     pricer: MoralisPrice,
+    sn_bridge_address: String,
 }
 
 impl<T> EthereumIndexer<T>
 where
-    T: RequestStore + EventStore + BlockStore + CrossChainTxStore + PendingWithdrawStore,
+    T: RequestStore
+        + EventStore
+        + BlockStore
+        + CrossChainTxStore
+        + PendingWithdrawStore
+        + CollectionStore
+        + AdminEventStore
+        + TransactionStore,
 {
-    ///
     pub async fn new(
         config: ChainConfig,
         store: Arc<T>,
         chains_blocks: Arc<AsyncRwLock<ChainsBlocks>>,
+        sn_bridge_address: String,
         xchain_txor_config: XchainTxConfig,
     ) -> Result<EthereumIndexer<T>> {
         let client = EthereumClient::new(config.clone()).await?;
-        /// TODO: should we add moralis api key to configuration file?
+        // TODO: should we add moralis api key to configuration file?
         let pricer = MoralisPrice::new(None);
         Ok(EthereumIndexer {
             client,
@@ -48,12 +80,13 @@ where
             chains_blocks,
             xchain_txor_config,
             pricer,
+            sn_bridge_address,
         })
     }
 
-    ///
     pub async fn start(&self) -> Result<()> {
-        let (mut from, _, _) = self.get_block_range_info().await?;
+        let (from, _, _) = self.get_block_range_info().await?;
+        let mut from = self.resume_from(from).await?;
 
         let mut need_cool_down = false;
 
@@ -68,7 +101,7 @@ where
 
             time::sleep(Duration::from_secs(fetch_interval)).await;
 
-            let to = match self.client.get_block_number().await {
+            let head = match self.client.get_block_number().await {
                 Ok(v) => v,
                 Err(e) => {
                     log::error!("Failed to retrieve block number: {:?}", e);
@@ -77,34 +110,53 @@ where
                 }
             };
 
-            if from >= to {
+            // Only blocks with enough confirmations are indexed.
+            let to = head.saturating_sub(self.config.confirmation_depth);
+
+            match self.detect_reorg().await {
+                Ok(Some(rollback_from)) => {
+                    if let Err(e) = self.rollback(rollback_from).await {
+                        log::error!("Error rolling back from block {}: {:?}", rollback_from, e);
+                        need_cool_down = true;
+                        continue;
+                    }
+                    from = from.min(rollback_from);
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    log::error!("Error detecting reorg: {:?}", e);
+                    need_cool_down = true;
+                    continue;
+                }
+            };
+
+            if from > to {
                 log::info!("Nothing to fetch (from={} to={})", from, to);
                 continue;
             }
 
-            let blocks_logs = match self.client.fetch_logs(from, to).await {
-                Ok(bl) => bl,
+            // If any block has an error, the range is fetched again
+            // on the next poll, from the first slice not yet indexed.
+            let next = match self.index_range(from, to).await {
+                Ok(n) => n,
                 Err(e) => {
-                    log::error!("Error fetching logs: {:?}", e);
+                    log::error!("Error indexing blocks {} - {}: {:?}", from, to, e);
                     need_cool_down = true;
                     continue;
                 }
             };
 
-            // log::debug!("blocks logs: {:?}", blocks_logs);
+            // All the blocks up to `to` are now processed without gap.
+            if let Err(e) = self.store.set_cursor(BridgeChain::Ethereum, to).await {
+                log::warn!("Error saving cursor at block {}: {:?}", to, e);
+            }
 
-            for (block_number, logs) in blocks_logs {
-                match self.process_logs(block_number, logs).await {
-                    Ok(_) => (),
-                    Err(e) => {
-                        log::error!(
-                            "Error processing logs for block {:?}\n{:?}",
-                            block_number,
-                            e
-                        );
-                        need_cool_down = true;
-                    }
-                };
+            // The last block of the range is always indexed with its hash,
+            // to be able to detect a reorg on the next poll.
+            if let Err(e) = self.process_head(to).await {
+                log::error!("Error processing block {}: {:?}", to, e);
+                need_cool_down = true;
+                continue;
             }
 
             match self.xchain_txs_send().await {
@@ -112,89 +164,289 @@ where
                 Err(e) => log::warn!("Error sending xchain_txs {:?}", e),
             };
 
-            //
-            // Check for pending withdraw
-            match self.process_pending_withdraws(to).await {
-                Ok(_) => (),
-                Err(e) => log::warn!("Error processing pending transactions {:?}", e),
-            };
+            from = next;
+        }
+    }
 
-            //
-            // The block range was fetched and processed.
-            // If any block has an error, an other instance of the indexer
-            // must be restarted on a the specific range.
-            from = to;
+    /// Returns the block to start indexing from, resuming after the
+    /// persisted cursor if it is beyond `from_block`.
+    async fn resume_from(&self, from_block: u64) -> Result<u64> {
+        match self.store.cursor(BridgeChain::Ethereum).await? {
+            Some(c) if c.block_number >= from_block => {
+                log::info!("Ethereum indexing resumed after block {}", c.block_number);
+                self.chains_blocks.write().await.eth = c.block_number;
+                Ok(c.block_number + 1)
+            }
+            _ => Ok(from_block),
+        }
+    }
+
+    /// Indexes the blocks from `from_block` to `to_block` (included), one slice at a time.
+    /// Each slice is processed and checkpointed as an indexed range before the next
+    /// one is fetched, and the blocks already indexed are skipped without fetching them.
+    ///
+    /// Returns the next block to be indexed.
+    async fn index_range(&self, from_block: u64, to_block: u64) -> Result<u64> {
+        let mut from = from_block;
+
+        while from <= to_block {
+            if let Some(r) = self
+                .store
+                .indexed_range(BridgeChain::Ethereum, from)
+                .await?
+            {
+                log::debug!("Ethereum blocks {} - {} already indexed", from, r.to_block);
+                from = r.to_block + 1;
+                continue;
+            }
+
+            let (blocks_logs, last_block) = self.client.fetch_logs(from, to_block).await?;
+
+            for (block_number, logs) in blocks_logs {
+                if let Err(e) = self.process_logs(block_number, logs).await {
+                    anyhow::bail!("Error processing logs for block {}: {:?}", block_number, e);
+                }
+            }
+
+            self.store
+                .insert_indexed_range(BridgeChain::Ethereum, from, last_block)
+                .await?;
 
             let mut cbs = self.chains_blocks.write().await;
-            cbs.eth = from;
+            cbs.eth = cbs.eth.max(last_block);
+
+            from = last_block + 1;
         }
+
+        // Also covers the blocks skipped as already indexed.
+        let mut cbs = self.chains_blocks.write().await;
+        cbs.eth = cbs.eth.max(to_block);
+
+        Ok(from)
     }
 
-    ///
+    /// Sends the pending cross chain transactions, and follows the submitted ones.
+    /// An error on a transaction doesn't prevent the others from being processed.
     async fn xchain_txs_send(&self) -> Result<()> {
         if !self.xchain_txor_config.enabled {
             log::debug!("xchain_txor is disabled in config, skipping");
             return Ok(());
         }
 
-        let cbs = self.chains_blocks.read().await;
-        if cbs.sn < self.xchain_txor_config.sn_min_block
-            || cbs.eth < self.xchain_txor_config.eth_min_block
+        {
+            let cbs = self.chains_blocks.read().await;
+            if cbs.sn < self.xchain_txor_config.sn_min_block
+                || cbs.eth < self.xchain_txor_config.eth_min_block
+            {
+                log::debug!(
+                    "xchain_txor skipped due to unmet blocks requirements {:?}",
+                    cbs
+                );
+                return Ok(());
+            }
+        }
+
+        let txs = self.store.pending_xtxs(BridgeChain::Ethereum).await?;
+        log::debug!("Verifying xchain_txs for ethereum node [{}]", txs.len());
+
+        for mut tx in txs {
+            let now = utils::utc_now_seconds();
+
+            let res = match tx.kind {
+                // Burn transactions are only sent on starknet.
+                CrossChainTxKind::BurnAuto => {
+                    log::warn!("Unexpected burn auto tx on ethereum {:?}", tx.req_hash);
+                    continue;
+                }
+                CrossChainTxKind::WithdrawAuto => match tx.status {
+                    CrossChainTxStatus::Submitted => self.xchain_tx_check(&mut tx, now).await,
+                    _ if tx.is_due(now) => self.xchain_tx_withdraw(&mut tx, now).await,
+                    _ => continue,
+                },
+            };
+
+            if let Err(e) = res {
+                log::warn!("Error relaying xchain_tx {}: {:?}", tx.req_hash, e);
+
+                // A submitted transaction may still be mined, errors only come from the node.
+                if tx.status != CrossChainTxStatus::Submitted {
+                    self.client.reset_nonce().await;
+                    tx.failed(format!("{:?}", e), now);
+                }
+            }
+
+            if tx.status == CrossChainTxStatus::Abandoned {
+                log::error!(
+                    "xchain_tx {} abandoned after {} attempts: {:?}",
+                    tx.req_hash,
+                    tx.attempts,
+                    tx.last_error
+                );
+
+                // The nonce of an abandoned transaction may never be mined, the next
+                // nonce is fetched again to not send the next transactions behind a gap.
+                if tx.nonce.is_some() {
+                    self.client.reset_nonce().await;
+                }
+            }
+
+            if let Err(e) = self.store.update_tx(tx).await {
+                log::error!("Error saving xchain_tx: {:?}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends the withdraw transaction, unless the request is already withdrawn on L1
+    /// or the fee policy defers it.
+    async fn xchain_tx_withdraw(&self, tx: &mut CrossChainTx, now: u64) -> Result<()> {
+        let req_events: Vec<Event> = self.store.events_by_request(&tx.req_hash).await?;
+        if req_events
+            .iter()
+            .any(|e| e.label == EventLabel::WithdrawCompletedL1)
         {
             log::debug!(
-                "xchain_txor skipped due to unmet blocks requirements {:?}",
-                cbs
+                "Request already withdrawn on L1 {:?}, skipping",
+                tx.req_hash
             );
+            tx.confirmed();
             return Ok(());
         }
 
-        let txs = self.store.pending_xtxs(BridgeChain::Ethereum).await?;
-        log::debug!("Verifying xchain_txs for ethereum node [{}]", txs.len());
+        let gas_price = self.client.get_gas_price().await?;
+        let gas = self
+            .client
+            .estimate_withdraw_gas(withdraw_args(tx)?)
+            .await?;
+        let paid_fee = match &self.xchain_txor_config.request_fee {
+            Some(fee) => Some(U256::from_dec_str(fee)?),
+            None => None,
+        };
 
-        let starklane = self.client.get_bridge_sender();
+        match fee_policy::evaluate(
+            tx,
+            gas * gas_price,
+            paid_fee,
+            self.xchain_txor_config.max_defer,
+            now,
+        ) {
+            RelayDecision::Relay => (),
+            RelayDecision::Defer => {
+                log::info!(
+                    "xchain_tx {} deferred, fee {:?} paid for {:?}",
+                    tx.req_hash,
+                    tx.paid_fee,
+                    tx.estimated_fee
+                );
+                return Ok(());
+            }
+            RelayDecision::Unprofitable => {
+                log::warn!(
+                    "xchain_tx {} unprofitable, fee {:?} paid for {:?}",
+                    tx.req_hash,
+                    tx.paid_fee,
+                    tx.estimated_fee
+                );
+                return Ok(());
+            }
+        };
 
-        for tx in txs {
-            let felts_strs: Vec<String> = serde_json::from_str(&tx.req_content)
-                .expect("Fail parsing request content for xchain_tx");
-
-            let u256s: Vec<U256> = felts_strs
-                .into_iter()
-                .map(|felt_str| {
-                    U256::from_str_radix(&felt_str, 16)
-                        .expect("Invalid U256 format (expecting hex str)")
-                })
-                .collect();
-
-            match tx.kind {
-                CrossChainTxKind::WithdrawAuto => {
-                    // If the withdraw event is already registered on L1, tx sending can be skipped.
-                    let req_events: Vec<Event> = self.store.events_by_request(&tx.req_hash).await?;
-                    if req_events
-                        .iter()
-                        .any(|e| e.label == EventLabel::WithdrawCompletedL1)
-                    {
-                        log::debug!(
-                            "Request already withdrawn on L1 {:?}, skipping",
-                            tx.req_hash
-                        );
-                        continue;
-                    }
+        let nonce = self.client.next_nonce().await?;
+
+        self.xchain_tx_submit(tx, nonce, gas_price, now).await
+    }
 
-                    let receipt = starklane.withdraw_tokens(u256s).send().await?.await?;
-                    if let Some(r) = receipt {
-                        self.store
-                            .set_tx_as_sent(tx.req_hash, format!("{:#064x}", r.transaction_hash))
-                            .await?;
-                    };
+    /// Checks the receipt of a submitted transaction. A transaction without receipt
+    /// after `STUCK_TX_TIMEOUT` is replaced with a higher gas price, unless its nonce
+    /// is already mined (by a previous replacement) or the request is withdrawn on L1.
+    async fn xchain_tx_check(&self, tx: &mut CrossChainTx, now: u64) -> Result<()> {
+        match self.client.tx_receipt_status(&tx.tx_hash).await? {
+            Some(true) => tx.confirmed(),
+            Some(false) => tx.failed(format!("Transaction {} reverted", tx.tx_hash), now),
+            None => {
+                if now < tx.submitted_at.unwrap_or(0) + STUCK_TX_TIMEOUT || !tx.stuck() {
+                    return Ok(());
                 }
-                CrossChainTxKind::BurnAuto => todo!(),
-            };
+
+                if self.xchain_tx_mined(tx).await? {
+                    log::info!(
+                        "xchain_tx {} already mined for request {}",
+                        tx.tx_hash,
+                        tx.req_hash
+                    );
+                    tx.confirmed();
+                    return Ok(());
+                }
+
+                let previous = match &tx.gas_price {
+                    Some(p) => U256::from_dec_str(p)?,
+                    None => U256::zero(),
+                };
+                let gas_price =
+                    client::bump_gas_price(previous, self.client.get_gas_price().await?);
+
+                // The same nonce is used to replace the stuck transaction.
+                let nonce = match tx.nonce {
+                    Some(n) => U256::from(n),
+                    None => self.client.next_nonce().await?,
+                };
+
+                log::info!(
+                    "Replacing stuck xchain_tx {} (gas price {})",
+                    tx.tx_hash,
+                    gas_price
+                );
+
+                self.xchain_tx_submit(tx, nonce, gas_price, now).await?;
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Returns true if the nonce of the transaction is already mined,
+    /// or if the request is already withdrawn on L1.
+    async fn xchain_tx_mined(&self, tx: &CrossChainTx) -> Result<bool> {
+        if let Some(nonce) = tx.nonce {
+            if self.client.mined_nonce().await? > U256::from(nonce) {
+                return Ok(true);
+            }
         }
 
+        Ok(self
+            .store
+            .events_by_request(&tx.req_hash)
+            .await?
+            .iter()
+            .any(|e| e.label == EventLabel::WithdrawCompletedL1))
+    }
+
+    async fn xchain_tx_submit(
+        &self,
+        tx: &mut CrossChainTx,
+        nonce: U256,
+        gas_price: U256,
+        now: u64,
+    ) -> Result<()> {
+        let starklane = self.client.get_bridge_sender();
+        let call = starklane
+            .withdraw_tokens(withdraw_args(tx)?)
+            .legacy()
+            .nonce(nonce)
+            .gas_price(gas_price);
+        let pending = call.send().await?;
+
+        tx.submitted(
+            format!("{:#064x}", pending.tx_hash()),
+            Some(nonce.as_u64()),
+            Some(gas_price.to_string()),
+            now,
+        );
+
         Ok(())
     }
 
-    ///
     async fn get_block_range_info(&self) -> Result<(u64, u64, bool)> {
         let from_u64: u64 =
             match BlockNumber::from_str(&self.config.from_block).expect("Invalid from_block") {
@@ -237,12 +489,81 @@ where
             block_number
         );
 
-        // TODO: start a database transaction/session.
+        let (block_hash, parent_hash) = match self.client.get_block_hashes(block_number).await? {
+            Some(hashes) => hashes,
+            None => anyhow::bail!("Block {} not found", block_number),
+        };
+
+        // The logs may have been fetched before a reorg of this block.
+        if let Some(log_block_hash) = logs.first().and_then(|l| l.block_hash) {
+            if format!("{:#064x}", log_block_hash) != block_hash {
+                anyhow::bail!("Block {} was reorged during logs fetching", block_number);
+            }
+        }
+
+        let block_idx = BlockIndex {
+            chain: BridgeChain::Ethereum,
+            block_number,
+            insert_timestamp: utils::utc_now_seconds(),
+            block_hash: Some(block_hash),
+            parent_hash: Some(parent_hash),
+        };
+
+        let data = self.prepare_logs(block_number, logs).await?;
+
+        // All the writes of the block are done in one transaction, to not
+        // have partially indexed blocks.
+        let store = self.store.begin_transaction().await?;
+
+        match self.store_logs(&store, data, block_idx).await {
+            Ok(()) => store.commit_transaction().await,
+            Err(e) => {
+                if let Err(abort_err) = store.abort_transaction().await {
+                    log::warn!("Failed to abort transaction: {:?}", abort_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Extracts the data to be written from the logs of the given block.
+    ///
+    /// All the RPC calls needed (block timestamp, event price) are done here,
+    /// to not be done while the block transaction is open.
+    async fn prepare_logs(&self, block_number: u64, logs: Vec<Log>) -> Result<Vec<LogData>> {
+        let mut data = vec![];
+        let mut block_timestamp: Option<u64> = None;
 
         for l in logs {
             let l_sig = l.topics[0];
 
-            match events::get_store_data(l)? {
+            if let Some(mut a) = events::get_admin_data(&l)? {
+                a.block_timestamp = self
+                    .block_timestamp(block_number, &mut block_timestamp)
+                    .await?;
+                data.push(LogData::Admin(a));
+                continue;
+            }
+
+            if let Some(mut c) = events::get_collection_data(&l)? {
+                if c.timestamp == 0 {
+                    c.timestamp = self
+                        .block_timestamp(block_number, &mut block_timestamp)
+                        .await?;
+                }
+                data.push(LogData::Collection(c));
+                continue;
+            }
+
+            let store_data = match events::get_store_data(l) {
+                Ok(d) => d,
+                Err(er) => {
+                    log::warn!("Log not processed {:?} -> {:?}", l_sig, er);
+                    continue;
+                }
+            };
+
+            match store_data {
                 (Some(r), Some(mut e), xchain_tx) => {
                     log::debug!("Request/Event/Tx\n{:?}\n{:?}\n{:?}", r, e, xchain_tx);
                     if e.label == EventLabel::DepositInitiatedL1 {
@@ -254,58 +575,220 @@ where
                             Err(e) => log::warn!("Failed to compute event price: {:?}", e),
                         }
                     }
-                    
+                    data.push(LogData::Request(Box::new((r, e, xchain_tx))));
+                }
+                // Events not carrying the request content, like cancellations.
+                (None, Some(e), _) => data.push(LogData::Event(e)),
+                // Maybe fine, like proxy upgrade / ownership, ...
+                _ => log::warn!("Event emitted by Starklane is not handled {:?}", l_sig),
+            };
+        }
+
+        Ok(data)
+    }
+
+    /// Returns the timestamp of the given block, fetched once per block.
+    async fn block_timestamp(&self, block_number: u64, cached: &mut Option<u64>) -> Result<u64> {
+        if let Some(t) = cached {
+            return Ok(*t);
+        }
+
+        let t = self.client.get_block_timestamp(block_number).await?;
+        *cached = Some(t);
+        Ok(t)
+    }
+
+    /// Writes the data extracted from the logs of the given block into `store`.
+    async fn store_logs(&self, store: &T, data: Vec<LogData>, block_idx: BlockIndex) -> Result<()> {
+        for d in data {
+            match d {
+                LogData::Admin(a) => {
+                    log::info!("Insert admin event: {:?}", &a);
+                    store.insert_admin_event(a).await?;
+                }
+                LogData::Collection(c) => {
+                    log::info!("Insert collection: {:?}", &c);
+                    store.insert_collection(c).await?;
+                }
+                LogData::Request(d) => {
+                    let (r, e, xchain_tx) = *d;
                     log::info!("Insert event: {:?}", &e);
-                    self.store.insert_event(e.clone()).await?;
+                    store.insert_event(e.clone()).await?;
+
+                    // The tokens escrowed on starknet are to be burnt once withdrawn on L1,
+                    // the transaction is queued until the bridge exposes the `burn_auto` entrypoint.
+                    if self.xchain_txor_config.burn_auto {
+                        if let Some(tx) = events::get_xchain_tx_burn(&r, &e)? {
+                            log::info!("Insert burn auto tx for request {}", tx.req_hash);
+                            store.insert_tx(tx).await?;
+                        }
+                    }
 
-                    if self.store.req_by_hash(&r.hash).await?.is_none() {
-                        self.store.insert_req(r).await?;
+                    // The request is created at its first event, which may be indexed
+                    // after the events of the other chain.
+                    match store.req_by_hash(&r.hash).await? {
+                        Some(req) if req.timestamp <= e.block_timestamp => (),
+                        _ => {
+                            store
+                                .insert_req(Request {
+                                    timestamp: e.block_timestamp,
+                                    ..r
+                                })
+                                .await?
+                        }
                     }
 
                     if let Some(tx) = xchain_tx {
-                        match tx.kind {
-                            CrossChainTxKind::WithdrawAuto => {
-                                // Force insert or update to ensure no more tx are fired.
-                                match self
-                                    .store
-                                    .tx_from_request_kind(
-                                        &tx.req_hash.clone(),
-                                        CrossChainTxKind::WithdrawAuto,
-                                    )
-                                    .await?
-                                {
-                                    Some(_) => {
-                                        self.store.set_tx_as_sent(tx.req_hash, tx.tx_hash).await?
-                                    }
-                                    None => self.store.insert_tx(tx).await?,
+                        if let CrossChainTxKind::WithdrawAuto = tx.kind {
+                            // Force insert or update to ensure no more tx are fired.
+                            match store
+                                .tx_from_request_kind(
+                                    &tx.req_hash.clone(),
+                                    CrossChainTxKind::WithdrawAuto,
+                                )
+                                .await?
+                            {
+                                Some(_) => {
+                                    store
+                                        .set_tx_as_sent(
+                                            tx.req_hash,
+                                            CrossChainTxKind::WithdrawAuto,
+                                            tx.tx_hash,
+                                        )
+                                        .await?
                                 }
+                                None => store.insert_tx(tx).await?,
                             }
-                            _ => (),
                         }
                     }
+                }
+                LogData::Event(e) => {
+                    log::info!("Insert event: {:?}", &e);
+                    store.insert_event(e).await?;
+                }
+            }
+        }
+
+        store.insert_block(block_idx).await
+    }
 
-                    // TODO: check for burn auto to send TX on ethereum
-                    //       and add them to the xchains store.
+    /// Writes the data derived from the state of the chain at `block_number`, once all
+    /// the blocks up to it are processed: the block with its hashes (if not already indexed),
+    /// the withdraws available on L1 and the transit errors.
+    ///
+    /// The RPC calls are done first, and all the writes in one transaction.
+    async fn process_head(&self, block_number: u64) -> Result<()> {
+        let block_idx = self.block_index(block_number).await?;
+        let available = self.available_withdraws(block_number).await?;
+        let errors = self.transit_errors().await?;
+
+        let store = self.store.begin_transaction().await?;
+
+        match self.store_head(&store, block_idx, available, errors).await {
+            Ok(()) => store.commit_transaction().await,
+            Err(e) => {
+                if let Err(abort_err) = store.abort_transaction().await {
+                    log::warn!("Failed to abort transaction: {:?}", abort_err);
                 }
-                // Maybe fine, like proxy upgrade / ownership, ...
-                _ => log::warn!("Event emitted by Starklane is not handled {:?}", l_sig),
-            };
+                Err(e)
+            }
+        }
+    }
+
+    /// Writes the data returned by the RPC calls of `process_head` into `store`.
+    async fn store_head(
+        &self,
+        store: &T,
+        block_idx: Option<BlockIndex>,
+        available: Vec<(PendingWithdraw, Event)>,
+        errors: Vec<Event>,
+    ) -> Result<()> {
+        if let Some(b) = block_idx {
+            store.insert_block(b).await?;
         }
 
-        let block_idx = BlockIndex {
+        for (pending, event) in available {
+            log::info!("Insert event: {:?}", &event);
+            store.insert_event(event).await?;
+            store.remove_pending_withdraw(pending).await?;
+        }
+
+        for event in errors {
+            log::warn!("Request {} in transit error", event.req_hash);
+            store.insert_event(event).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the given block with its hashes, if not already indexed.
+    async fn block_index(&self, block_number: u64) -> Result<Option<BlockIndex>> {
+        if self
+            .store
+            .block_by_number(BridgeChain::Ethereum, block_number)
+            .await?
+            .is_some()
+        {
+            return Ok(None);
+        }
+
+        let (block_hash, parent_hash) = match self.client.get_block_hashes(block_number).await? {
+            Some(hashes) => hashes,
+            None => anyhow::bail!("Block {} not found", block_number),
+        };
+
+        Ok(Some(BlockIndex {
             chain: BridgeChain::Ethereum,
             block_number,
             insert_timestamp: utils::utc_now_seconds(),
-        };
+            block_hash: Some(block_hash),
+            parent_hash: Some(parent_hash),
+        }))
+    }
 
-        self.store.insert_block(block_idx).await?;
-        // TODO: end the database transaction/session.
+    /// Compares the latest indexed blocks with the canonical chain.
+    ///
+    /// Returns the first orphaned block number if a reorg is detected.
+    async fn detect_reorg(&self) -> Result<Option<u64>> {
+        reorg::detect_reorg(self.store.as_ref(), |n| self.client.get_block_hashes(n)).await
+    }
 
-        Ok(())
+    /// Removes all the data written for the ethereum blocks starting at `from_block`,
+    /// in one transaction.
+    async fn rollback(&self, from_block: u64) -> Result<()> {
+        let store = self.store.begin_transaction().await?;
+
+        match reorg::rollback(
+            &store,
+            from_block,
+            &self.sn_bridge_address,
+            &self.config.bridge_address,
+        )
+        .await
+        {
+            Ok(()) => store.commit_transaction().await,
+            Err(e) => {
+                if let Err(abort_err) = store.abort_transaction().await {
+                    log::warn!("Failed to abort transaction: {:?}", abort_err);
+                }
+                Err(e)
+            }
+        }
     }
 
-    async fn process_pending_withdraws(&self, block_number: u64) -> Result<()> {
+    /// Returns the pending withdraws whose message is available on L1 at `block_number`,
+    /// with the `WithdrawAvailableL1` event to be inserted for each of them.
+    async fn available_withdraws(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<(PendingWithdraw, Event)>> {
+        let mut available = vec![];
+
         let pendings = self.store.get_pending_withdraws().await?;
+        if pendings.is_empty() {
+            return Ok(available);
+        }
+
         let timestamp = self.client.get_block_timestamp(block_number).await?;
         for pending in pendings {
             let status = self
@@ -313,22 +796,47 @@ where
                 .query_message_status(pending.message_hash)
                 .await?;
             if status != 0 {
-                if let Some(mut event) = self.store.event_by_tx(&pending.tx_hash).await? {
+                // One transaction may deposit several requests.
+                let deposit = self
+                    .store
+                    .events_by_request(&pending.req_hash)
+                    .await?
+                    .into_iter()
+                    .find(|e| e.tx_hash == pending.tx_hash);
+
+                if let Some(mut event) = deposit {
                     if event.label != EventLabel::WithdrawCompletedL1 {
                         event.block_timestamp = timestamp;
                         event.block_number = block_number;
                         event.label = EventLabel::WithdrawAvailableL1;
+                        event.finality = None;
+                        event.log_index = None;
                         // TODO: which transaction hash we should set?
                         event.tx_hash = "0x435553544f4d5f5452414e53414354494f4e".to_owned(); // CUSTOM_TRANSACTION
-                        
-                        log::info!("Insert event: {:?}", &event);
-                        self.store.insert_event(event).await?;
-                        self.store.remove_pending_withdraw(pending).await?;
+
+                        available.push((pending, event));
                     }
                 }
             }
         }
-        Ok(())
+        Ok(available)
+    }
+
+    /// Returns the transit error events for the L2->L1 requests whose message
+    /// is not available on L1 after `messaging_timeout` seconds.
+    async fn transit_errors(&self) -> Result<Vec<Event>> {
+        let timeout = match self.config.messaging_timeout {
+            Some(t) => t,
+            None => return Ok(vec![]),
+        };
+
+        status::transit_errors(
+            self.store.as_ref(),
+            BridgeChain::Starknet,
+            timeout,
+            utils::utc_now_seconds(),
+        )
+        .await
     }
 
     async fn compute_event_price(&self, e: &Event) -> Result<EventPrice> {
@@ -338,7 +846,7 @@ where
             .get_price("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2", None)
             .await?;
         let mut usd_price = (gas as f64) * eth_price.parse::<f64>()?;
-        usd_price = usd_price / (10_u64.pow(18) as f64);
+        usd_price /= 10_u64.pow(18) as f64;
 
         Ok(EventPrice {
             gas,
@@ -346,3 +854,13 @@ where
         })
     }
 }
+
+/// Returns the arguments of `withdraw_tokens`, from the request content (in hexadecimal felts).
+fn withdraw_args(tx: &CrossChainTx) -> Result<Vec<U256>> {
+    let felts_strs: Vec<String> = serde_json::from_str(&tx.req_content)?;
+
+    Ok(felts_strs
+        .iter()
+        .map(|felt_str| U256::from_str_radix(felt_str, 16))
+        .collect::<Result<Vec<U256>, _>>()?)
+}
@@ -1,5 +1,7 @@
 pub mod client;
 pub mod events;
+pub mod fee_policy;
 pub use client::EthereumClient;
 pub mod indexer;
 pub use indexer::EthereumIndexer;
+pub mod reorg;
@@ -0,0 +1,449 @@
+//! Detection of the ethereum reorgs, and rollback of the data
+//! written for the orphaned blocks.
+use crate::storage::{
+    protocol::ProtocolParser,
+    store::{
+        AdminEventStore, BlockStore, CollectionStore, CrossChainTxStore, EventStore,
+        PendingWithdrawStore, RequestStore,
+    },
+    BridgeChain, CrossChainTxKind, CrossChainTxStatus, Event, EventLabel, PendingWithdraw,
+};
+use anyhow::Result;
+use std::future::Future;
+
+// Maximum number of indexed blocks checked against the canonical chain
+// when looking for a reorg.
+const REORG_MAX_DEPTH: u64 = 64;
+
+/// Compares the latest indexed blocks with the canonical chain, where
+/// `block_hashes` returns the hash and parent hash of a canonical block.
+///
+/// Returns the first orphaned block number if a reorg is detected.
+/// Blocks indexed without hash can't be verified, and are considered
+/// as canonical.
+pub async fn detect_reorg<S, F, Fut>(store: &S, block_hashes: F) -> Result<Option<u64>>
+where
+    S: BlockStore,
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<Option<(String, String)>>>,
+{
+    let blocks = store
+        .latest_blocks(BridgeChain::Ethereum, REORG_MAX_DEPTH)
+        .await?;
+
+    let mut first_orphaned: Option<u64> = None;
+
+    for b in blocks {
+        let stored_hash = match &b.block_hash {
+            Some(h) => h,
+            None => break,
+        };
+
+        match block_hashes(b.block_number).await? {
+            Some((hash, _)) if &hash == stored_hash => break,
+            _ => {
+                log::warn!("Ethereum block {} was reorged", b.block_number);
+                first_orphaned = Some(b.block_number);
+            }
+        }
+    }
+
+    Ok(first_orphaned)
+}
+
+/// Removes all the data written for the ethereum blocks starting at `from_block`.
+///
+/// The auto withdraws completed in the orphaned blocks are queued again, the burn
+/// transactions not yet sent for the orphaned withdraws are removed, and the pending
+/// withdraws made available in the orphaned blocks are restored.
+pub async fn rollback<S>(
+    store: &S,
+    from_block: u64,
+    sn_bridge_address: &str,
+    eth_bridge_address: &str,
+) -> Result<()>
+where
+    S: RequestStore
+        + EventStore
+        + BlockStore
+        + CrossChainTxStore
+        + PendingWithdrawStore
+        + AdminEventStore
+        + CollectionStore,
+{
+    log::warn!("Rolling back ethereum blocks from {}", from_block);
+
+    let events = store
+        .remove_events_from_block(BridgeChain::Ethereum, from_block)
+        .await?;
+
+    for e in events {
+        log::info!("Remove orphaned event: {:?}", &e);
+
+        // Inserted by the indexer itself (with a custom transaction hash)
+        // when the message of the request is available on L1.
+        if e.label == EventLabel::WithdrawAvailableL1 {
+            restore_pending_withdraw(store, &e, sn_bridge_address, eth_bridge_address).await?;
+            continue;
+        }
+
+        // The request may have been inserted by the orphaned event only.
+        if store.events_by_request(&e.req_hash).await?.is_empty() {
+            store.remove_txs_by_hash(&e.tx_hash).await?;
+            store.remove_req(&e.req_hash).await?;
+            store
+                .remove_pending_withdraws_by_request(&e.req_hash)
+                .await?;
+            continue;
+        }
+
+        // Queued when the withdraw was indexed, the tokens must not be burnt anymore.
+        if e.label == EventLabel::WithdrawCompletedL1 {
+            remove_unsent_burn(store, &e.req_hash).await?;
+        }
+
+        if let Some(mut tx) = store
+            .tx_from_request_kind(&e.req_hash, CrossChainTxKind::WithdrawAuto)
+            .await?
+        {
+            if tx.tx_hash == e.tx_hash {
+                log::info!("Queue again auto withdraw of request {}", tx.req_hash);
+                tx.queued();
+                store.update_tx(tx).await?;
+            }
+        }
+    }
+
+    store
+        .remove_admin_events_from_block(BridgeChain::Ethereum, from_block)
+        .await?;
+
+    store
+        .remove_collections_from_block(BridgeChain::Ethereum, from_block)
+        .await?;
+
+    store
+        .remove_blocks_from(BridgeChain::Ethereum, from_block)
+        .await?;
+
+    // The cursor must not be beyond the orphaned blocks in case of restart.
+    if let Some(c) = store.cursor(BridgeChain::Ethereum).await? {
+        if c.block_number >= from_block {
+            store
+                .set_cursor(BridgeChain::Ethereum, from_block.saturating_sub(1))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the burn transaction of the request, if not sent yet.
+async fn remove_unsent_burn<S>(store: &S, req_hash: &str) -> Result<()>
+where
+    S: CrossChainTxStore,
+{
+    if let Some(tx) = store
+        .tx_from_request_kind(req_hash, CrossChainTxKind::BurnAuto)
+        .await?
+    {
+        if matches!(
+            tx.status,
+            CrossChainTxStatus::Queued | CrossChainTxStatus::Failed
+        ) {
+            log::info!("Remove burn auto of request {}", req_hash);
+            store
+                .remove_tx(req_hash, CrossChainTxKind::BurnAuto)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Inserts again the pending withdraw of the request of an orphaned
+/// `WithdrawAvailableL1` event, from the deposit on starknet.
+async fn restore_pending_withdraw<S>(
+    store: &S,
+    event: &Event,
+    sn_bridge_address: &str,
+    eth_bridge_address: &str,
+) -> Result<()>
+where
+    S: RequestStore + EventStore + PendingWithdrawStore,
+{
+    let req = match store.req_by_hash(&event.req_hash).await? {
+        Some(r) => r,
+        None => return Ok(()),
+    };
+
+    let deposit = store
+        .events_by_request(&req.hash)
+        .await?
+        .into_iter()
+        .find(|e| e.label == EventLabel::DepositInitiatedL2);
+
+    if let Some(deposit) = deposit {
+        log::info!("Restore pending withdraw of request {}", req.hash);
+        store
+            .insert_pending_withdraw(PendingWithdraw {
+                req_hash: req.hash.clone(),
+                tx_hash: deposit.tx_hash,
+                chain_src: req.chain_src,
+                timestamp: deposit.block_timestamp,
+                message_hash: req.message_to_l1_hash(sn_bridge_address, eth_bridge_address)?,
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{
+        memory::MemoryStore, BlockIndex, CollectionContract, CrossChainTx, Request,
+    };
+
+    fn block(block_number: u64, hash: &str) -> BlockIndex {
+        BlockIndex {
+            chain: BridgeChain::Ethereum,
+            block_number,
+            insert_timestamp: 0,
+            block_hash: Some(hash.to_string()),
+            parent_hash: None,
+        }
+    }
+
+    fn event(req_hash: &str, label: EventLabel, block_number: u64, tx_hash: &str) -> Event {
+        Event {
+            req_hash: req_hash.to_string(),
+            label,
+            block_timestamp: block_number * 10,
+            block_number,
+            tx_hash: tx_hash.to_string(),
+            price: None,
+            finality: None,
+            log_index: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect_reorg() {
+        let store = MemoryStore::new();
+        for n in 1..=4 {
+            store
+                .insert_block(block(n, &format!("0x{}", n)))
+                .await
+                .unwrap();
+        }
+
+        let canonical = |n: u64| async move { Ok(Some((format!("0x{}", n), String::new()))) };
+        assert_eq!(detect_reorg(&store, canonical).await.unwrap(), None);
+
+        // Blocks 3 and 4 were replaced.
+        let reorged = |n: u64| async move {
+            let hash = if n >= 3 {
+                format!("0x{}b", n)
+            } else {
+                format!("0x{}", n)
+            };
+            Ok(Some((hash, String::new())))
+        };
+        assert_eq!(detect_reorg(&store, reorged).await.unwrap(), Some(3));
+
+        // Block 4 doesn't exist anymore.
+        let shorter = |n: u64| async move {
+            Ok(if n >= 4 {
+                None
+            } else {
+                Some((format!("0x{}", n), String::new()))
+            })
+        };
+        assert_eq!(detect_reorg(&store, shorter).await.unwrap(), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_rollback() {
+        let store = MemoryStore::new();
+        let sn_bridge = String::from("0x10");
+        let eth_bridge = String::from("0x20");
+
+        // L2->L1 request available on L1 at block 5, and withdrawn at block 6
+        // by the relayer.
+        let l2_req = Request {
+            hash: String::from("0xa"),
+            chain_src: BridgeChain::Starknet,
+            content: String::from(r#"["0x1", "0x2"]"#),
+            ..Default::default()
+        };
+        store.insert_req(l2_req.clone()).await.unwrap();
+        for e in [
+            event("0xa", EventLabel::DepositInitiatedL2, 100, "0xd1"),
+            event("0xa", EventLabel::WithdrawAvailableL1, 5, "0xcustom"),
+            event("0xa", EventLabel::WithdrawCompletedL1, 6, "0xw1"),
+        ] {
+            store.insert_event(e).await.unwrap();
+        }
+        store
+            .insert_tx(CrossChainTx {
+                chain: BridgeChain::Ethereum,
+                kind: CrossChainTxKind::WithdrawAuto,
+                req_hash: String::from("0xa"),
+                tx_hash: String::from("0xw1"),
+                status: CrossChainTxStatus::Confirmed,
+                attempts: 2,
+                nonce: Some(7),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // L1->L2 request deposited at block 6 only.
+        store
+            .insert_req(Request {
+                hash: String::from("0xb"),
+                chain_src: BridgeChain::Ethereum,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store
+            .insert_event(event("0xb", EventLabel::DepositInitiatedL1, 6, "0xd2"))
+            .await
+            .unwrap();
+
+        // Collections mapped at blocks 4 and 6.
+        for (address, block_number) in [("0x100", 4), ("0x200", 6)] {
+            store
+                .insert_collection(CollectionContract {
+                    chain_src: BridgeChain::Ethereum,
+                    address_src: address.to_string(),
+                    address_dst: String::from("0x300"),
+                    chain: BridgeChain::Ethereum,
+                    block_number,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+
+        for n in 4..=6 {
+            store
+                .insert_block(block(n, &format!("0x{}", n)))
+                .await
+                .unwrap();
+        }
+        store.set_cursor(BridgeChain::Ethereum, 6).await.unwrap();
+
+        rollback(&store, 5, &sn_bridge, &eth_bridge).await.unwrap();
+
+        let events = store.events_by_request("0xa").await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].label, EventLabel::DepositInitiatedL2);
+
+        let tx = store
+            .tx_from_request_kind("0xa", CrossChainTxKind::WithdrawAuto)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.status, CrossChainTxStatus::Queued);
+        assert_eq!(tx.tx_hash, "");
+        assert_eq!(tx.attempts, 0);
+        assert_eq!(tx.nonce, None);
+
+        let pendings = store.get_pending_withdraws().await.unwrap();
+        assert_eq!(pendings.len(), 1);
+        assert_eq!(pendings[0].req_hash, "0xa");
+        assert_eq!(pendings[0].tx_hash, "0xd1");
+        assert_eq!(pendings[0].timestamp, 1000);
+        assert_eq!(
+            pendings[0].message_hash,
+            l2_req.message_to_l1_hash(&sn_bridge, &eth_bridge).unwrap()
+        );
+
+        assert!(store.req_by_hash("0xb").await.unwrap().is_none());
+
+        assert!(store
+            .collection_by_address("0x100")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(store
+            .collection_by_address("0x200")
+            .await
+            .unwrap()
+            .is_none());
+
+        let blocks = store
+            .latest_blocks(BridgeChain::Ethereum, 10)
+            .await
+            .unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].block_number, 4);
+        assert_eq!(
+            store
+                .cursor(BridgeChain::Ethereum)
+                .await
+                .unwrap()
+                .unwrap()
+                .block_number,
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rollback_burn_auto() {
+        let store = MemoryStore::new();
+        let bridge = String::from("0x10");
+
+        // L2->L1 requests withdrawn at block 5, with a burn auto queued
+        // for the first one and already sent for the second one.
+        for (hash, status) in [
+            ("0xa", CrossChainTxStatus::Queued),
+            ("0xb", CrossChainTxStatus::Submitted),
+        ] {
+            store
+                .insert_req(Request {
+                    hash: hash.to_string(),
+                    chain_src: BridgeChain::Starknet,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            store
+                .insert_event(event(hash, EventLabel::DepositInitiatedL2, 100, "0xd1"))
+                .await
+                .unwrap();
+            store
+                .insert_event(event(hash, EventLabel::WithdrawCompletedL1, 5, "0xw1"))
+                .await
+                .unwrap();
+            store
+                .insert_tx(CrossChainTx {
+                    chain: BridgeChain::Starknet,
+                    kind: CrossChainTxKind::BurnAuto,
+                    req_hash: hash.to_string(),
+                    status,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+        store.insert_block(block(5, "0x5")).await.unwrap();
+
+        rollback(&store, 5, &bridge, &bridge).await.unwrap();
+
+        assert!(store
+            .tx_from_request_kind("0xa", CrossChainTxKind::BurnAuto)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .tx_from_request_kind("0xb", CrossChainTxKind::BurnAuto)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,162 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+
+use super::AppState;
+use crate::{
+    storage::{store::AdminEventStore, AdminEvent, AdminEventKind, BridgeChain},
+    utils::denormalize_hex,
+};
+
+/// Configuration of a bridge contract, rebuilt from its administration events.
+/// Fields never set by an event are `None`.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct BridgeConfig {
+    enabled: Option<bool>,
+    white_list_enabled: bool,
+    white_listed_collections: Vec<String>,
+    minimum_gas_fee: Option<String>,
+    owner: Option<String>,
+    implementation: Option<String>,
+    bridge_l1_address: Option<String>,
+    erc721_class_hash: Option<String>,
+}
+
+impl BridgeConfig {
+    /// Replays the administration events, expected in emission order.
+    pub fn from_events(events: &[AdminEvent]) -> BridgeConfig {
+        let mut config = BridgeConfig::default();
+
+        for e in events {
+            match &e.kind {
+                AdminEventKind::BridgeEnabled { enabled } => config.enabled = Some(*enabled),
+                AdminEventKind::WhiteListEnabled { enabled } => {
+                    config.white_list_enabled = *enabled
+                }
+                AdminEventKind::CollectionWhiteListUpdated {
+                    collection,
+                    enabled,
+                } => {
+                    let collection = denormalize(collection);
+                    config.white_listed_collections.retain(|c| c != &collection);
+                    if *enabled {
+                        config.white_listed_collections.push(collection);
+                    }
+                }
+                AdminEventKind::MinimumGasFeeUpdated { gas_fee } => {
+                    config.minimum_gas_fee = Some(gas_fee.clone())
+                }
+                AdminEventKind::OwnershipTransferred { new_owner, .. } => {
+                    config.owner = Some(denormalize(new_owner))
+                }
+                AdminEventKind::Upgraded { implementation } => {
+                    config.implementation = Some(denormalize(implementation))
+                }
+                AdminEventKind::BridgeL1AddressUpdated { address } => {
+                    config.bridge_l1_address = Some(denormalize(address))
+                }
+                AdminEventKind::Erc721ClassHashUpdated { class_hash } => {
+                    config.erc721_class_hash = Some(denormalize(class_hash))
+                }
+            }
+        }
+
+        config
+    }
+}
+
+fn denormalize(s: &str) -> String {
+    denormalize_hex(s).expect("Failed to denormalize address")
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BridgesConfig {
+    l1: BridgeConfig,
+    l2: BridgeConfig,
+}
+
+/// Returns the configuration of both bridges, rebuilt from the indexed
+/// administration events.
+pub async fn bridges_config<S: AdminEventStore + Send + Sync>(
+    state: State<AppState<S>>,
+) -> Result<Json<BridgesConfig>, (StatusCode, String)> {
+    let (l1_events, l2_events) = match (
+        state.store.admin_events(BridgeChain::Ethereum).await,
+        state.store.admin_events(BridgeChain::Starknet).await,
+    ) {
+        (Ok(l1), Ok(l2)) => (l1, l2),
+        _ => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error retrieving admin events".to_string(),
+            ))
+        }
+    };
+
+    Ok(Json(BridgesConfig {
+        l1: BridgeConfig::from_events(&l1_events),
+        l2: BridgeConfig::from_events(&l2_events),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin_event(block_number: u64, kind: AdminEventKind) -> AdminEvent {
+        AdminEvent {
+            chain: BridgeChain::Ethereum,
+            kind,
+            block_number,
+            event_index: 0,
+            block_timestamp: 0,
+            tx_hash: String::from("0x1"),
+        }
+    }
+
+    #[test]
+    fn test_config_from_events() {
+        let collection = "0x0000000000000000000000008c7173db918eb0f015ba2d319e94e1eab95c63fb";
+        let events = vec![
+            admin_event(1, AdminEventKind::WhiteListEnabled { enabled: true }),
+            admin_event(
+                2,
+                AdminEventKind::CollectionWhiteListUpdated {
+                    collection: collection.to_string(),
+                    enabled: true,
+                },
+            ),
+            admin_event(
+                3,
+                AdminEventKind::CollectionWhiteListUpdated {
+                    collection: collection.to_string(),
+                    enabled: true,
+                },
+            ),
+            admin_event(
+                4,
+                AdminEventKind::MinimumGasFeeUpdated {
+                    gas_fee: String::from("5000"),
+                },
+            ),
+        ];
+
+        let config = BridgeConfig::from_events(&events);
+        assert!(config.white_list_enabled);
+        assert_eq!(
+            config.white_listed_collections,
+            vec!["0x8c7173db918eb0f015ba2d319e94e1eab95c63fb"]
+        );
+        assert_eq!(config.minimum_gas_fee, Some(String::from("5000")));
+        assert_eq!(config.enabled, None);
+
+        let events = vec![admin_event(
+            5,
+            AdminEventKind::CollectionWhiteListUpdated {
+                collection: collection.to_string(),
+                enabled: false,
+            },
+        )];
+        let config = BridgeConfig::from_events(&events);
+        assert!(config.white_listed_collections.is_empty());
+    }
+}
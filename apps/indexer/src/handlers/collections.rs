@@ -0,0 +1,63 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use super::AppState;
+use crate::{
+    storage::{store::CollectionStore, BridgeChain},
+    utils::{denormalize_hex, normalize_hex},
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CollectionInfo {
+    chain_src: BridgeChain,
+    l1_address: String,
+    l2_address: String,
+    req_hash: Option<String>,
+    timestamp: u64,
+}
+
+/// Resolves a collection address (L1 or L2) to its counterpart on the other chain.
+pub async fn collection_from_address<S: CollectionStore + Send + Sync>(
+    Path(address): Path<String>,
+    state: State<AppState<S>>,
+) -> Result<Json<CollectionInfo>, (StatusCode, String)> {
+    let address = normalize_hex(&address).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Collection address shall be an hexadecimal string".to_string(),
+        )
+    })?;
+
+    let collection = match state.store.collection_by_address(&address).await {
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                "Collection not registered".to_string(),
+            ))
+        }
+        Err(_) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error retrieving collection".to_string(),
+            ))
+        }
+    };
+
+    let (l1_address, l2_address) = match collection.chain_src {
+        BridgeChain::Ethereum => (collection.address_src, collection.address_dst),
+        BridgeChain::Starknet => (collection.address_dst, collection.address_src),
+    };
+
+    Ok(Json(CollectionInfo {
+        chain_src: collection.chain_src,
+        l1_address: denormalize_hex(&l1_address).expect("Failed to denormalize 'l1_address'"),
+        l2_address: denormalize_hex(&l2_address).expect("Failed to denormalize 'l2_address'"),
+        req_hash: collection.req_hash,
+        timestamp: collection.timestamp,
+    }))
+}
@@ -2,15 +2,34 @@ use std::sync::Arc;
 
 use tokio::sync::RwLock as AsyncRwLock;
 
-use crate::storage::mongo::MongoStore;
+use crate::ethereum_indexer::client::EthereumClient;
 use crate::ChainsBlocks;
 
+pub mod admin;
+pub mod collections;
+pub mod relayer;
 pub mod requests;
+pub mod tokens;
 
-#[derive(Clone)]
-pub struct AppState {
-    pub store: Arc<MongoStore>,
+/// State shared by the handlers, generic over the store backend.
+pub struct AppState<S> {
+    pub store: Arc<S>,
     pub l1_address: String,
     pub l2_address: String,
     pub chains_blocks: Arc<AsyncRwLock<ChainsBlocks>>,
+    // Client used to query the Starknet core contract, if configured.
+    pub eth_client: Option<Arc<EthereumClient>>,
+}
+
+// Not derived, as the store itself doesn't need to be `Clone`.
+impl<S> Clone for AppState<S> {
+    fn clone(&self) -> Self {
+        AppState {
+            store: Arc::clone(&self.store),
+            l1_address: self.l1_address.clone(),
+            l2_address: self.l2_address.clone(),
+            chains_blocks: Arc::clone(&self.chains_blocks),
+            eth_client: self.eth_client.clone(),
+        }
+    }
 }
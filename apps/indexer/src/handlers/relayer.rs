@@ -0,0 +1,45 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+
+use super::AppState;
+use crate::storage::{store::CrossChainTxStore, BridgeChain, CrossChainTx, CrossChainTxStatus};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RelayerQueueQuery {
+    chain: Option<BridgeChain>,
+    status: Option<CrossChainTxStatus>,
+}
+
+/// Returns the cross chain transactions handled by the relayer,
+/// optionally filtered by chain and status.
+pub async fn relayer_queue<S: CrossChainTxStore + Send + Sync>(
+    Query(query): Query<RelayerQueueQuery>,
+    state: State<AppState<S>>,
+) -> Result<Json<Vec<CrossChainTx>>, (StatusCode, String)> {
+    let chains = match query.chain {
+        Some(c) => vec![c],
+        None => vec![BridgeChain::Ethereum, BridgeChain::Starknet],
+    };
+
+    let mut txs = vec![];
+    for chain in chains {
+        match state.store.list_xtxs(chain).await {
+            Ok(t) => txs.extend(t),
+            Err(e) => {
+                log::error!("Error retrieving xchain txs: {:?}", e);
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Error retrieving xchain txs".to_string(),
+                ));
+            }
+        }
+    }
+
+    txs.retain(|t| query.status.is_none_or(|s| t.status == s));
+
+    Ok(Json(txs))
+}
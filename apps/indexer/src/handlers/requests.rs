@@ -1,17 +1,20 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
     Json,
 };
+use ethers::types::H256;
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use super::AppState;
 use crate::{
     storage::{
-        protocol::ProtocolParser,
-        store::{EventStore, RequestStore},
-        Event, Request,
+        protocol::{DecodedRequest, ProtocolParser},
+        status::{RequestState, RequestStatus},
+        store::{BlockStore, CrossChainTxStore, EventStore, RequestStore},
+        BridgeChain, CrossChainTx, CrossChainTxKind, Event, Request, RequestFilter, SortOrder,
     },
     utils::{denormalize_hex, normalize_hex},
 };
@@ -25,7 +28,7 @@ impl Serialize for RequestWrapper {
         S: serde::Serializer,
     {
         let RequestWrapper(ref inner) = *self;
-        let mut state = serializer.serialize_struct("Request", 7)?;
+        let mut state = serializer.serialize_struct("Request", 13)?;
         state.serialize_field("hash", &inner.hash)?;
         state.serialize_field("chain_src", &inner.chain_src)?;
         state.serialize_field(
@@ -47,56 +50,318 @@ impl Serialize for RequestWrapper {
                 .expect("Failed to denormalize 'collection_dst'"),
         )?;
         state.serialize_field("content", &inner.content)?;
+        state.serialize_field("timestamp", &inner.timestamp)?;
+        state.serialize_field("collection_type", &inner.collection_type)?;
+        state.serialize_field("name", &inner.name)?;
+        state.serialize_field("symbol", &inner.symbol)?;
+        state.serialize_field("base_uri", &inner.base_uri)?;
+        state.serialize_field("tokens", &inner.tokens)?;
         state.end()
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RequestInfo {
-    req: RequestWrapper,
-    events: Vec<Event>,
-    token_ids: Vec<String>,
+    pub req: RequestWrapper,
+    pub events: Vec<Event>,
+    pub token_ids: Vec<String>,
+    // Status computed from the events.
+    pub state: RequestState,
+}
+
+impl RequestInfo {
+    /// Builds the DTO of a request, sorting its events to ensure
+    /// the latest is the current status.
+    pub fn new(req: Request, mut events: Vec<Event>) -> RequestInfo {
+        events.sort_by_key(|e| e.label);
+
+        // Requests indexed before the content was decoded have no tokens.
+        let token_ids = if req.tokens.is_empty() {
+            req.get_token_ids().unwrap_or_default()
+        } else {
+            req.tokens.iter().map(|t| t.token_id.clone()).collect()
+        };
+
+        let state = RequestState::from_events(&req, &events);
+
+        RequestInfo {
+            req: RequestWrapper(req),
+            events,
+            token_ids,
+            state,
+        }
+    }
+}
+
+/// Number of requests returned in one page when no limit is given.
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Maximum number of requests returned in one page.
+const MAX_PAGE_SIZE: usize = 100;
+
+/// Maximum number of pages read from the store to fill a page filtered on the status.
+/// The cursor reached is returned once exceeded, even if the page is not full.
+const MAX_SCANNED_PAGES: usize = 10;
+
+/// Response header carrying the cursor of the next page, if any.
+const NEXT_CURSOR_HEADER: &str = "X-Next-Cursor";
+
+/// Query parameters of the wallet requests.
+#[derive(Debug, Default, Deserialize)]
+pub struct RequestsQuery {
+    // Source chain of the requests.
+    chain_src: Option<BridgeChain>,
+    // Current status of the requests, computed from their events.
+    status: Option<RequestStatus>,
+    // Collection address, on the source or the destination chain.
+    collection: Option<String>,
+    // Time range (included) of the requests creation.
+    from_timestamp: Option<u64>,
+    to_timestamp: Option<u64>,
+    // Order of the requests by creation time, most recent first by default.
+    #[serde(default)]
+    order: SortOrder,
+    // Maximum number of requests in the page.
+    limit: Option<usize>,
+    // Cursor returned in the `X-Next-Cursor` header of the previous page.
+    cursor: Option<String>,
+}
+
+/// Position of a request in the list: its creation timestamp and its hash.
+type SortKey = (u64, String);
+
+fn parse_cursor(cursor: &str) -> Option<SortKey> {
+    let (timestamp, hash) = cursor.split_once('_')?;
+    Some((timestamp.parse().ok()?, hash.to_string()))
+}
+
+fn format_cursor(key: &SortKey) -> String {
+    format!("{}_{}", key.0, key.1)
 }
 
 /// Builds a DTO with requests and associated events.
-pub async fn reqs_info_from_wallet(
+/// The requests are the ones sent or received by the wallet, filtered and paginated
+/// by the query parameters. The cursor of the next page is returned in the `X-Next-Cursor` header.
+pub async fn reqs_info_from_wallet<S: RequestStore + EventStore + Send + Sync>(
     Path(wallet): Path<String>,
-    state: State<AppState>,
-) -> Result<Json<Vec<RequestInfo>>, (StatusCode, String)> {
-    let wallet = normalize_hex(&wallet).expect("Wallet address shall be an hexadecimal string");
+    Query(query): Query<RequestsQuery>,
+    state: State<AppState<S>>,
+) -> Result<(HeaderMap, Json<Vec<RequestInfo>>), (StatusCode, String)> {
+    let wallet = normalize_hex(&wallet).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Wallet address shall be an hexadecimal string".to_string(),
+        )
+    })?;
+
+    let collection = match &query.collection {
+        Some(c) => Some(normalize_hex(c).map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                "Collection address shall be an hexadecimal string".to_string(),
+            )
+        })?),
+        None => None,
+    };
+
+    let cursor = match &query.cursor {
+        Some(c) => {
+            Some(parse_cursor(c).ok_or((StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))?)
+        }
+        None => None,
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    // One more request is fetched to know if a next page exists.
+    let mut filter = RequestFilter {
+        chain_src: query.chain_src,
+        collection,
+        from_timestamp: query.from_timestamp,
+        to_timestamp: query.to_timestamp,
+        order: query.order,
+        after: cursor,
+        limit: Some(limit + 1),
+    };
 
     let mut dtos: Vec<RequestInfo> = vec![];
+    let mut next_cursor: Option<SortKey> = None;
+
+    // The status is computed from the events, the requests filtered out
+    // are replaced by the following ones until the page is full,
+    // or until `MAX_SCANNED_PAGES` are read.
+    for scanned in 1.. {
+        let reqs = state
+            .store
+            .reqs_by_wallet(&wallet, &filter)
+            .await
+            .map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Error retrieving requests".to_string(),
+                )
+            })?;
+        let last_page = reqs.len() <= limit;
+
+        let hashes: Vec<String> = reqs.iter().map(|r| r.hash.clone()).collect();
+        let all_events = state.store.events_by_requests(&hashes).await.map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error retrieving events".to_string(),
+            )
+        })?;
+
+        let mut events_by_hash: HashMap<String, Vec<Event>> = HashMap::new();
+        for event in all_events {
+            events_by_hash
+                .entry(event.req_hash.clone())
+                .or_default()
+                .push(event);
+        }
 
-    if let Ok(reqs) = state.store.reqs_by_wallet(&wallet).await {
         for req in reqs {
-            if let Ok(mut events) = state.store.events_by_request(&req.hash).await {
-                // Sort enum to ensure the latest is the current status.
-                events.sort_by_key(|e| e.label);
-
-                dtos.push(RequestInfo {
-                    req: RequestWrapper(req.clone()),
-                    events,
-                    token_ids: req.get_token_ids(),
-                });
-            } else {
-                // TODO: maybe no need to crash here? Only skip this request?
+            filter.after = Some((req.timestamp, req.hash.clone()));
+
+            let events = events_by_hash.remove(&req.hash).unwrap_or_default();
+            let info = RequestInfo::new(req, events);
+
+            if query.status.is_some_and(|s| info.state.status != s) {
+                continue;
+            }
+
+            dtos.push(info);
+        }
+
+        if last_page || dtos.len() > limit {
+            break;
+        }
+
+        if scanned >= MAX_SCANNED_PAGES {
+            next_cursor = filter.after.clone();
+            break;
+        }
+    }
+
+    if dtos.len() > limit {
+        dtos.truncate(limit);
+        next_cursor = dtos
+            .last()
+            .map(|last| (last.req.0.timestamp, last.req.0.hash.clone()));
+    }
+
+    let mut headers = HeaderMap::new();
+    if let Some(key) = next_cursor {
+        headers.insert(
+            NEXT_CURSOR_HEADER,
+            HeaderValue::from_str(&format_cursor(&key))
+                .expect("Cursor shall be a valid header value"),
+        );
+    }
+
+    Ok((headers, Json(dtos)))
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequestDetail {
+    req: RequestWrapper,
+    // Request decoded from its content, none if the content can't be decoded.
+    decoded: Option<DecodedRequest>,
+    // Events of the request, the latest being the current status.
+    events: Vec<Event>,
+    // Status computed from the events.
+    state: RequestState,
+    // Hash of the L2->L1 message, only for requests initiated on starknet.
+    message_hash: Option<String>,
+    // Number of messages with this hash waiting to be consumed in the
+    // Starknet core contract, none if it can't be queried.
+    message_status: Option<u64>,
+    // Transactions sent by the indexer for the request.
+    xchain_txs: Vec<CrossChainTx>,
+}
+
+/// Returns a request given by its hash, with its events, the status of
+/// its L2->L1 message and the cross chain transactions associated.
+pub async fn request_from_hash<S: RequestStore + EventStore + CrossChainTxStore + Send + Sync>(
+    Path(hash): Path<String>,
+    state: State<AppState<S>>,
+) -> Result<Json<RequestDetail>, (StatusCode, String)> {
+    let hash = normalize_hex(&hash).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Request hash shall be an hexadecimal string".to_string(),
+        )
+    })?;
+
+    let req = match state.store.req_by_hash(&hash).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, "Request not found".to_string())),
+        Err(_) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error retrieving request".to_string(),
+            ))
+        }
+    };
+
+    let mut events = state.store.events_by_request(&hash).await.map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error retrieving events".to_string(),
+        )
+    })?;
+    // Sort enum to ensure the latest is the current status.
+    events.sort_by_key(|e| e.label);
+
+    let mut xchain_txs = vec![];
+    for kind in [CrossChainTxKind::WithdrawAuto, CrossChainTxKind::BurnAuto] {
+        match state.store.tx_from_request_kind(&hash, kind).await {
+            Ok(Some(tx)) => xchain_txs.push(tx),
+            Ok(None) => (),
+            Err(_) => {
                 return Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    "Error retrieving events".to_string(),
-                ));
+                    "Error retrieving cross chain transactions".to_string(),
+                ))
             }
         }
-    } else {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Error retrieving requests".to_string(),
-        ));
     }
 
-    Ok(Json(dtos))
+    let message_hash = match req.chain_src {
+        BridgeChain::Starknet => req
+            .message_to_l1_hash(&state.l2_address, &state.l1_address)
+            .ok(),
+        BridgeChain::Ethereum => None,
+    };
+
+    let message_status = match (&state.eth_client, message_hash) {
+        (Some(client), Some(msg_hash)) => match client.query_message_status(msg_hash).await {
+            Ok(status) => Some(status),
+            Err(e) => {
+                log::warn!("Failed to query message status of {}: {:?}", hash, e);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    Ok(Json(RequestDetail {
+        decoded: req.decoded().ok(),
+        state: RequestState::from_events(&req, &events),
+        req: RequestWrapper(req),
+        events,
+        message_hash: message_hash.map(|h| format!("{:#x}", H256::from(h))),
+        message_status,
+        xchain_txs,
+    }))
 }
 
-pub async fn transaction(Path(txhash): Path<String>, state: State<AppState>) -> StatusCode {
+pub async fn transaction<S: EventStore + Send + Sync>(
+    Path(txhash): Path<String>,
+    state: State<AppState<S>>,
+) -> StatusCode {
     let txhash = normalize_hex(&txhash).expect("Transaction hash shall be an hexadecimal string");
     if let Ok(event) = state.store.event_by_tx(&txhash).await {
         if event.is_some() {
@@ -112,6 +377,8 @@ pub struct IndexerInfo {
     l2_address: String,
     l1_block_number: u64,
     l2_block_number: u64,
+    l1_cursor: Option<u64>,
+    l2_cursor: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -119,9 +386,9 @@ pub struct Stats {
     total_tokens_bridged_on_starknet: u64,
 }
 
-pub async fn contract_stats(
+pub async fn contract_stats<S: EventStore + Send + Sync>(
     Path(eth_contract_address): Path<String>,
-    state: State<AppState>,
+    state: State<AppState<S>>,
 ) -> Result<Json<Stats>, (StatusCode, String)> {
     let contract_address = normalize_hex(&eth_contract_address)
         .expect("Contract address shall be an hexadecimal string");
@@ -139,7 +406,22 @@ pub async fn contract_stats(
     Ok(Json(stats))
 }
 
-pub async fn info(state: State<AppState>) -> Result<Json<IndexerInfo>, (StatusCode, String)> {
+pub async fn info<S: BlockStore + Send + Sync>(
+    state: State<AppState<S>>,
+) -> Result<Json<IndexerInfo>, (StatusCode, String)> {
+    let (l1_cursor, l2_cursor) = match (
+        state.store.cursor(BridgeChain::Ethereum).await,
+        state.store.cursor(BridgeChain::Starknet).await,
+    ) {
+        (Ok(l1), Ok(l2)) => (l1.map(|c| c.block_number), l2.map(|c| c.block_number)),
+        _ => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error retrieving indexing cursors".to_string(),
+            ))
+        }
+    };
+
     let chains_blocks = state.chains_blocks.read().await;
 
     let info = IndexerInfo {
@@ -147,6 +429,95 @@ pub async fn info(state: State<AppState>) -> Result<Json<IndexerInfo>, (StatusCo
         l2_address: state.l2_address.clone(),
         l1_block_number: chains_blocks.eth,
         l2_block_number: chains_blocks.sn,
+        l1_cursor,
+        l2_cursor,
     };
     Ok(Json(info))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{memory::MemoryStore, store::EventStore, EventLabel};
+    use crate::ChainsBlocks;
+    use std::sync::Arc;
+    use tokio::sync::RwLock as AsyncRwLock;
+
+    #[tokio::test]
+    async fn test_status_filter_bounded() {
+        let store = MemoryStore::new();
+        let count = MAX_SCANNED_PAGES * 2 + 5;
+        for i in 0..count {
+            let hash = format!("0x{:x}", i + 1);
+            store
+                .insert_req(Request {
+                    hash: hash.clone(),
+                    chain_src: BridgeChain::Ethereum,
+                    from: normalize_hex("0x1").unwrap(),
+                    timestamp: i as u64,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            store
+                .insert_event(Event {
+                    req_hash: hash,
+                    label: EventLabel::DepositInitiatedL1,
+                    block_timestamp: i as u64,
+                    block_number: i as u64,
+                    tx_hash: String::from("0xa"),
+                    price: None,
+                    finality: None,
+                    log_index: Some(i as u64),
+                })
+                .await
+                .unwrap();
+        }
+
+        let state = State(AppState {
+            store: Arc::new(store),
+            l1_address: String::new(),
+            l2_address: String::new(),
+            chains_blocks: Arc::new(AsyncRwLock::new(ChainsBlocks { sn: 0, eth: 0 })),
+            eth_client: None,
+        });
+
+        // No request is completed, the scan stops after `MAX_SCANNED_PAGES`
+        // pages of 2 requests and returns the cursor reached.
+        let query = |cursor: Option<String>| RequestsQuery {
+            status: Some(RequestStatus::Completed),
+            limit: Some(1),
+            cursor,
+            ..Default::default()
+        };
+        let (headers, Json(dtos)) =
+            reqs_info_from_wallet(Path(String::from("0x1")), Query(query(None)), state.clone())
+                .await
+                .unwrap();
+        assert!(dtos.is_empty());
+        let cursor = headers[NEXT_CURSOR_HEADER].to_str().unwrap().to_string();
+        let scanned = (MAX_SCANNED_PAGES * 2) as u64;
+        assert_eq!(
+            parse_cursor(&cursor),
+            Some((
+                count as u64 - scanned,
+                format!("0x{:x}", count as u64 - scanned + 1)
+            ))
+        );
+
+        let (headers, Json(dtos)) =
+            reqs_info_from_wallet(Path(String::from("0x1")), Query(query(Some(cursor))), state)
+                .await
+                .unwrap();
+        assert!(dtos.is_empty());
+        assert!(headers.get(NEXT_CURSOR_HEADER).is_none());
+    }
+
+    #[test]
+    fn test_parse_cursor() {
+        let key = (20, "0x4".to_string());
+        assert_eq!(parse_cursor(&format_cursor(&key)), Some(key));
+        assert_eq!(parse_cursor("abc_0x1"), None);
+        assert_eq!(parse_cursor("10"), None);
+    }
+}
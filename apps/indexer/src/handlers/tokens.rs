@@ -0,0 +1,275 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use super::requests::RequestInfo;
+use super::AppState;
+use crate::{
+    storage::{
+        store::{CrossChainTxStore, EventStore, RequestStore},
+        BridgeChain, CrossChainTx, CrossChainTxKind, CrossChainTxStatus, Event, EventLabel,
+        Request,
+    },
+    utils::{denormalize_hex, normalize_hex},
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenInfo {
+    collection: String,
+    token_id: String,
+    // Chain where the token currently is, none while a request is in transit.
+    current_chain: Option<BridgeChain>,
+    // Owner of the token on the current chain, as set by the latest request.
+    owner: Option<String>,
+    // True if the token deposited by the latest request is held by the bridge,
+    // until it is returned by a cancel or burnt by the `burn_auto` transaction.
+    escrowed: bool,
+    // Requests which moved the token, from the oldest.
+    requests: Vec<RequestInfo>,
+}
+
+/// Location of a token, deduced from the latest request which moved it.
+#[derive(Debug, PartialEq)]
+struct TokenLocation {
+    current_chain: Option<BridgeChain>,
+    owner: Option<String>,
+    escrowed: bool,
+}
+
+impl TokenLocation {
+    /// `burn` is the `burn_auto` transaction of the request, if any.
+    fn from_request(req: &Request, events: &[Event], burn: Option<&CrossChainTx>) -> TokenLocation {
+        let has = |label: EventLabel| events.iter().any(|e| e.label == label);
+
+        // The token left in escrow is only burnt once the burn is confirmed.
+        let escrowed = !burn.is_some_and(|tx| tx.status == CrossChainTxStatus::Confirmed);
+
+        let chain_dst = match req.chain_src {
+            BridgeChain::Ethereum => BridgeChain::Starknet,
+            BridgeChain::Starknet => BridgeChain::Ethereum,
+        };
+
+        if has(EventLabel::WithdrawCompletedL1) || has(EventLabel::WithdrawCompletedL2) {
+            TokenLocation {
+                current_chain: Some(chain_dst),
+                owner: Some(req.to.clone()),
+                escrowed,
+            }
+        } else if has(EventLabel::CancelCompletedL1) {
+            TokenLocation {
+                current_chain: Some(req.chain_src),
+                owner: Some(req.from.clone()),
+                escrowed: false,
+            }
+        } else {
+            TokenLocation {
+                current_chain: None,
+                owner: None,
+                escrowed,
+            }
+        }
+    }
+}
+
+/// Returns the requests which moved a token, and where the token currently is.
+/// The collection address is the L1 or L2 address, and the token id
+/// is in decimal or in hexadecimal (with `0x` prefix).
+pub async fn token_info<S: RequestStore + EventStore + CrossChainTxStore + Send + Sync>(
+    Path((collection, token_id)): Path<(String, String)>,
+    state: State<AppState<S>>,
+) -> Result<Json<TokenInfo>, (StatusCode, String)> {
+    let collection_address = normalize_hex(&collection).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Collection address shall be an hexadecimal string".to_string(),
+        )
+    })?;
+
+    let token_id = parse_token_id(&token_id).ok_or((
+        StatusCode::BAD_REQUEST,
+        "Token id shall be a decimal or an hexadecimal string".to_string(),
+    ))?;
+
+    let reqs = state
+        .store
+        .reqs_by_token(&collection_address, &token_id)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error retrieving requests".to_string(),
+            )
+        })?;
+
+    let hashes: Vec<String> = reqs.iter().map(|r| r.hash.clone()).collect();
+    let mut events = state.store.events_by_requests(&hashes).await.map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error retrieving events".to_string(),
+        )
+    })?;
+    events.sort_by_key(|e| e.label);
+
+    let mut reqs_events: Vec<(Request, Vec<Event>)> = reqs
+        .into_iter()
+        .map(|req| {
+            let req_events = events
+                .iter()
+                .filter(|e| e.req_hash == req.hash)
+                .cloned()
+                .collect();
+            (req, req_events)
+        })
+        .collect();
+
+    // Requests are ordered by their first event.
+    reqs_events.sort_by_key(|(_, events)| {
+        events
+            .iter()
+            .map(|e| e.block_timestamp)
+            .min()
+            .unwrap_or(u64::MAX)
+    });
+
+    let (req, events) = match reqs_events.last() {
+        Some(last) => last,
+        None => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                "No request found for this token".to_string(),
+            ))
+        }
+    };
+
+    let burn = state
+        .store
+        .tx_from_request_kind(&req.hash, CrossChainTxKind::BurnAuto)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error retrieving cross chain transactions".to_string(),
+            )
+        })?;
+
+    let location = TokenLocation::from_request(req, events, burn.as_ref());
+
+    let requests = reqs_events
+        .into_iter()
+        .map(|(req, events)| RequestInfo::new(req, events))
+        .collect();
+
+    Ok(Json(TokenInfo {
+        collection,
+        token_id,
+        current_chain: location.current_chain,
+        owner: location
+            .owner
+            .map(|o| denormalize_hex(&o).expect("Failed to denormalize 'owner'")),
+        escrowed: location.escrowed,
+        requests,
+    }))
+}
+
+/// Returns the token id in decimal.
+fn parse_token_id(token_id: &str) -> Option<String> {
+    let value = match token_id.strip_prefix("0x") {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+        None => BigUint::parse_bytes(token_id.as_bytes(), 10),
+    };
+
+    value.map(|v| v.to_str_radix(10))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(label: EventLabel) -> Event {
+        Event {
+            req_hash: String::from("0x1"),
+            label,
+            block_timestamp: 0,
+            block_number: 0,
+            tx_hash: String::from("0x2"),
+            price: None,
+            finality: None,
+            log_index: None,
+        }
+    }
+
+    #[test]
+    fn test_token_location() {
+        let req = Request {
+            chain_src: BridgeChain::Ethereum,
+            from: String::from("0xa"),
+            to: String::from("0xb"),
+            ..Default::default()
+        };
+
+        let deposit = [event(EventLabel::DepositInitiatedL1)];
+        assert_eq!(
+            TokenLocation::from_request(&req, &deposit, None),
+            TokenLocation {
+                current_chain: None,
+                owner: None,
+                escrowed: true,
+            }
+        );
+
+        let withdrawn = [
+            event(EventLabel::DepositInitiatedL1),
+            event(EventLabel::WithdrawCompletedL2),
+        ];
+        assert_eq!(
+            TokenLocation::from_request(&req, &withdrawn, None),
+            TokenLocation {
+                current_chain: Some(BridgeChain::Starknet),
+                owner: Some(String::from("0xb")),
+                escrowed: true,
+            }
+        );
+
+        // Tokens left in escrow are burnt once the burn is confirmed.
+        let mut burn = CrossChainTx {
+            kind: CrossChainTxKind::BurnAuto,
+            status: CrossChainTxStatus::Submitted,
+            ..Default::default()
+        };
+        assert!(TokenLocation::from_request(&req, &withdrawn, Some(&burn)).escrowed);
+        burn.status = CrossChainTxStatus::Confirmed;
+        assert_eq!(
+            TokenLocation::from_request(&req, &withdrawn, Some(&burn)),
+            TokenLocation {
+                current_chain: Some(BridgeChain::Starknet),
+                owner: Some(String::from("0xb")),
+                escrowed: false,
+            }
+        );
+
+        let cancelled = [
+            event(EventLabel::DepositInitiatedL1),
+            event(EventLabel::CancelStartedL1),
+            event(EventLabel::CancelCompletedL1),
+        ];
+        assert_eq!(
+            TokenLocation::from_request(&req, &cancelled, None),
+            TokenLocation {
+                current_chain: Some(BridgeChain::Ethereum),
+                owner: Some(String::from("0xa")),
+                escrowed: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_token_id() {
+        assert_eq!(parse_token_id("255"), Some(String::from("255")));
+        assert_eq!(parse_token_id("0xff"), Some(String::from("255")));
+        assert_eq!(parse_token_id("0xzz"), None);
+    }
+}
@@ -5,23 +5,37 @@ use crate::config::StarklaneIndexerConfig;
 use anyhow::Result;
 use axum::{http::Request, middleware::Next, response::Response, routing::get, Router, Server};
 use clap::Parser;
-use ethereum_indexer::EthereumIndexer;
-use handlers::{requests, AppState};
+use ethereum_indexer::{client::EthereumClient, EthereumIndexer};
+use handlers::{admin, collections, relayer, requests, tokens, AppState};
 use starknet_indexer::StarknetIndexer;
 use std::sync::Arc;
-use storage::mongo::MongoStore;
+use storage::{
+    memory::MemoryStore,
+    mongo::MongoStore,
+    sql::SqlStore,
+    store::{
+        AdminEventStore, BlockStore, CollectionStore, CrossChainTxStore, EventStore,
+        PendingWithdrawStore, RequestStore, StarknetBridgeRequestStore, TransactionStore,
+    },
+};
 use tokio::sync::RwLock as AsyncRwLock;
 
 pub mod config;
 pub mod ethereum_indexer;
 pub mod handlers;
 pub mod price;
+pub mod signer;
 pub mod starknet_indexer;
 pub mod storage;
 pub mod utils;
 
-const ENV_PREFIX: &'static str = "INDEXER";
-const ENV_SEPARATOR: &'static str = "__"; // "_" can't be used since we have key with '_' in json
+const ENV_PREFIX: &str = "INDEXER";
+const ENV_SEPARATOR: &str = "__"; // "_" can't be used since we have key with '_' in json
+
+// Connection string schemes selecting the store, mongo being the default.
+const MEMORY_SCHEME: &str = "memory://";
+const POSTGRES_SCHEMES: [&str; 2] = ["postgres://", "postgresql://"];
+const SQLITE_SCHEME: &str = "sqlite:";
 
 #[derive(Parser, Debug)]
 #[clap(about = "Starklane indexer")]
@@ -29,13 +43,32 @@ struct Args {
     #[clap(long, help = "Configuration file (JSON)", env = format!("{}{}CONFIG_FILE", ENV_PREFIX, ENV_SEPARATOR))]
     config_file: String,
 
-    #[clap(long, help = "Mongo db connection string", env = format!("{}{}MONGODB_URI", ENV_PREFIX, ENV_SEPARATOR))]
-    mongodb: String,
+    #[clap(long, help = "Database connection string: mongodb:// for a Mongo db store, postgres:// for a PostgreSQL store, sqlite: for an embedded SQLite store, memory:// for an in-memory store", env = format!("{}{}DATABASE_URL", ENV_PREFIX, ENV_SEPARATOR), required_unless_present = "mongodb")]
+    database_url: Option<String>,
+
+    #[clap(long, help = "Deprecated, use --database-url", env = format!("{}{}MONGODB_URI", ENV_PREFIX, ENV_SEPARATOR), hide = true)]
+    mongodb: Option<String>,
 
     #[clap(long, help = "The IP to bind to start indexer api server", env = format!("{}{}SERVER_IP", ENV_PREFIX, ENV_SEPARATOR))]
     api_server_ip: Option<String>,
 }
 
+impl Args {
+    /// Returns the database connection string, from the deprecated
+    /// `--mongodb` argument if `--database-url` is not set.
+    fn database_url(&self) -> String {
+        match (&self.database_url, &self.mongodb) {
+            (Some(url), _) => url.clone(),
+            (None, Some(url)) => {
+                log::warn!("--mongodb (INDEXER__MONGODB_URI) is deprecated, use --database-url (INDEXER__DATABASE_URL)");
+                url.clone()
+            }
+            // Enforced by clap.
+            (None, None) => unreachable!("--database-url is required"),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ChainsBlocks {
     sn: u64,
@@ -72,26 +105,61 @@ async fn main() -> Result<()> {
         .try_deserialize()
         .expect("Failed to retrieve configuration");
 
-    let dbname = extract_database_name(&args.mongodb)
+    let database_url = args.database_url();
+
+    if database_url.starts_with(MEMORY_SCHEME) {
+        log::warn!("Using an in-memory store, nothing will be persisted");
+        return run(Arc::new(MemoryStore::new()), config, args).await;
+    }
+
+    if database_url.starts_with(SQLITE_SCHEME)
+        || POSTGRES_SCHEMES.iter().any(|s| database_url.starts_with(s))
+    {
+        let sql_store = Arc::new(SqlStore::new(&database_url).await?);
+        return run(sql_store, config, args).await;
+    }
+
+    let dbname = extract_database_name(&database_url)
         .expect("Database name couldn't be extracted from the connection string");
 
-    let mongo_store = Arc::new(MongoStore::new(&args.mongodb, dbname).await?);
+    let mongo_store = Arc::new(MongoStore::new(&database_url, dbname).await?);
+
+    run(mongo_store, config, args).await
+}
 
+/// Starts the indexers and the api server on the given store.
+async fn run<S>(store: Arc<S>, config: StarklaneIndexerConfig, args: Args) -> Result<()>
+where
+    S: RequestStore
+        + EventStore
+        + BlockStore
+        + CrossChainTxStore
+        + StarknetBridgeRequestStore
+        + PendingWithdrawStore
+        + CollectionStore
+        + AdminEventStore
+        + TransactionStore
+        + Send
+        + Sync
+        + 'static,
+{
     let chains_blocks = Arc::new(AsyncRwLock::new(ChainsBlocks { sn: 0, eth: 0 }));
 
-    let eth_indexer = EthereumIndexer::<MongoStore>::new(
+    let eth_indexer = EthereumIndexer::<S>::new(
         config.ethereum.clone(),
-        Arc::clone(&mongo_store),
+        Arc::clone(&store),
         Arc::clone(&chains_blocks),
-        config.xchain_txor,
+        config.starknet.clone().bridge_address,
+        config.xchain_txor.clone(),
     )
     .await?;
 
-    let sn_indexer = StarknetIndexer::<MongoStore>::new(
+    let sn_indexer = StarknetIndexer::<S>::new(
         config.starknet.clone(),
-        Arc::clone(&mongo_store),
+        Arc::clone(&store),
         Arc::clone(&chains_blocks),
         config.ethereum.clone().bridge_address,
+        config.xchain_txor,
     )
     .await?;
 
@@ -118,20 +186,49 @@ async fn main() -> Result<()> {
             return;
         }
 
+        // The messages status can only be queried if the core contract is known.
+        // The api only reads from the chain, the signer is not loaded again.
+        let eth_client = if config.ethereum.messaging_address.is_some() {
+            match EthereumClient::read_only(&config.ethereum) {
+                Ok(c) => Some(Arc::new(c)),
+                Err(e) => {
+                    log::warn!("Api can't query the messages status: {:?}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         let app_state = AppState {
-            store: Arc::clone(&mongo_store),
+            store: Arc::clone(&store),
             l1_address: config.ethereum.bridge_address,
             l2_address: config.starknet.bridge_address,
             chains_blocks: Arc::clone(&chains_blocks),
+            eth_client,
         };
 
         let app = Router::new()
-            .route("/requests/:wallet", get(requests::reqs_info_from_wallet))
-            .route("/tx/:txhash", get(requests::transaction))
-            .route("/info", get(requests::info))
+            .route(
+                "/requests/:wallet",
+                get(requests::reqs_info_from_wallet::<S>),
+            )
+            .route("/request/:hash", get(requests::request_from_hash::<S>))
+            .route("/tx/:txhash", get(requests::transaction::<S>))
+            .route("/info", get(requests::info::<S>))
+            .route("/config", get(admin::bridges_config::<S>))
+            .route("/relayer/queue", get(relayer::relayer_queue::<S>))
+            .route(
+                "/collections/:address",
+                get(collections::collection_from_address::<S>),
+            )
+            .route(
+                "/tokens/:collection/:token_id",
+                get(tokens::token_info::<S>),
+            )
             .route(
                 "/stats/:eth_contract_address",
-                get(requests::contract_stats),
+                get(requests::contract_stats::<S>),
             )
             .layer(axum::middleware::from_fn(version_header))
             .with_state(app_state);
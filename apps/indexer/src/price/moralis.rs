@@ -22,10 +22,9 @@ pub struct MoralisPrice {
 
 impl MoralisPrice {
     pub fn new(api_key: Option<&str>) -> MoralisPrice {
-        let api_key = if api_key.is_none() {
-            env::var("MORALIS_API_KEY").expect("MORALIS_API_KEY environment variable")
-        } else {
-            api_key.unwrap().to_owned()
+        let api_key = match api_key {
+            Some(k) => k.to_owned(),
+            None => env::var("MORALIS_API_KEY").expect("MORALIS_API_KEY environment variable"),
         };
         let client = reqwest::Client::new();
         let mut headers = HeaderMap::new();
@@ -37,8 +36,7 @@ impl MoralisPrice {
 
     pub async fn get_price(&self, token: &str, block: Option<u64>) -> Result<String> {
         let base_url = "https://deep-index.moralis.io/api/v2.2/erc20";
-        let url = if block.is_some() {
-            let block = block.unwrap();
+        let url = if let Some(block) = block {
             format!("{base_url}/{token}/price?chain=eth&to_block={block}")
         } else {
             format!("{base_url}/{token}/price?chain=eth")
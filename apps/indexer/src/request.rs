@@ -31,7 +31,6 @@ pub struct Event {
     pub tx_hash: String,
 }
 
-///
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum EventLabel {
@@ -45,7 +44,6 @@ pub enum EventLabel {
     TransitErrorL2L1,
 }
 
-///
 impl ToString for EventLabel {
     fn to_string(&self) -> String {
         match self {
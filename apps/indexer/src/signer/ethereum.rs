@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::signers::{to_eip155_v, LocalWallet, Signer};
+use ethers::types::{
+    transaction::{eip2718::TypedTransaction, eip712::Eip712},
+    Address, Signature, H256,
+};
+use ethers::utils::hash_message;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{read_password, remote::RemoteSigner, HashSigner, Secp256k1, SignerError};
+use crate::config::{ChainConfig, SignerConfig};
+
+#[async_trait]
+impl HashSigner<Secp256k1> for LocalWallet {
+    async fn public_key(&self) -> Result<Address> {
+        Ok(self.address())
+    }
+
+    async fn sign_hash(&self, hash: &H256) -> Result<Signature> {
+        Ok(LocalWallet::sign_hash(self, *hash)?)
+    }
+}
+
+/// Ethereum signer of the relayer transactions, usable with `SignerMiddleware`.
+#[derive(Clone)]
+pub struct EthereumSigner {
+    signer: Arc<dyn HashSigner<Secp256k1>>,
+    address: Address,
+    chain_id: u64,
+}
+
+impl fmt::Debug for EthereumSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EthereumSigner")
+            .field("address", &self.address)
+            .field("chain_id", &self.chain_id)
+            .finish()
+    }
+}
+
+impl EthereumSigner {
+    pub async fn new(signer: Arc<dyn HashSigner<Secp256k1>>, chain_id: u64) -> Result<Self> {
+        let address = signer.public_key().await?;
+
+        Ok(EthereumSigner {
+            signer,
+            address,
+            chain_id,
+        })
+    }
+
+    /// Returns the signer configured for the chain, if any.
+    pub async fn from_config(config: &ChainConfig, chain_id: u64) -> Result<Option<Self>> {
+        let signer: Arc<dyn HashSigner<Secp256k1>> =
+            match (&config.signer, &config.account_private_key) {
+                (
+                    Some(SignerConfig::Keystore {
+                        path,
+                        password_file,
+                    }),
+                    _,
+                ) => Arc::new(LocalWallet::decrypt_keystore(
+                    path,
+                    read_password(password_file)?,
+                )?),
+                (
+                    Some(SignerConfig::Remote {
+                        url,
+                        key_id,
+                        auth_token_file,
+                    }),
+                    _,
+                ) => Arc::new(RemoteSigner::new(url, key_id, auth_token_file.as_deref())?),
+                (None, Some(pk)) => Arc::new(pk.parse::<LocalWallet>()?),
+                (None, None) => return Ok(None),
+            };
+
+        Ok(Some(EthereumSigner::new(signer, chain_id).await?))
+    }
+
+    async fn sign(&self, hash: H256) -> Result<Signature, SignerError> {
+        let mut signature = self.signer.sign_hash(&hash).await?;
+        signature.v = normalize_v(signature.v)?;
+
+        if signature.recover(hash).map_err(anyhow::Error::from)? != self.address {
+            return Err(anyhow!("Signature not matching the address {:?}", self.address).into());
+        }
+
+        Ok(signature)
+    }
+}
+
+/// Returns the recovery id `v` as 27 or 28, accepting signers returning 0 or 1.
+fn normalize_v(v: u64) -> Result<u64> {
+    match v {
+        0 | 1 => Ok(v + 27),
+        27 | 28 => Ok(v),
+        _ => Err(anyhow!("Invalid signature recovery id {}", v)),
+    }
+}
+
+#[async_trait]
+impl Signer for EthereumSigner {
+    type Error = SignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        self.sign(hash_message(message)).await
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        // The sighash must have the same chain id as v in the signature.
+        let chain_id = tx.chain_id().map(|id| id.as_u64()).unwrap_or(self.chain_id);
+        let mut tx = tx.clone();
+        tx.set_chain_id(chain_id);
+
+        let mut signature = self.sign(tx.sighash()).await?;
+        signature.v = to_eip155_v(signature.v as u8 - 27, chain_id);
+
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        let encoded = payload
+            .encode_eip712()
+            .map_err(|e| anyhow!("Eip712 encoding: {}", e))?;
+
+        self.sign(H256::from(encoded)).await
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_v() {
+        assert_eq!(normalize_v(0).unwrap(), 27);
+        assert_eq!(normalize_v(1).unwrap(), 28);
+        assert_eq!(normalize_v(27).unwrap(), 27);
+        assert_eq!(normalize_v(28).unwrap(), 28);
+        assert!(normalize_v(2).is_err());
+        assert!(normalize_v(37).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! Signers of the relayer transactions.
+//!
+//! The keys of the relayer are used through the `HashSigner` trait, which is
+//! implemented by the local keys (loaded from an encrypted JSON keystore) and by
+//! the HTTP remote signer. The chain specific signers adapt a `HashSigner` to the
+//! signer traits expected by `ethers` and `starknet`.
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::types::{Address, Signature as EthSignature, H256};
+use std::fmt;
+use std::fs;
+
+pub mod ethereum;
+pub mod remote;
+pub mod starknet;
+
+/// Curve of the keys of a chain, with the types involved in a signature.
+pub trait Curve: Send + Sync + 'static {
+    // Name of the curve in the remote signer protocol.
+    const NAME: &'static str;
+
+    type Hash: Send + Sync;
+    type PublicKey: Send;
+    type Signature: Send;
+}
+
+/// Ethereum keys, identified by their address.
+pub struct Secp256k1;
+
+impl Curve for Secp256k1 {
+    const NAME: &'static str = "secp256k1";
+
+    type Hash = H256;
+    type PublicKey = Address;
+    type Signature = EthSignature;
+}
+
+/// Starknet keys.
+pub struct Stark;
+
+impl Curve for Stark {
+    const NAME: &'static str = "stark";
+
+    type Hash = ::starknet::core::types::FieldElement;
+    type PublicKey = ::starknet::core::types::FieldElement;
+    type Signature = ::starknet::core::crypto::Signature;
+}
+
+/// Signer holding a key of the relayer, without exposing it.
+#[async_trait]
+pub trait HashSigner<C: Curve>: Send + Sync {
+    /// Returns the address (ethereum) or the public key (starknet) of the key.
+    async fn public_key(&self) -> Result<C::PublicKey>;
+
+    /// Signs the hash.
+    async fn sign_hash(&self, hash: &C::Hash) -> Result<C::Signature>;
+}
+
+/// Error of the chain specific signers, which must implement `std::error::Error`.
+#[derive(Debug)]
+pub struct SignerError(anyhow::Error);
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.0)
+    }
+}
+
+impl std::error::Error for SignerError {}
+
+impl From<anyhow::Error> for SignerError {
+    fn from(e: anyhow::Error) -> Self {
+        SignerError(e)
+    }
+}
+
+/// Reads a secret (keystore password or auth token) from a file, ignoring the trailing new line.
+fn read_password(password_file: &str) -> Result<String> {
+    let password = fs::read_to_string(password_file)?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
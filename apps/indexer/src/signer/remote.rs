@@ -0,0 +1,352 @@
+//! Client of an HTTP remote signer, holding the keys of the relayer.
+//!
+//! The remote signer exposes two endpoints, both taking a JSON body with the
+//! `key_id` and the `curve` (`secp256k1` or `stark`) of the key, and authenticated
+//! with an `Authorization: Bearer <token>` header when a token is configured:
+//!
+//! * `POST /public_key` returns `{ "public_key": "0x..." }`, the address of
+//!   an ethereum key or the public key of a starknet key.
+//! * `POST /sign` with the `hash` to sign (`0x...`, 32 bytes) returns `{ "signature": "0x..." }`,
+//!   `r`, `s` and `v` (0/1 or 27/28) on 65 bytes for ethereum, `r` and `s` on 64 bytes for starknet.
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::types::{Address, Signature as EthSignature, H256};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use starknet::core::{crypto::Signature as StarkSignature, types::FieldElement};
+use std::str::FromStr;
+use std::time::Duration;
+use url::Url;
+
+use super::{read_password, Curve, HashSigner, Secp256k1, Stark};
+
+// Timeout in seconds of a request to the remote signer.
+const REQUEST_TIMEOUT: u64 = 30;
+
+/// Body of the remote signer requests.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoteSignerRequest {
+    pub key_id: String,
+    pub curve: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicKeyResponse {
+    pub public_key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignResponse {
+    pub signature: String,
+}
+
+pub struct RemoteSigner {
+    url: Url,
+    key_id: String,
+    // Bearer token sent with each request, if any.
+    auth_token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl RemoteSigner {
+    /// The auth token is read from `auth_token_file`, if any.
+    pub fn new(url: &str, key_id: &str, auth_token_file: Option<&str>) -> Result<RemoteSigner> {
+        let auth_token = auth_token_file.map(read_password).transpose()?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT))
+            .build()?;
+
+        Ok(RemoteSigner {
+            url: Url::parse(url)?,
+            key_id: key_id.to_string(),
+            auth_token,
+            client,
+        })
+    }
+
+    async fn post<C: Curve, T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        hash: Option<String>,
+    ) -> Result<T> {
+        let body = RemoteSignerRequest {
+            key_id: self.key_id.clone(),
+            curve: C::NAME.to_string(),
+            hash,
+        };
+
+        let mut request = self.client.post(self.url.join(endpoint)?).json(&body);
+        if let Some(token) = &self.auth_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?.error_for_status()?;
+
+        Ok(response.json::<T>().await?)
+    }
+
+    async fn sign<C: Curve>(&self, hash: String) -> Result<String> {
+        let response: SignResponse = self.post::<C, _>("sign", Some(hash)).await?;
+        Ok(response.signature)
+    }
+
+    async fn public_key<C: Curve>(&self) -> Result<String> {
+        let response: PublicKeyResponse = self.post::<C, _>("public_key", None).await?;
+        Ok(response.public_key)
+    }
+}
+
+#[async_trait]
+impl HashSigner<Secp256k1> for RemoteSigner {
+    async fn public_key(&self) -> Result<Address> {
+        Ok(Address::from_str(&self.public_key::<Secp256k1>().await?)?)
+    }
+
+    async fn sign_hash(&self, hash: &H256) -> Result<EthSignature> {
+        let signature = self.sign::<Secp256k1>(format!("{:#x}", hash)).await?;
+        Ok(EthSignature::from_str(&signature)?)
+    }
+}
+
+#[async_trait]
+impl HashSigner<Stark> for RemoteSigner {
+    async fn public_key(&self) -> Result<FieldElement> {
+        Ok(FieldElement::from_hex_be(
+            &self.public_key::<Stark>().await?,
+        )?)
+    }
+
+    async fn sign_hash(&self, hash: &FieldElement) -> Result<StarkSignature> {
+        let signature = self.sign::<Stark>(format!("{:#x}", hash)).await?;
+        decode_stark_signature(&signature)
+    }
+}
+
+/// Encodes the starknet signature as `r` and `s` on 32 bytes each.
+pub fn encode_stark_signature(signature: &StarkSignature) -> String {
+    let bytes = [signature.r.to_bytes_be(), signature.s.to_bytes_be()].concat();
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("0x{}", hex)
+}
+
+/// Decodes a starknet signature encoded by `encode_stark_signature`.
+pub fn decode_stark_signature(signature: &str) -> Result<StarkSignature> {
+    let hex = signature.strip_prefix("0x").unwrap_or(signature);
+    if hex.len() != 128 || !hex.is_ascii() {
+        return Err(anyhow!("Invalid starknet signature {:?}", signature));
+    }
+
+    Ok(StarkSignature {
+        r: FieldElement::from_hex_be(&hex[..64])?,
+        s: FieldElement::from_hex_be(&hex[64..])?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ChainConfig, SignerConfig};
+    use crate::signer::{ethereum::EthereumSigner, starknet::StarknetSigner};
+    use axum::{
+        extract::State,
+        http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+        routing::post,
+        Json, Router, Server,
+    };
+    use ethers::signers::{LocalWallet, Signer as _};
+    use ethers::types::{transaction::eip2718::TypedTransaction, TransactionRequest};
+    use starknet::signers::{Signer as _, SigningKey};
+    use std::sync::Arc;
+
+    type Keys = Arc<(LocalWallet, SigningKey)>;
+
+    const AUTH_TOKEN: &str = "token";
+
+    fn check_auth(headers: &HeaderMap) -> Result<(), StatusCode> {
+        match headers.get(AUTHORIZATION) {
+            Some(h) if h == format!("Bearer {}", AUTH_TOKEN).as_str() => Ok(()),
+            _ => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+
+    async fn mock_public_key(
+        State(keys): State<Keys>,
+        headers: HeaderMap,
+        Json(req): Json<RemoteSignerRequest>,
+    ) -> Result<Json<PublicKeyResponse>, StatusCode> {
+        check_auth(&headers)?;
+        let public_key = match req.curve.as_str() {
+            "secp256k1" => format!("{:#x}", keys.0.address()),
+            "stark" => format!("{:#x}", keys.1.verifying_key().scalar()),
+            _ => return Err(StatusCode::BAD_REQUEST),
+        };
+
+        Ok(Json(PublicKeyResponse { public_key }))
+    }
+
+    async fn mock_sign(
+        State(keys): State<Keys>,
+        headers: HeaderMap,
+        Json(req): Json<RemoteSignerRequest>,
+    ) -> Result<Json<SignResponse>, StatusCode> {
+        check_auth(&headers)?;
+        let hash = req.hash.ok_or(StatusCode::BAD_REQUEST)?;
+
+        let signature = match req.curve.as_str() {
+            "secp256k1" => {
+                let hash = H256::from_str(&hash).map_err(|_| StatusCode::BAD_REQUEST)?;
+                let signature = keys.0.sign_hash(hash).unwrap();
+                format!("0x{}", signature)
+            }
+            "stark" => {
+                let hash = FieldElement::from_hex_be(&hash).map_err(|_| StatusCode::BAD_REQUEST)?;
+                encode_stark_signature(&keys.1.sign(&hash).unwrap())
+            }
+            _ => return Err(StatusCode::BAD_REQUEST),
+        };
+
+        Ok(Json(SignResponse { signature }))
+    }
+
+    /// Starts a local remote signer holding the keys, and returns its url.
+    fn mock_server(keys: Keys) -> String {
+        let app = Router::new()
+            .route("/public_key", post(mock_public_key))
+            .route("/sign", post(mock_sign))
+            .with_state(keys);
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(
+            Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        url
+    }
+
+    fn keys() -> Keys {
+        let secret = FieldElement::from_hex_be("0x1234").unwrap();
+        let wallet = LocalWallet::from_bytes(&secret.to_bytes_be()).unwrap();
+        Arc::new((wallet, SigningKey::from_secret_scalar(secret)))
+    }
+
+    fn chain_config(signer: SignerConfig) -> ChainConfig {
+        ChainConfig {
+            rpc_url: String::from("http://127.0.0.1:8545"),
+            from_block: String::from("0"),
+            to_block: None,
+            bridge_address: String::from("0x1"),
+            fetch_interval: 1,
+            messaging_address: None,
+            messaging_timeout: None,
+            account_address: None,
+            account_private_key: None,
+            signer: Some(signer),
+            cooling_down: 0,
+            confirmation_depth: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer() {
+        let keys = keys();
+        let url = mock_server(keys.clone());
+        let token_file =
+            std::env::temp_dir().join(format!("starklane_token_{}", std::process::id()));
+        std::fs::write(&token_file, format!("{}\n", AUTH_TOKEN)).unwrap();
+
+        let unauthenticated = chain_config(SignerConfig::Remote {
+            url: url.clone(),
+            key_id: String::from("relayer"),
+            auth_token_file: None,
+        });
+        assert!(EthereumSigner::from_config(&unauthenticated, 5)
+            .await
+            .is_err());
+
+        let config = chain_config(SignerConfig::Remote {
+            url,
+            key_id: String::from("relayer"),
+            auth_token_file: Some(token_file.to_string_lossy().to_string()),
+        });
+
+        let eth_signer = EthereumSigner::from_config(&config, 5)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(eth_signer.address(), keys.0.address());
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::zero())
+            .nonce(3)
+            .gas_price(100)
+            .into();
+        assert_eq!(
+            eth_signer.sign_transaction(&tx).await.unwrap(),
+            keys.0
+                .clone()
+                .with_chain_id(5u64)
+                .sign_transaction(&tx)
+                .await
+                .unwrap()
+        );
+
+        let sn_signer = StarknetSigner::from_config(&config).unwrap().unwrap();
+        let public_key = sn_signer.get_public_key().await.unwrap();
+        assert_eq!(public_key.scalar(), keys.1.verifying_key().scalar());
+
+        let hash = FieldElement::from_hex_be("0xabcd").unwrap();
+        let signature = sn_signer.sign_hash(&hash).await.unwrap();
+        assert!(public_key.verify(&hash, &signature).unwrap());
+
+        std::fs::remove_file(token_file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_keystore_signer() {
+        let keys = keys();
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("starklane_keystore_{}", std::process::id()));
+        let password_file = dir.join(format!("starklane_password_{}", std::process::id()));
+
+        keys.1.save_as_keystore(&path, "secret").unwrap();
+        std::fs::write(&password_file, "secret\n").unwrap();
+
+        let config = chain_config(SignerConfig::Keystore {
+            path: path.to_string_lossy().to_string(),
+            password_file: password_file.to_string_lossy().to_string(),
+        });
+
+        let eth_signer = EthereumSigner::from_config(&config, 5)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(eth_signer.address(), keys.0.address());
+
+        let sn_signer = StarknetSigner::from_config(&config).unwrap().unwrap();
+        let public_key = sn_signer.get_public_key().await.unwrap();
+        assert_eq!(public_key.scalar(), keys.1.verifying_key().scalar());
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(password_file).unwrap();
+    }
+
+    #[test]
+    fn test_stark_signature_encoding() {
+        let signature = StarkSignature {
+            r: FieldElement::from_hex_be("0x1").unwrap(),
+            s: FieldElement::from_hex_be("0x2").unwrap(),
+        };
+
+        let encoded = encode_stark_signature(&signature);
+        assert_eq!(encoded.len(), 130);
+
+        let decoded = decode_stark_signature(&encoded).unwrap();
+        assert_eq!(decoded.r, signature.r);
+        assert_eq!(decoded.s, signature.s);
+        assert!(decode_stark_signature("0x12").is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use starknet::core::{crypto::Signature, types::FieldElement};
+use starknet::signers::{Signer, SigningKey, VerifyingKey};
+use std::sync::Arc;
+
+use super::{read_password, remote::RemoteSigner, HashSigner, SignerError, Stark};
+use crate::config::{ChainConfig, SignerConfig};
+
+#[async_trait]
+impl HashSigner<Stark> for SigningKey {
+    async fn public_key(&self) -> Result<FieldElement> {
+        Ok(self.verifying_key().scalar())
+    }
+
+    async fn sign_hash(&self, hash: &FieldElement) -> Result<Signature> {
+        Ok(self.sign(hash)?)
+    }
+}
+
+/// Starknet signer of the relayer transactions, usable with `SingleOwnerAccount`.
+#[derive(Clone)]
+pub struct StarknetSigner {
+    signer: Arc<dyn HashSigner<Stark>>,
+}
+
+impl StarknetSigner {
+    pub fn new(signer: Arc<dyn HashSigner<Stark>>) -> Self {
+        StarknetSigner { signer }
+    }
+
+    /// Returns the signer configured for the chain, if any.
+    pub fn from_config(config: &ChainConfig) -> Result<Option<Self>> {
+        let signer: Arc<dyn HashSigner<Stark>> = match (&config.signer, &config.account_private_key)
+        {
+            (
+                Some(SignerConfig::Keystore {
+                    path,
+                    password_file,
+                }),
+                _,
+            ) => Arc::new(SigningKey::from_keystore(
+                path,
+                &read_password(password_file)?,
+            )?),
+            (
+                Some(SignerConfig::Remote {
+                    url,
+                    key_id,
+                    auth_token_file,
+                }),
+                _,
+            ) => Arc::new(RemoteSigner::new(url, key_id, auth_token_file.as_deref())?),
+            (None, Some(pk)) => Arc::new(SigningKey::from_secret_scalar(
+                FieldElement::from_hex_be(pk)?,
+            )),
+            (None, None) => return Ok(None),
+        };
+
+        Ok(Some(StarknetSigner::new(signer)))
+    }
+}
+
+#[async_trait]
+impl Signer for StarknetSigner {
+    type GetPublicKeyError = SignerError;
+    type SignError = SignerError;
+
+    async fn get_public_key(&self) -> Result<VerifyingKey, Self::GetPublicKeyError> {
+        Ok(VerifyingKey::from_scalar(self.signer.public_key().await?))
+    }
+
+    async fn sign_hash(&self, hash: &FieldElement) -> Result<Signature, Self::SignError> {
+        Ok(self.signer.sign_hash(hash).await?)
+    }
+}
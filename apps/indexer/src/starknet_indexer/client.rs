@@ -1,32 +1,39 @@
 use crate::config::ChainConfig;
+use crate::signer::starknet::StarknetSigner;
 use anyhow::{anyhow, Result};
 use regex::Regex;
 use starknet::{
     accounts::{Account, Call, SingleOwnerAccount},
     core::{types::FieldElement, types::*},
     providers::{jsonrpc::HttpTransport, AnyProvider, JsonRpcClient, Provider},
-    signers::{LocalWallet, SigningKey},
 };
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use tokio::time::{self, Duration};
 use url::Url;
 
-///
+// Max block range used to fetch starknet events.
+// All the events of the range are kept in memory before being processed.
+const BLOCKS_MAX_RANGE: u64 = 1000;
+
+// Interval in seconds between two checks of a transaction receipt.
+const RECEIPT_POLL_INTERVAL: u64 = 5;
+// Number of checks of a transaction receipt before giving up.
+const RECEIPT_MAX_ATTEMPTS: u64 = 60;
+
 pub struct StarknetClient {
     chain_id: FieldElement,
     provider: AnyProvider,
-    wallet: Option<LocalWallet>,
+    signer: Option<StarknetSigner>,
     account_address: Option<FieldElement>,
     bridge_address: FieldElement,
 }
 
 impl StarknetClient {
-    ///
     pub async fn new(config: ChainConfig) -> Result<StarknetClient> {
         let rpc_url = Url::parse(&config.rpc_url)?;
         let provider = AnyProvider::JsonRpcHttp(JsonRpcClient::new(HttpTransport::new(rpc_url)));
 
-        let wallet = StarknetClient::wallet_from_private_key(&config.account_private_key);
+        let signer = StarknetSigner::from_config(&config)?;
         let chain_id = provider.chain_id().await?;
         let account_address = if let Some(ac) = &config.account_address {
             Some(FieldElement::from_hex_be(ac)?)
@@ -38,14 +45,13 @@ impl StarknetClient {
 
         Ok(StarknetClient {
             provider,
-            wallet,
+            signer,
             account_address,
             chain_id,
             bridge_address,
         })
     }
 
-    ///
     pub async fn block_id_to_u64(&self, id: &BlockId) -> Result<u64> {
         match id {
             BlockId::Tag(BlockTag::Latest) => Ok(self.provider.block_number().await?),
@@ -54,7 +60,6 @@ impl StarknetClient {
         }
     }
 
-    ///
     pub fn parse_block_id(&self, id: &str) -> Result<BlockId> {
         let regex_block_number = Regex::new("^[0-9]{1,}$").unwrap();
 
@@ -69,30 +74,54 @@ impl StarknetClient {
         }
     }
 
-    /// On Starknet, a chunk size limits the maximum number of events
-    /// that can be retrieved with one call.
-    /// To ensure all events are fetched, we must ensure all events pages
-    /// are correctly fechted.
+    /// Returns the status of the given block.
+    pub async fn block_status(&self, block_number: u64) -> Result<BlockStatus> {
+        match self
+            .provider
+            .get_block_with_tx_hashes(BlockId::Number(block_number))
+            .await?
+        {
+            MaybePendingBlockWithTxHashes::Block(b) => Ok(b.status),
+            MaybePendingBlockWithTxHashes::PendingBlock(_) => Ok(BlockStatus::Pending),
+        }
+    }
+
+    /// Returns the timestamp of the given block.
+    pub async fn get_block_timestamp(&self, block_number: u64) -> Result<u64> {
+        match self
+            .provider
+            .get_block_with_tx_hashes(BlockId::Number(block_number))
+            .await?
+        {
+            MaybePendingBlockWithTxHashes::Block(b) => Ok(b.timestamp),
+            MaybePendingBlockWithTxHashes::PendingBlock(b) => Ok(b.timestamp),
+        }
+    }
+
+    /// Fetches the events of the next slice of blocks, starting at `from_block`
+    /// and containing at most `BLOCKS_MAX_RANGE` blocks, without going beyond `to_block`.
     ///
-    /// TODO: for now this version is ok, but it can be RAM consuming
-    /// as the events are accumulated before this function returns.
-    /// We can think of an other version that returns each page, and let
-    /// the caller process the pages.
+    /// On Starknet, a chunk size limits the maximum number of events
+    /// that can be retrieved with one call. All the pages of the slice are fetched
+    /// to ensure the events of a block are complete, but the slice size keeps
+    /// the memory usage bounded. The caller is expected to process the returned events
+    /// and to call this function again from the block following the last block fetched.
     ///
-    /// TODO: change return type to HashMap instead of vector, to have the block
-    /// number as a key.
+    /// Returns the events grouped by block number, and the last block fetched.
     pub async fn fetch_events(
         &self,
-        from_block: BlockId,
-        to_block: BlockId,
-    ) -> Result<HashMap<u64, Vec<EmittedEvent>>> {
-        log::info!("Starknet fetching blocks {:?} - {:?}", from_block, to_block);
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<(BTreeMap<u64, Vec<EmittedEvent>>, u64)> {
+        let last_block = to_block.min(from_block + BLOCKS_MAX_RANGE - 1);
+
+        log::info!("Starknet fetching blocks {} - {}", from_block, last_block);
 
-        let mut events: HashMap<u64, Vec<EmittedEvent>> = HashMap::new();
+        let mut events: BTreeMap<u64, Vec<EmittedEvent>> = BTreeMap::new();
 
         let filter = EventFilter {
-            from_block: Some(from_block),
-            to_block: Some(to_block),
+            from_block: Some(BlockId::Number(from_block)),
+            to_block: Some(BlockId::Number(last_block)),
             address: Some(self.bridge_address),
             keys: None,
         };
@@ -106,12 +135,9 @@ impl StarknetClient {
                 .get_events(filter.clone(), continuation_token, chunk_size)
                 .await?;
 
-            event_page.events.iter().for_each(|e| {
-                events
-                    .entry(e.block_number)
-                    .and_modify(|v| v.push(e.clone()))
-                    .or_insert(vec![e.clone()]);
-            });
+            for e in event_page.events {
+                events.entry(e.block_number).or_default().push(e);
+            }
 
             continuation_token = event_page.continuation_token;
 
@@ -120,31 +146,14 @@ impl StarknetClient {
             }
         }
 
-        Ok(events)
+        Ok((events, last_block))
     }
 
-    /// Returns a local wallet from a private key, if provided.
-    fn wallet_from_private_key(private_key: &Option<String>) -> Option<LocalWallet> {
-        if let Some(pk) = private_key {
-            let private_key = match FieldElement::from_hex_be(pk) {
-                Ok(p) => p,
-                Err(e) => {
-                    log::error!("Error importing private key: {:?}", e);
-                    return None;
-                }
-            };
-            let key = SigningKey::from_secret_scalar(private_key);
-            Some(LocalWallet::from_signing_key(key))
-        } else {
-            None
-        }
-    }
-
-    ///
-    pub async fn invoke_tx(&self, calls: Vec<Call>) -> Result<()> {
-        let signer = match &self.wallet {
-            Some(w) => Arc::new(w),
-            None => anyhow::bail!("A private key is required to send transaction on starknet!"),
+    /// Sends the calls in one invoke transaction, and returns the transaction hash.
+    pub async fn invoke_tx(&self, calls: Vec<Call>) -> Result<FieldElement> {
+        let signer = match &self.signer {
+            Some(s) => s.clone(),
+            None => anyhow::bail!("A signer is required to send transaction on starknet!"),
         };
 
         let account_address = match self.account_address {
@@ -161,11 +170,40 @@ impl StarknetClient {
 
         let execution = account.execute(calls).fee_estimate_multiplier(1.5f64);
         let estimated_fee = (execution.estimate_fee().await?.overall_fee) * 3 / 2;
-        let _tx = execution.max_fee(estimated_fee.into()).send().await?;
+        let tx = execution.max_fee(estimated_fee.into()).send().await?;
 
-        //println!("InvokeTX: {:?}", tx);
+        Ok(tx.transaction_hash)
+    }
+
+    /// Waits for the transaction to be accepted on L2, and returns
+    /// an error if it is rejected.
+    ///
+    /// Returns `false` if no receipt is available in time, as the transaction
+    /// may still be accepted later.
+    pub async fn wait_for_receipt(&self, tx_hash: FieldElement) -> Result<bool> {
+        for _ in 0..RECEIPT_MAX_ATTEMPTS {
+            time::sleep(Duration::from_secs(RECEIPT_POLL_INTERVAL)).await;
+
+            match self.provider.get_transaction_receipt(tx_hash).await {
+                Ok(MaybePendingTransactionReceipt::Receipt(TransactionReceipt::Invoke(r))) => {
+                    match r.status {
+                        TransactionStatus::Rejected => {
+                            anyhow::bail!("Transaction {:#064x} rejected", tx_hash)
+                        }
+                        TransactionStatus::Pending => (),
+                        _ => return Ok(true),
+                    }
+                }
+                Ok(MaybePendingTransactionReceipt::Receipt(r)) => {
+                    anyhow::bail!("Unexpected receipt for {:#064x}: {:?}", tx_hash, r)
+                }
+                Ok(MaybePendingTransactionReceipt::PendingReceipt(_)) => (),
+                // The transaction may not be known yet by the node.
+                Err(e) => log::debug!("No receipt yet for {:#064x}: {:?}", tx_hash, e),
+            }
+        }
 
-        Ok(())
+        Ok(false)
     }
 
     /* Example of a call with invoke:
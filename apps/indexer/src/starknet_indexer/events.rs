@@ -1,7 +1,9 @@
-use crate::storage::{BridgeChain, CrossChainTx, CrossChainTxKind, Event, EventLabel, Request};
+use crate::storage::{
+    AdminEvent, AdminEventKind, BridgeChain, CollectionContract, CrossChainTx, CrossChainTxKind,
+    Event, EventLabel, FinalityStatus, Request,
+};
 use crate::utils::normalize_hex;
 use anyhow::{anyhow, Result};
-use serde_json::{json, Value};
 use starknet::core::{types::FieldElement, types::*};
 use starknet::macros::selector;
 
@@ -11,12 +13,10 @@ pub const DEPOSIT_REQUEST_INITIATED_SELECTOR: &str =
 pub const WITHDRAW_REQUEST_COMPLETED_SELECTOR: &str =
     "0x132aab9714c265c8ad151ce006bb91691100722ddec42e7ee96dc9dfa9e741c";
 
-pub const REQUEST_HEADER_WITHDRAW_AUTO: u128 = 0x01000000;
-pub const REQUEST_HEADER_BURN_AUTO: u128 = 0x010000;
-
-///
 pub fn get_store_data(
     event: EmittedEvent,
+    event_index: u64,
+    finality: FinalityStatus,
 ) -> Result<(Option<Request>, Option<Event>, Option<CrossChainTx>)> {
     // keys[0] -> selector.
     // keys[1,2] -> req hash.
@@ -31,7 +31,6 @@ pub fn get_store_data(
 
     let hash = u256_to_hex(&event.keys[1..])?;
     let block_timestamp = event.keys[3];
-    let request_header = event.data[0];
 
     let mut store_event = Event {
         req_hash: hash.clone(),
@@ -40,6 +39,8 @@ pub fn get_store_data(
         block_number: event.block_number,
         tx_hash: felt_to_hex(&event.transaction_hash),
         price: None,
+        finality: Some(finality),
+        log_index: Some(event_index),
     };
 
     let tx;
@@ -50,11 +51,7 @@ pub fn get_store_data(
 
             let request = request_from_event_data(&store_event.label, event.data)?;
 
-            tx = get_xchain_tx_withdraw(
-                request_header,
-                request.hash.clone(),
-                request.content.clone(),
-            )?;
+            tx = get_xchain_tx_withdraw(&request)?;
 
             assert_eq!(request.hash, store_event.req_hash);
             Ok((Some(request), Some(store_event), tx))
@@ -64,7 +61,7 @@ pub fn get_store_data(
 
             let request = request_from_event_data(&store_event.label, event.data)?;
 
-            // TODO: burn txs.
+            // Tokens escrowed on L1 can't be burnt by the indexer.
 
             assert_eq!(request.hash, store_event.req_hash);
             Ok((Some(request), Some(store_event), None))
@@ -73,68 +70,123 @@ pub fn get_store_data(
     }
 }
 
-/// From the raw buffer in the event data, parse the request fields
-/// required to build `Request`.
-fn request_from_event_data(event_label: &EventLabel, data: Vec<FieldElement>) -> Result<Request> {
-    // First 7 felts are the fixed size part of the request.
-    if data.len() < 7 {
-        return Err(anyhow!(
-            "Request can't be extracted from event data: {:?}",
-            data
-        ));
+/// Returns the collection registered by the event, if any.
+///
+/// The request hash and the timestamp are not emitted with those events,
+/// they must be set by the caller.
+pub fn get_collection_data(event: &EmittedEvent) -> Result<Option<CollectionContract>> {
+    // keys[0] -> selector.
+    // keys[1] -> collection l1.
+    // keys[2] -> collection l2.
+    if event.keys.len() < 3 {
+        return Ok(None);
+    }
+
+    if event.keys[0] != selector!("CollectionDeployedFromL1")
+        && event.keys[0] != selector!("L1L2CollectionMappingUpdated")
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(CollectionContract {
+        chain_src: BridgeChain::Ethereum,
+        address_src: normalize_hex(felt_to_hex(&event.keys[1]).as_str())?,
+        address_dst: normalize_hex(felt_to_hex(&event.keys[2]).as_str())?,
+        req_hash: None,
+        timestamp: 0,
+        chain: BridgeChain::Starknet,
+        block_number: event.block_number,
+    }))
+}
+
+/// Returns the administration event from the emitted event, if any.
+///
+/// The block timestamp is not emitted with those events, it must be set by the caller.
+pub fn get_admin_data(event: &EmittedEvent, event_index: u64) -> Result<Option<AdminEvent>> {
+    if event.keys.is_empty() {
+        return Ok(None);
     }
 
-    let content_array: Vec<Value> = data.iter().map(|f| json!(felt_to_hex(f))).collect();
-    let content = serde_json::to_string(&content_array)?;
-
-    let req = match event_label {
-        EventLabel::DepositInitiatedL2 => Request {
-            hash: u256_to_hex(&data[1..])?, // first felt is the header.
-            chain_src: BridgeChain::Starknet,
-            collection_src: normalize_hex(felt_to_hex(&data[4]).as_str())?, // collection l2
-            collection_dst: normalize_hex(felt_to_hex(&data[3]).as_str())?, // collection l1
-            from: normalize_hex(felt_to_hex(&data[6]).as_str())?,           // owner l2
-            to: normalize_hex(felt_to_hex(&data[5]).as_str())?,             // owner l1
-            content,
-        },
-        EventLabel::WithdrawCompletedL2 => Request {
-            hash: u256_to_hex(&data[1..])?, // first felt is the header.
-            chain_src: BridgeChain::Ethereum,
-            collection_src: normalize_hex(felt_to_hex(&data[3]).as_str())?, // collection l1
-            collection_dst: normalize_hex(felt_to_hex(&data[4]).as_str())?, // collection l2
-            from: normalize_hex(felt_to_hex(&data[5]).as_str())?,           // owner l1
-            to: normalize_hex(felt_to_hex(&data[6]).as_str())?,             // owner l2
-            content,
-        },
-        _ => {
-            return Err(anyhow!(
-                "EventLabel {:?} not supposed to generate a request",
-                event_label
-            ))
+    let selector = event.keys[0];
+
+    let kind = if selector == selector!("BridgeEnabled") {
+        AdminEventKind::BridgeEnabled {
+            enabled: felt_to_bool(event.data.first())?,
+        }
+    } else if selector == selector!("WhiteListEnabled") {
+        AdminEventKind::WhiteListEnabled {
+            enabled: felt_to_bool(event.data.first())?,
+        }
+    } else if selector == selector!("CollectionWhiteListUpdated") {
+        AdminEventKind::CollectionWhiteListUpdated {
+            collection: normalize_hex(&felt_to_hex(key_at(event, 1)?))?,
+            enabled: felt_to_bool(event.data.first())?,
         }
+    } else if selector == selector!("ReplacedClassHash") {
+        let class = event
+            .data
+            .get(1)
+            .ok_or_else(|| anyhow!("Missing class hash in ReplacedClassHash"))?;
+        AdminEventKind::Upgraded {
+            implementation: normalize_hex(&felt_to_hex(class))?,
+        }
+    } else if selector == selector!("BridgeL1AddressUpdated") {
+        AdminEventKind::BridgeL1AddressUpdated {
+            address: normalize_hex(&felt_to_hex(key_at(event, 1)?))?,
+        }
+    } else if selector == selector!("ERC721ClassHashUpdated") {
+        AdminEventKind::Erc721ClassHashUpdated {
+            class_hash: normalize_hex(&felt_to_hex(key_at(event, 1)?))?,
+        }
+    } else if selector == selector!("OwnershipTransferred") {
+        AdminEventKind::OwnershipTransferred {
+            previous_owner: normalize_hex(&felt_to_hex(key_at(event, 1)?))?,
+            new_owner: normalize_hex(&felt_to_hex(key_at(event, 2)?))?,
+        }
+    } else {
+        return Ok(None);
     };
 
-    Ok(req)
+    Ok(Some(AdminEvent {
+        chain: BridgeChain::Starknet,
+        kind,
+        block_number: event.block_number,
+        event_index,
+        block_timestamp: 0,
+        tx_hash: felt_to_hex(&event.transaction_hash),
+    }))
 }
 
-///
-fn get_xchain_tx_withdraw(
-    header: FieldElement,
-    req_hash: String,
-    req_content: String,
-) -> Result<Option<CrossChainTx>> {
-    // For now, header must be convertible into u128.
-    let h: u128 = header.try_into()?;
+fn key_at(event: &EmittedEvent, index: usize) -> Result<&FieldElement> {
+    event
+        .keys
+        .get(index)
+        .ok_or_else(|| anyhow!("Missing key {} in event {:?}", index, event.keys[0]))
+}
+
+fn felt_to_bool(felt: Option<&FieldElement>) -> Result<bool> {
+    match felt {
+        Some(f) => Ok(*f != FieldElement::ZERO),
+        None => Err(anyhow!("Missing boolean in event data")),
+    }
+}
 
-    let can_withdraw_auto = h & REQUEST_HEADER_WITHDRAW_AUTO == REQUEST_HEADER_WITHDRAW_AUTO;
+/// From the raw buffer in the event data, decodes the request.
+fn request_from_event_data(event_label: &EventLabel, data: Vec<FieldElement>) -> Result<Request> {
+    let content: Vec<String> = data.iter().map(felt_to_hex).collect();
+
+    Request::from_content(event_label, &content)
+}
 
-    if can_withdraw_auto {
+fn get_xchain_tx_withdraw(req: &Request) -> Result<Option<CrossChainTx>> {
+    if req.decoded()?.header.withdraw_auto {
         Ok(Some(CrossChainTx {
             chain: BridgeChain::Ethereum,
             kind: CrossChainTxKind::WithdrawAuto,
-            req_hash: req_hash.clone(),
-            req_content: req_content.clone(),
+            req_hash: req.hash.clone(),
+            req_content: req.content.clone(),
             tx_hash: String::from(""),
+            ..Default::default()
         }))
     } else {
         Ok(None)
@@ -150,7 +202,6 @@ fn u256_to_hex(felts: &[FieldElement]) -> Result<String> {
     Ok(format!("0x{:032x}{:032x}", felts[1], felts[0]))
 }
 
-///
 #[inline(always)]
 fn felt_to_hex(fe: &FieldElement) -> String {
     format!("{:#64x}", fe)
@@ -0,0 +1,337 @@
+//! Finality of the starknet blocks, and promotion of the events
+//! once their block is accepted on L1.
+use crate::storage::{
+    store::{
+        AdminEventStore, CollectionStore, CrossChainTxStore, EventStore, PendingWithdrawStore,
+        RequestStore, StarknetBridgeRequestStore, TransactionStore,
+    },
+    BridgeChain, CrossChainTxKind, CrossChainTxStatus, EventLabel, FinalityStatus,
+};
+use anyhow::Result;
+use starknet::core::types::BlockStatus;
+use std::future::Future;
+
+/// Finality of a starknet block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFinality {
+    // The block is accepted, its events are indexed with this finality status.
+    Accepted(FinalityStatus),
+    // The block will never be part of the chain, its events are dropped.
+    Rejected,
+}
+
+/// Converts the status of a block into its finality.
+pub fn block_finality(status: BlockStatus) -> BlockFinality {
+    match status {
+        BlockStatus::Pending | BlockStatus::AcceptedOnL2 => {
+            BlockFinality::Accepted(FinalityStatus::AcceptedOnL2)
+        }
+        BlockStatus::AcceptedOnL1 => BlockFinality::Accepted(FinalityStatus::AcceptedOnL1),
+        BlockStatus::Rejected => BlockFinality::Rejected,
+    }
+}
+
+/// Checks the blocks with events only accepted on L2, where `block_status` returns
+/// the current status of a block, and marks the events as accepted on L1 up to
+/// the latest block accepted on L1.
+///
+/// The events of the rejected blocks are dropped.
+pub async fn finalize_events<S, F, Fut>(store: &S, block_status: F) -> Result<()>
+where
+    S: RequestStore
+        + EventStore
+        + CrossChainTxStore
+        + PendingWithdrawStore
+        + StarknetBridgeRequestStore
+        + CollectionStore
+        + AdminEventStore
+        + TransactionStore,
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<BlockStatus>>,
+{
+    let blocks = store
+        .event_blocks_by_finality(FinalityStatus::AcceptedOnL2)
+        .await?;
+
+    // Blocks are accepted on L1 in order, we can stop at the first
+    // block that is not yet accepted on L1.
+    let mut last_finalized: Option<u64> = None;
+    for block_number in blocks {
+        match block_finality(block_status(block_number).await?) {
+            BlockFinality::Accepted(FinalityStatus::AcceptedOnL1) => {
+                last_finalized = Some(block_number)
+            }
+            BlockFinality::Accepted(FinalityStatus::AcceptedOnL2) => break,
+            BlockFinality::Rejected => {
+                let tx_store = store.begin_transaction().await?;
+                match drop_rejected_block(&tx_store, block_number).await {
+                    Ok(()) => tx_store.commit_transaction().await?,
+                    Err(e) => {
+                        if let Err(abort_err) = tx_store.abort_transaction().await {
+                            log::warn!("Failed to abort transaction: {:#}", abort_err);
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(block_number) = last_finalized {
+        log::info!(
+            "Starknet events accepted on L1 up to block {}",
+            block_number
+        );
+        store.finalize_events(block_number).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes the events of a rejected block, with the requests, pending withdraws
+/// and bridged tokens inserted for them, and the collections and administration
+/// events of the block. The auto withdraws not yet sent are abandoned.
+async fn drop_rejected_block<S>(store: &S, block_number: u64) -> Result<()>
+where
+    S: RequestStore
+        + EventStore
+        + CrossChainTxStore
+        + PendingWithdrawStore
+        + StarknetBridgeRequestStore
+        + CollectionStore
+        + AdminEventStore,
+{
+    log::warn!("Starknet block {} was rejected", block_number);
+
+    let events = store
+        .remove_block_events(BridgeChain::Starknet, block_number)
+        .await?;
+
+    for e in events {
+        log::info!("Remove rejected event: {:?}", &e);
+
+        if e.label == EventLabel::DepositInitiatedL2 {
+            store
+                .remove_pending_withdraws_by_request(&e.req_hash)
+                .await?;
+
+            if let Some(mut tx) = store
+                .tx_from_request_kind(&e.req_hash, CrossChainTxKind::WithdrawAuto)
+                .await?
+            {
+                if matches!(
+                    tx.status,
+                    CrossChainTxStatus::Queued | CrossChainTxStatus::Failed
+                ) {
+                    tx.status = CrossChainTxStatus::Abandoned;
+                    tx.last_error = Some(format!("Deposit block {} rejected", block_number));
+                    store.update_tx(tx).await?;
+                }
+            }
+        }
+
+        if e.label == EventLabel::WithdrawCompletedL2 {
+            store.remove_request(&e.tx_hash, &e.req_hash).await?;
+        }
+
+        if store.events_by_request(&e.req_hash).await?.is_empty() {
+            store.remove_req(&e.req_hash).await?;
+        }
+    }
+
+    store
+        .remove_block_collections(BridgeChain::Starknet, block_number)
+        .await?;
+
+    store
+        .remove_block_admin_events(BridgeChain::Starknet, block_number)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{
+        memory::MemoryStore, AdminEvent, AdminEventKind, CollectionContract, CrossChainTx, Event,
+        Request,
+    };
+
+    fn event(req_hash: &str, label: EventLabel, block_number: u64) -> Event {
+        Event {
+            req_hash: req_hash.to_string(),
+            label,
+            block_timestamp: 0,
+            block_number,
+            tx_hash: format!("0x{}", block_number),
+            price: None,
+            finality: Some(FinalityStatus::AcceptedOnL2),
+            log_index: None,
+        }
+    }
+
+    #[test]
+    fn test_block_finality() {
+        assert_eq!(
+            block_finality(BlockStatus::Pending),
+            BlockFinality::Accepted(FinalityStatus::AcceptedOnL2)
+        );
+        assert_eq!(
+            block_finality(BlockStatus::AcceptedOnL2),
+            BlockFinality::Accepted(FinalityStatus::AcceptedOnL2)
+        );
+        assert_eq!(
+            block_finality(BlockStatus::AcceptedOnL1),
+            BlockFinality::Accepted(FinalityStatus::AcceptedOnL1)
+        );
+        assert_eq!(
+            block_finality(BlockStatus::Rejected),
+            BlockFinality::Rejected
+        );
+    }
+
+    #[tokio::test]
+    async fn test_finalize_events() {
+        let store = MemoryStore::new();
+
+        for (hash, block_number) in [("0xa", 1), ("0xb", 2), ("0xc", 3), ("0xd", 4)] {
+            store
+                .insert_req(Request {
+                    hash: hash.to_string(),
+                    chain_src: BridgeChain::Starknet,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            store
+                .insert_event(event(hash, EventLabel::DepositInitiatedL2, block_number))
+                .await
+                .unwrap();
+        }
+        store
+            .insert_tx(CrossChainTx {
+                chain: BridgeChain::Ethereum,
+                kind: CrossChainTxKind::WithdrawAuto,
+                req_hash: String::from("0xb"),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // L1->L2 request withdrawn, a collection deployed and the bridge
+        // disabled on L2 in the same rejected block 2.
+        // ERC721 header, hash, collections, owners, empty name, symbol and base_uri,
+        // one token and empty values, uris and new owners.
+        let content: Vec<String> = [
+            "0x101", "0x2", "0x3", "0x4", "0x5", "0x6", "0x7", "0x0", "0x0", "0x0", "0x0", "0x0",
+            "0x0", "0x0", "0x0", "0x0", "0x1", "0x1", "0x0", "0x0", "0x0", "0x0",
+        ]
+        .iter()
+        .map(|f| f.to_string())
+        .collect();
+        let l1_req = Request {
+            hash: String::from("0xe"),
+            ..Request::from_content(&EventLabel::WithdrawCompletedL2, &content).unwrap()
+        };
+        store.insert_req(l1_req.clone()).await.unwrap();
+        store
+            .insert_event(event("0xe", EventLabel::WithdrawCompletedL2, 2))
+            .await
+            .unwrap();
+        store
+            .insert_request(String::from("0x2"), l1_req.clone())
+            .await
+            .unwrap();
+        store
+            .insert_collection(CollectionContract {
+                chain_src: BridgeChain::Ethereum,
+                address_src: String::from("0x100"),
+                address_dst: String::from("0x200"),
+                chain: BridgeChain::Starknet,
+                block_number: 2,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store
+            .insert_admin_event(AdminEvent {
+                chain: BridgeChain::Starknet,
+                kind: AdminEventKind::BridgeEnabled { enabled: false },
+                block_number: 2,
+                event_index: 0,
+                block_timestamp: 0,
+                tx_hash: String::from("0x2"),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .get_total_tokens_bridged_on_starknet(&l1_req.collection_src)
+                .await
+                .unwrap(),
+            1
+        );
+
+        // Block 2 was rejected, and block 4 is not yet accepted on L1.
+        let status = |n: u64| async move {
+            Ok(match n {
+                2 => BlockStatus::Rejected,
+                4 => BlockStatus::AcceptedOnL2,
+                _ => BlockStatus::AcceptedOnL1,
+            })
+        };
+        finalize_events(&store, status).await.unwrap();
+
+        let finality = |hash: &'static str| {
+            let store = &store;
+            async move {
+                store
+                    .events_by_request(hash)
+                    .await
+                    .unwrap()
+                    .first()
+                    .and_then(|e| e.finality)
+            }
+        };
+        assert_eq!(finality("0xa").await, Some(FinalityStatus::AcceptedOnL1));
+        assert_eq!(finality("0xb").await, None);
+        assert_eq!(finality("0xc").await, Some(FinalityStatus::AcceptedOnL1));
+        assert_eq!(finality("0xd").await, Some(FinalityStatus::AcceptedOnL2));
+
+        assert!(store.req_by_hash("0xb").await.unwrap().is_none());
+        let tx = store
+            .tx_from_request_kind("0xb", CrossChainTxKind::WithdrawAuto)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.status, CrossChainTxStatus::Abandoned);
+
+        assert!(store.req_by_hash("0xe").await.unwrap().is_none());
+        assert_eq!(
+            store
+                .get_total_tokens_bridged_on_starknet(&l1_req.collection_src)
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(store
+            .collection_by_address("0x200")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .admin_events(BridgeChain::Starknet)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(
+            store
+                .event_blocks_by_finality(FinalityStatus::AcceptedOnL2)
+                .await
+                .unwrap(),
+            vec![4]
+        );
+    }
+}
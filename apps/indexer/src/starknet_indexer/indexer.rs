@@ -1,24 +1,33 @@
 use super::client::StarknetClient;
 use super::events;
-use crate::config::ChainConfig;
+use super::finality::{self, BlockFinality};
+use crate::config::{ChainConfig, XchainTxConfig};
 use crate::storage::protocol::ProtocolParser;
 use crate::storage::{
+    status,
     store::{
-        BlockStore, CrossChainTxStore, EventStore, PendingWithdrawStore, RequestStore,
-        StarknetBridgeRequestStore,
+        AdminEventStore, BlockStore, CollectionStore, CrossChainTxStore, EventStore,
+        PendingWithdrawStore, RequestStore, StarknetBridgeRequestStore, TransactionStore,
     },
-    BlockIndex, BridgeChain, CrossChainTxKind, EventLabel, PendingWithdraw,
+    AdminEvent, BlockIndex, BridgeChain, CollectionContract, CrossChainTxKind, EventLabel,
+    FinalityStatus, PendingWithdraw, Request,
 };
 use crate::utils;
 use crate::ChainsBlocks;
 use anyhow::Result;
 use starknet::core::types::{BlockId, BlockTag, EmittedEvent};
-use starknet::macros::selector;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock as AsyncRwLock;
 use tokio::time::{self, Duration};
 
-///
+// Interval in seconds between two checks of the events finality.
+// Blocks are usually accepted on L1 several hours after being accepted on L2.
+const FINALITY_POLL_INTERVAL: u64 = 60;
+
+// Interval in seconds between two checks of the L1->L2 messages in transit.
+const TRANSIT_ERROR_POLL_INTERVAL: u64 = 60;
+
 pub struct StarknetIndexer<
     T: RequestStore + EventStore + BlockStore + CrossChainTxStore + PendingWithdrawStore,
 > {
@@ -27,6 +36,7 @@ pub struct StarknetIndexer<
     store: Arc<T>,
     chains_blocks: Arc<AsyncRwLock<ChainsBlocks>>,
     eth_bridge_address: String,
+    xchain_txor_config: XchainTxConfig,
 }
 
 impl<T> StarknetIndexer<T>
@@ -36,14 +46,17 @@ where
         + BlockStore
         + CrossChainTxStore
         + StarknetBridgeRequestStore
-        + PendingWithdrawStore,
+        + PendingWithdrawStore
+        + CollectionStore
+        + AdminEventStore
+        + TransactionStore,
 {
-    ///
     pub async fn new(
         config: ChainConfig,
         store: Arc<T>,
         chains_blocks: Arc<AsyncRwLock<ChainsBlocks>>,
         eth_bridge_address: String,
+        xchain_txor_config: XchainTxConfig,
     ) -> Result<StarknetIndexer<T>> {
         let client = StarknetClient::new(config.clone()).await?;
         Ok(StarknetIndexer {
@@ -52,15 +65,11 @@ where
             store,
             chains_blocks,
             eth_bridge_address,
+            xchain_txor_config,
         })
     }
 
-    ///
     pub async fn start(&self) -> Result<()> {
-        // TODO: start a loop that will check for the TXs (with request) to be sent to
-        // starknet...! (as we have here a client for starknet txs with invoke available).
-        // On starknet, it should only by auto-burn invokes.
-
         let from_block = self.client.parse_block_id(&self.config.from_block)?;
         let to_block = if let Some(to) = &self.config.to_block {
             self.client.parse_block_id(to)?
@@ -69,30 +78,41 @@ where
         };
 
         if to_block == BlockId::Tag(BlockTag::Latest) {
-            self.head_of_chain_poll(from_block).await?;
+            tokio::try_join!(
+                self.head_of_chain_poll(from_block),
+                self.finality_poll(),
+                self.transit_error_poll(),
+                self.xchain_txs_poll()
+            )?;
         } else {
-            todo!();
+            self.fetch_range(from_block, to_block).await?;
         }
 
         Ok(())
-        // TODO: as ethereum, we need to limit the number of blocks to keep being functional
-        // even if a lot's of events are emitted by Starklane.
-        // Also, this will help with transactional approach for the database, to ensure
-        // atomicity.
     }
 
+    /// Fetches and processes all the events from `from_block` to `to_block` (included),
+    /// and returns once `to_block` is reached.
     ///
-    //fn fetch_range(&self, _from_block: BlockId, _to_block: BlockId) -> Result<()> {
-    // TODO: a logic for fetching only from - to range of blocks
-    // and process associated events, and stop when to is reached.
+    /// Blocks already indexed (by an other instance of the indexer for instance)
+    /// are skipped, which allows a backfill to run alongside the head of chain polling.
+    async fn fetch_range(&self, from_block: BlockId, to_block: BlockId) -> Result<()> {
+        let from_u64 = self.client.block_id_to_u64(&from_block).await?;
+        let to_u64 = self.client.block_id_to_u64(&to_block).await?;
+
+        if from_u64 > to_u64 {
+            anyhow::bail!(
+                "Invalid block range: from_block {} is after to_block {}",
+                from_u64,
+                to_u64
+            );
+        }
 
-    // Here we can put a logic to only fetch small ranges by small ranges, until
-    // we reach to.
+        log::info!("Starknet indexing block range {} - {}", from_u64, to_u64);
+        self.index_range(from_u64, to_u64).await?;
 
-    // If the logic is simple, maybe head_of_chain_poll and this one can be the same
-    // function.
-    //Ok(())
-    //}
+        Ok(())
+    }
 
     /// Fetches all the events staring at `from_block` until head of the chain.
     /// Then polls the head of the chain.
@@ -102,7 +122,8 @@ where
             self.config.fetch_interval
         );
 
-        let mut from_u64 = self.client.block_id_to_u64(&from_block).await?;
+        let from_u64 = self.client.block_id_to_u64(&from_block).await?;
+        let mut from_u64 = self.resume_from(from_u64).await?;
         let mut need_cool_down = false;
 
         loop {
@@ -130,53 +151,203 @@ where
             };
 
             // Don't fetch if we already are on the head of the chain.
-            if from_u64 >= latest_u64 {
+            if from_u64 > latest_u64 {
                 log::info!("Nothing to fetch (from={} to={})", from_u64, latest_u64);
                 continue;
             }
 
-            let blocks_events = match self
-                .client
-                .fetch_events(BlockId::Number(from_u64), BlockId::Number(latest_u64))
-                .await
-            {
-                Ok(r) => r,
+            // If any block has an error, the range is fetched again
+            // on the next poll, from the first slice not yet indexed.
+            match self.index_range(from_u64, latest_u64).await {
+                Ok(next) => {
+                    from_u64 = next;
+
+                    // All the blocks up to `latest_u64` are now processed without gap.
+                    if let Err(e) = self
+                        .store
+                        .set_cursor(BridgeChain::Starknet, latest_u64)
+                        .await
+                    {
+                        log::warn!("Failed to save cursor at block {:#}: {:#}", latest_u64, e);
+                    }
+                }
                 Err(e) => {
                     log::error!(
-                        "Failed to fetch events for block ({:#}-{:#}): {:#}",
+                        "Failed to index blocks ({:#}-{:#}): {:#}",
                         from_u64,
                         latest_u64,
                         e
                     );
                     need_cool_down = true;
-                    continue;
                 }
             };
+        }
+    }
 
-            // log::debug!("blocks events: {:?}", blocks_events);
+    /// Returns the block to start indexing from, resuming after the
+    /// persisted cursor if it is beyond `from_block`.
+    async fn resume_from(&self, from_block: u64) -> Result<u64> {
+        match self.store.cursor(BridgeChain::Starknet).await? {
+            Some(c) if c.block_number >= from_block => {
+                log::info!("Starknet indexing resumed after block {}", c.block_number);
+                self.chains_blocks.write().await.sn = c.block_number;
+                Ok(c.block_number + 1)
+            }
+            _ => Ok(from_block),
+        }
+    }
+
+    /// Indexes the blocks from `from_block` to `to_block` (included), one slice at a time.
+    /// Each slice is processed and checkpointed as an indexed range before the next
+    /// one is fetched, and the blocks already indexed are skipped without fetching them.
+    ///
+    /// Returns the next block to be indexed.
+    async fn index_range(&self, from_block: u64, to_block: u64) -> Result<u64> {
+        let mut from = from_block;
+
+        while from <= to_block {
+            if let Some(r) = self
+                .store
+                .indexed_range(BridgeChain::Starknet, from)
+                .await?
+            {
+                log::debug!("Starknet blocks {} - {} already indexed", from, r.to_block);
+                from = r.to_block + 1;
+                continue;
+            }
+
+            let (blocks_events, last_block) = self.client.fetch_events(from, to_block).await?;
 
             for (block_number, events) in blocks_events {
-                match self.process_events(block_number, events).await {
-                    Ok(_) => (),
-                    Err(e) => {
-                        log::error!(
-                            "Failed to process events for block {:#}: {:#}",
-                            block_number,
-                            e
-                        );
-                        need_cool_down = true;
-                        continue;
-                    }
+                if let Err(e) = self.process_events(block_number, events).await {
+                    anyhow::bail!(
+                        "Failed to process events for block {:#}: {:#}",
+                        block_number,
+                        e
+                    );
                 }
             }
 
-            // The block range was fetched and processed.
-            // If any block has an error, an other instance of the indexer
-            // must be restarted on a the specific range.
-            from_u64 = latest_u64;
+            self.store
+                .insert_indexed_range(BridgeChain::Starknet, from, last_block)
+                .await?;
 
             let mut cbs = self.chains_blocks.write().await;
-            cbs.sn = from_u64;
+            cbs.sn = cbs.sn.max(last_block);
+
+            from = last_block + 1;
+        }
+
+        // Also covers the blocks skipped as already indexed.
+        let mut cbs = self.chains_blocks.write().await;
+        cbs.sn = cbs.sn.max(to_block);
+
+        Ok(from)
+    }
+
+    /// Periodically sends the cross chain transactions pending on starknet.
+    async fn xchain_txs_poll(&self) -> Result<()> {
+        loop {
+            time::sleep(Duration::from_secs(self.config.fetch_interval)).await;
+
+            if let Err(e) = self.xchain_txs_send().await {
+                log::warn!("Error sending xchain_txs {:#}", e);
+            }
+        }
+    }
+
+    /// Sends the cross chain transactions pending on starknet, waiting for the
+    /// receipt of each transaction before recording its hash.
+    async fn xchain_txs_send(&self) -> Result<()> {
+        if !self.xchain_txor_config.enabled {
+            log::debug!("xchain_txor is disabled in config, skipping");
+            return Ok(());
+        }
+
+        {
+            let cbs = self.chains_blocks.read().await;
+            if cbs.sn < self.xchain_txor_config.sn_min_block
+                || cbs.eth < self.xchain_txor_config.eth_min_block
+            {
+                log::debug!(
+                    "xchain_txor skipped due to unmet blocks requirements {:?}",
+                    cbs
+                );
+                return Ok(());
+            }
+        }
+
+        let txs = self.store.pending_xtxs(BridgeChain::Starknet).await?;
+        log::debug!("Verifying xchain_txs for starknet node [{}]", txs.len());
+
+        for tx in txs {
+            match tx.kind {
+                // Withdraw transactions are only sent on ethereum.
+                CrossChainTxKind::WithdrawAuto => {
+                    log::warn!("Unexpected withdraw auto tx on starknet {:?}", tx.req_hash)
+                }
+                // The starknet bridge doesn't expose a `burn_auto` entrypoint yet,
+                // the burn auto transactions stay queued until it does.
+                CrossChainTxKind::BurnAuto => log::debug!(
+                    "No burn_auto entrypoint on the starknet bridge, {:?} stays queued",
+                    tx.req_hash
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Periodically promotes the events to `AcceptedOnL1`
+    /// once their block is accepted on L1.
+    async fn finality_poll(&self) -> Result<()> {
+        loop {
+            time::sleep(Duration::from_secs(FINALITY_POLL_INTERVAL)).await;
+
+            if let Err(e) = self.finalize_events().await {
+                log::warn!("Failed to update events finality: {:#}", e);
+            }
+        }
+    }
+
+    /// Checks the blocks with events only accepted on L2, and marks the events
+    /// as accepted on L1 up to the latest block accepted on L1.
+    async fn finalize_events(&self) -> Result<()> {
+        finality::finalize_events(self.store.as_ref(), |n| self.client.block_status(n)).await
+    }
+
+    /// Periodically inserts a transit error event for the L1->L2 requests
+    /// not withdrawn on L2 after `messaging_timeout` seconds, if configured.
+    async fn transit_error_poll(&self) -> Result<()> {
+        let timeout = match self.config.messaging_timeout {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+
+        loop {
+            time::sleep(Duration::from_secs(TRANSIT_ERROR_POLL_INTERVAL)).await;
+
+            let errors = match status::transit_errors(
+                self.store.as_ref(),
+                BridgeChain::Ethereum,
+                timeout,
+                utils::utc_now_seconds(),
+            )
+            .await
+            {
+                Ok(e) => e,
+                Err(e) => {
+                    log::warn!("Failed to detect transit errors: {:#}", e);
+                    continue;
+                }
+            };
+
+            for event in errors {
+                log::warn!("Request {} in transit error", event.req_hash);
+                if let Err(e) = self.store.insert_event(event).await {
+                    log::warn!("Failed to insert transit error: {:#}", e);
+                }
+            }
         }
     }
 
@@ -198,69 +369,174 @@ where
             block_number
         );
 
-        // TODO: start a database transaction/session.
+        let block_hash = events.first().map(|e| format!("{:#064x}", e.block_hash));
+
+        let block_idx = BlockIndex {
+            chain: BridgeChain::Starknet,
+            block_number,
+            insert_timestamp: utils::utc_now_seconds(),
+            block_hash,
+            parent_hash: None,
+        };
+
+        let finality = match finality::block_finality(self.client.block_status(block_number).await?)
+        {
+            BlockFinality::Accepted(f) => f,
+            BlockFinality::Rejected => {
+                // The block is indexed without its events, to not be fetched again.
+                log::warn!(
+                    "Starknet block {} was rejected, {} events dropped",
+                    block_number,
+                    events.len()
+                );
+                return self.store.insert_block(block_idx).await;
+            }
+        };
+
+        // Fetched before the transaction, to not hold it during the RPC call.
+        let block_timestamp = self.admin_block_timestamp(block_number, &events).await?;
+
+        // All the writes of the block are done in one transaction, to not
+        // have partially indexed blocks.
+        let store = self.store.begin_transaction().await?;
+
+        match self
+            .store_events(&store, events, finality, block_idx, block_timestamp)
+            .await
+        {
+            Ok(()) => store.commit_transaction().await,
+            Err(e) => {
+                if let Err(abort_err) = store.abort_transaction().await {
+                    log::warn!("Failed to abort transaction: {:#}", abort_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the timestamp of the block if it has collection or administration
+    /// events, which don't emit it.
+    async fn admin_block_timestamp(
+        &self,
+        block_number: u64,
+        events: &[EmittedEvent],
+    ) -> Result<Option<u64>> {
+        let needed = events.iter().enumerate().any(|(index, e)| {
+            matches!(events::get_admin_data(e, index as u64), Ok(Some(_)))
+                || matches!(events::get_collection_data(e), Ok(Some(_)))
+        });
+
+        if !needed {
+            return Ok(None);
+        }
+
+        Ok(Some(self.client.get_block_timestamp(block_number).await?))
+    }
 
+    /// Writes the data extracted from the events of the given block into `store`.
+    /// `block_timestamp` is required for the collection and administration events.
+    async fn store_events(
+        &self,
+        store: &T,
+        events: Vec<EmittedEvent>,
+        finality: FinalityStatus,
+        block_idx: BlockIndex,
+        block_timestamp: Option<u64>,
+    ) -> Result<()> {
         let sn_bridge_address = &self.config.clone().bridge_address;
         let eth_bridge_address = &self.eth_bridge_address;
 
-        for e in events {
+        // Collections deployed from L1 are emitted in the same transaction
+        // as the withdraw request, which gives the request hash.
+        let mut collections: Vec<(String, CollectionContract)> = vec![];
+        let mut tx_requests: HashMap<String, String> = HashMap::new();
+        let mut admin_events: Vec<AdminEvent> = vec![];
+
+        for (index, e) in events.into_iter().enumerate() {
             //log::debug!("raw event\n{:?}\n", e);
             let event_selector = e.keys[0];
 
-            // TODO: some evens may be skipped like deployed collections etc..
-            // For now, we will ignore the events manually.
-            if event_selector == selector!("CollectionDeployedFromL1") {
-                continue;
+            match events::get_admin_data(&e, index as u64) {
+                Ok(Some(a)) => {
+                    admin_events.push(a);
+                    continue;
+                }
+                Ok(None) => (),
+                Err(er) => {
+                    log::warn!("Admin event not processed {:?} -> {:?}", event_selector, er);
+                    continue;
+                }
+            }
+
+            match events::get_collection_data(&e) {
+                Ok(Some(c)) => {
+                    collections.push((format!("{:#64x}", e.transaction_hash), c));
+                    continue;
+                }
+                Ok(None) => (),
+                Err(er) => {
+                    log::warn!("Collection not processed {:?} -> {:?}", event_selector, er);
+                    continue;
+                }
             }
 
-            match events::get_store_data(e) {
+            match events::get_store_data(e, index as u64, finality) {
                 Ok(store_data) => match store_data {
                     (Some(req), Some(ev), xchain_tx) => {
                         log::debug!("Request/Event/Tx\n{:?}\n{:?}\n{:?}", req, ev, xchain_tx);
+                        tx_requests.insert(ev.tx_hash.clone(), req.hash.clone());
                         log::info!("Insert event: {:?}", &ev);
-                        self.store.insert_event(ev.clone()).await?;
-
-                        if self.store.req_by_hash(&req.hash).await?.is_none() {
-                            self.store.insert_req(req.clone()).await?;
+                        store.insert_event(ev.clone()).await?;
+
+                        // The request is created at its first event, which may be indexed
+                        // after the events of the other chain.
+                        match store.req_by_hash(&req.hash).await? {
+                            Some(r) if r.timestamp <= ev.block_timestamp => (),
+                            _ => {
+                                store
+                                    .insert_req(Request {
+                                        timestamp: ev.block_timestamp,
+                                        ..req.clone()
+                                    })
+                                    .await?
+                            }
                         }
 
                         if ev.label == EventLabel::WithdrawCompletedL2 {
-                            self.store
+                            store
                                 .insert_request(ev.tx_hash.clone(), req.clone())
                                 .await?;
                         }
 
                         if ev.label == EventLabel::DepositInitiatedL2 {
-                            self.store
+                            store
                                 .insert_pending_withdraw(PendingWithdraw {
                                     req_hash: req.clone().hash,
                                     tx_hash: ev.tx_hash,
                                     chain_src: req.clone().chain_src,
                                     timestamp: ev.block_timestamp,
-                                    message_hash: req
-                                        .message_to_l1_hash(sn_bridge_address, eth_bridge_address),
+                                    message_hash: req.message_to_l1_hash(
+                                        sn_bridge_address,
+                                        eth_bridge_address,
+                                    )?,
                                 })
                                 .await?;
                         }
 
                         if let Some(tx) = xchain_tx {
-                            match tx.kind {
-                                CrossChainTxKind::WithdrawAuto => {
-                                    // First check if the tx is not already inserted to not overwrite
-                                    // an event already indexed on ethereum.
-                                    if self
-                                        .store
-                                        .tx_from_request_kind(
-                                            &tx.req_hash.clone(),
-                                            CrossChainTxKind::WithdrawAuto,
-                                        )
-                                        .await?
-                                        .is_none()
-                                    {
-                                        self.store.insert_tx(tx).await?;
-                                    }
+                            // First check if the tx is not already inserted to not overwrite
+                            // an event already indexed on ethereum.
+                            if let CrossChainTxKind::WithdrawAuto = tx.kind {
+                                if store
+                                    .tx_from_request_kind(
+                                        &tx.req_hash.clone(),
+                                        CrossChainTxKind::WithdrawAuto,
+                                    )
+                                    .await?
+                                    .is_none()
+                                {
+                                    store.insert_tx(tx).await?;
                                 }
-                                _ => (),
                             }
                         }
                     }
@@ -274,15 +550,21 @@ where
             };
         }
 
-        let block_idx = BlockIndex {
-            chain: BridgeChain::Starknet,
-            block_number,
-            insert_timestamp: utils::utc_now_seconds(),
-        };
+        if let Some(timestamp) = block_timestamp {
+            for (tx_hash, mut c) in collections {
+                c.req_hash = tx_requests.get(&tx_hash).cloned();
+                c.timestamp = timestamp;
+                log::info!("Insert collection: {:?}", &c);
+                store.insert_collection(c).await?;
+            }
 
-        self.store.insert_block(block_idx).await?;
-        // TODO: end the database transaction/session.
+            for mut a in admin_events {
+                a.block_timestamp = timestamp;
+                log::info!("Insert admin event: {:?}", &a);
+                store.insert_admin_event(a).await?;
+            }
+        }
 
-        Ok(())
+        store.insert_block(block_idx).await
     }
 }
@@ -5,3 +5,4 @@ pub mod indexer;
 pub use indexer::StarknetIndexer;
 
 pub mod events;
+pub mod finality;
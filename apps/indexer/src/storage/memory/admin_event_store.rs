@@ -0,0 +1,64 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::MemoryStore;
+use crate::storage::{store::AdminEventStore, AdminEvent, BridgeChain};
+
+#[async_trait]
+impl AdminEventStore for MemoryStore {
+    async fn insert_admin_event(&self, event: AdminEvent) -> Result<()> {
+        self.with_data(|d| {
+            match d.admin_events.iter_mut().find(|e| {
+                e.chain == event.chain
+                    && e.block_number == event.block_number
+                    && e.event_index == event.event_index
+            }) {
+                Some(e) => *e = event,
+                None => d.admin_events.push(event),
+            }
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn admin_events(&self, chain: BridgeChain) -> Result<Vec<AdminEvent>> {
+        let mut events: Vec<AdminEvent> = self
+            .with_data(|d| {
+                d.admin_events
+                    .iter()
+                    .filter(|e| e.chain == chain)
+                    .cloned()
+                    .collect()
+            })
+            .await;
+
+        events.sort_by_key(|e| (e.block_number, e.event_index));
+
+        Ok(events)
+    }
+
+    async fn remove_admin_events_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<()> {
+        self.with_data(|d| {
+            d.admin_events
+                .retain(|e| e.chain != chain || e.block_number < block_number)
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn remove_block_admin_events(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.with_data(|d| {
+            d.admin_events
+                .retain(|e| e.chain != chain || e.block_number != block_number)
+        })
+        .await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::cmp::Reverse;
+
+use super::MemoryStore;
+use crate::storage::{store::BlockStore, BlockIndex, BridgeChain, IndexCursor, IndexedRange};
+use crate::utils;
+
+#[async_trait]
+impl BlockStore for MemoryStore {
+    async fn insert_block(&self, block: BlockIndex) -> Result<()> {
+        self.with_data(|d| {
+            match d
+                .blocks
+                .iter_mut()
+                .find(|b| b.chain == block.chain && b.block_number == block.block_number)
+            {
+                Some(b) => *b = block,
+                None => d.blocks.push(block),
+            }
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn block_by_number(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Option<BlockIndex>> {
+        Ok(self
+            .with_data(|d| {
+                d.blocks
+                    .iter()
+                    .find(|b| b.chain == chain && b.block_number == block_number)
+                    .cloned()
+            })
+            .await)
+    }
+
+    async fn latest_blocks(&self, chain: BridgeChain, count: u64) -> Result<Vec<BlockIndex>> {
+        let mut blocks: Vec<BlockIndex> = self
+            .with_data(|d| {
+                d.blocks
+                    .iter()
+                    .filter(|b| b.chain == chain)
+                    .cloned()
+                    .collect()
+            })
+            .await;
+
+        blocks.sort_by_key(|b| Reverse(b.block_number));
+        blocks.truncate(count as usize);
+
+        Ok(blocks)
+    }
+
+    async fn remove_blocks_from(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.with_data(|d| {
+            d.blocks
+                .retain(|b| b.chain != chain || b.block_number < block_number);
+
+            d.indexed_ranges
+                .retain(|r| r.chain != chain || r.from_block < block_number);
+
+            d.indexed_ranges
+                .iter_mut()
+                .filter(|r| r.chain == chain && r.to_block >= block_number)
+                .for_each(|r| r.to_block = block_number - 1);
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn insert_indexed_range(
+        &self,
+        chain: BridgeChain,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<()> {
+        self.with_data(|d| {
+            match d
+                .indexed_ranges
+                .iter_mut()
+                .find(|r| r.chain == chain && r.to_block + 1 == from_block)
+            {
+                Some(r) => r.to_block = to_block,
+                None => d.indexed_ranges.push(IndexedRange {
+                    chain,
+                    from_block,
+                    to_block,
+                }),
+            }
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn indexed_range(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Option<IndexedRange>> {
+        Ok(self
+            .with_data(|d| {
+                d.indexed_ranges
+                    .iter()
+                    .filter(|r| {
+                        r.chain == chain
+                            && r.from_block <= block_number
+                            && r.to_block >= block_number
+                    })
+                    .max_by_key(|r| r.to_block)
+                    .cloned()
+            })
+            .await)
+    }
+
+    async fn cursor(&self, chain: BridgeChain) -> Result<Option<IndexCursor>> {
+        Ok(self
+            .with_data(|d| d.cursors.iter().find(|c| c.chain == chain).cloned())
+            .await)
+    }
+
+    async fn set_cursor(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        let cursor = IndexCursor {
+            chain,
+            block_number,
+            update_timestamp: utils::utc_now_seconds(),
+        };
+
+        self.with_data(|d| match d.cursors.iter_mut().find(|c| c.chain == chain) {
+            Some(c) => *c = cursor,
+            None => d.cursors.push(cursor),
+        })
+        .await;
+
+        Ok(())
+    }
+}
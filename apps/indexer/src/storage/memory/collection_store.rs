@@ -0,0 +1,58 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::MemoryStore;
+use crate::storage::{store::CollectionStore, BridgeChain, CollectionContract};
+
+#[async_trait]
+impl CollectionStore for MemoryStore {
+    async fn insert_collection(&self, collection: CollectionContract) -> Result<()> {
+        self.with_data(|d| {
+            if !d.collections.iter().any(|c| {
+                c.address_src == collection.address_src && c.address_dst == collection.address_dst
+            }) {
+                d.collections.push(collection);
+            }
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn collection_by_address(&self, address: &str) -> Result<Option<CollectionContract>> {
+        // A mapping may be updated, the latest one is the current one.
+        Ok(self
+            .with_data(|d| {
+                d.collections
+                    .iter()
+                    .filter(|c| c.address_src == address || c.address_dst == address)
+                    .max_by_key(|c| c.timestamp)
+                    .cloned()
+            })
+            .await)
+    }
+
+    async fn remove_collections_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<()> {
+        self.with_data(|d| {
+            d.collections
+                .retain(|c| c.chain != chain || c.block_number < block_number)
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn remove_block_collections(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.with_data(|d| {
+            d.collections
+                .retain(|c| c.chain != chain || c.block_number != block_number)
+        })
+        .await;
+
+        Ok(())
+    }
+}
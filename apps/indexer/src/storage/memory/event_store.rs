@@ -0,0 +1,161 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::MemoryStore;
+use crate::storage::{store::EventStore, BridgeChain, Event, EventLabel, FinalityStatus};
+
+#[async_trait]
+impl EventStore for MemoryStore {
+    async fn insert_event(&self, event: Event) -> Result<()> {
+        self.with_data(
+            |d| match d.events.iter_mut().find(|e| same_event(e, &event)) {
+                Some(e) => *e = event,
+                None => d.events.push(event),
+            },
+        )
+        .await;
+
+        Ok(())
+    }
+
+    async fn events_by_request(&self, req_hash: &str) -> Result<Vec<Event>> {
+        Ok(self
+            .with_data(|d| {
+                d.events
+                    .iter()
+                    .filter(|e| e.req_hash == req_hash)
+                    .cloned()
+                    .collect()
+            })
+            .await)
+    }
+
+    async fn events_by_requests(&self, req_hashes: &[String]) -> Result<Vec<Event>> {
+        Ok(self
+            .with_data(|d| {
+                d.events
+                    .iter()
+                    .filter(|e| req_hashes.contains(&e.req_hash))
+                    .cloned()
+                    .collect()
+            })
+            .await)
+    }
+
+    async fn get_total_tokens_bridged_on_starknet(&self, contract_address: &str) -> Result<u64> {
+        Ok(self
+            .with_data(|d| {
+                d.starknet_bridge_requests
+                    .iter()
+                    .filter(|r| r.collection_src == contract_address)
+                    .map(|r| r.token_ids.len() as u64)
+                    .sum()
+            })
+            .await)
+    }
+
+    async fn event_by_tx(&self, tx_hash: &str) -> Result<Option<Event>> {
+        Ok(self
+            .with_data(|d| d.events.iter().find(|e| e.tx_hash == tx_hash).cloned())
+            .await)
+    }
+
+    async fn remove_events_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Vec<Event>> {
+        Ok(self
+            .with_data(|d| {
+                let (removed, kept): (Vec<Event>, Vec<Event>) = d
+                    .events
+                    .drain(..)
+                    .partition(|e| e.label.chain() == chain && e.block_number >= block_number);
+                d.events = kept;
+                removed
+            })
+            .await)
+    }
+
+    async fn remove_block_events(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Vec<Event>> {
+        Ok(self
+            .with_data(|d| {
+                let (removed, kept): (Vec<Event>, Vec<Event>) = d
+                    .events
+                    .drain(..)
+                    .partition(|e| e.label.chain() == chain && e.block_number == block_number);
+                d.events = kept;
+                removed
+            })
+            .await)
+    }
+
+    async fn event_blocks_by_finality(&self, finality: FinalityStatus) -> Result<Vec<u64>> {
+        let mut blocks: Vec<u64> = self
+            .with_data(|d| {
+                d.events
+                    .iter()
+                    .filter(|e| e.finality == Some(finality))
+                    .map(|e| e.block_number)
+                    .collect()
+            })
+            .await;
+
+        blocks.sort();
+        blocks.dedup();
+
+        Ok(blocks)
+    }
+
+    async fn finalize_events(&self, block_number: u64) -> Result<()> {
+        self.with_data(|d| {
+            d.events
+                .iter_mut()
+                .filter(|e| {
+                    e.finality == Some(FinalityStatus::AcceptedOnL2)
+                        && e.block_number <= block_number
+                })
+                .for_each(|e| e.finality = Some(FinalityStatus::AcceptedOnL1));
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn events_without_followup(
+        &self,
+        label: EventLabel,
+        followups: &[EventLabel],
+        max_timestamp: u64,
+    ) -> Result<Vec<Event>> {
+        Ok(self
+            .with_data(|d| {
+                d.events
+                    .iter()
+                    .filter(|e| e.label == label && e.block_timestamp <= max_timestamp)
+                    .filter(|e| {
+                        !d.events
+                            .iter()
+                            .any(|f| f.req_hash == e.req_hash && followups.contains(&f.label))
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .await)
+    }
+}
+
+/// Returns true if `a` and `b` are the same event: emitted by the same log,
+/// or inserted by the indexer for the same request.
+fn same_event(a: &Event, b: &Event) -> bool {
+    match (a.log_index, b.log_index) {
+        (Some(_), Some(_)) => {
+            a.label == b.label && a.tx_hash == b.tx_hash && a.log_index == b.log_index
+        }
+        _ => a.req_hash == b.req_hash && a.label == b.label && a.tx_hash == b.tx_hash,
+    }
+}
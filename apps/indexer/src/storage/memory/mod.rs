@@ -0,0 +1,222 @@
+//! In-memory store, mainly used for tests and local runs
+//! where no database is available. Nothing is persisted.
+use super::{
+    AdminEvent, BlockIndex, CollectionContract, CrossChainTx, Event, IndexCursor, IndexedRange,
+    PendingWithdraw, Request, StarknetBridgeRequest,
+};
+use std::sync::Arc;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+mod admin_event_store;
+mod block_store;
+mod collection_store;
+mod event_store;
+mod pending_withdraw_store;
+mod request_store;
+mod starknet_bridge_request_store;
+mod transaction_store;
+mod xchain_tx_store;
+
+/// Content of the in-memory store.
+#[derive(Debug, Default, Clone)]
+struct MemoryData {
+    requests: Vec<Request>,
+    events: Vec<Event>,
+    blocks: Vec<BlockIndex>,
+    indexed_ranges: Vec<IndexedRange>,
+    cursors: Vec<IndexCursor>,
+    xchain_txs: Vec<CrossChainTx>,
+    starknet_bridge_requests: Vec<StarknetBridgeRequest>,
+    pending_withdraws: Vec<PendingWithdraw>,
+    collections: Vec<CollectionContract>,
+    admin_events: Vec<AdminEvent>,
+}
+
+/// Transaction in progress, holding the lock on the data
+/// until it is committed or aborted.
+struct MemoryTransaction {
+    guard: Option<OwnedMutexGuard<MemoryData>>,
+    // Data before the transaction, restored on abort.
+    snapshot: MemoryData,
+}
+
+/// In-memory store abstraction.
+///
+/// A transaction locks the whole store until it is committed or aborted,
+/// the other operations are waiting for the transaction to end.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    data: Arc<AsyncMutex<MemoryData>>,
+    transaction: Option<Arc<AsyncMutex<MemoryTransaction>>>,
+}
+
+impl MemoryStore {
+    /// Initializes a new empty in-memory store.
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+
+    /// Executes `f` on the store data, in the current transaction if any.
+    async fn with_data<R>(&self, f: impl FnOnce(&mut MemoryData) -> R + Send) -> R {
+        match &self.transaction {
+            Some(t) => {
+                let mut t = t.lock().await;
+                let data = t.guard.as_mut().expect("Transaction already ended");
+                f(data)
+            }
+            None => {
+                let mut data = self.data.lock().await;
+                f(&mut data)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{
+        store::{BlockStore, EventStore, PendingWithdrawStore, RequestStore, TransactionStore},
+        BridgeChain, Event, EventLabel, PendingWithdraw, RequestFilter, SortOrder,
+    };
+
+    fn request(hash: &str) -> Request {
+        Request {
+            hash: hash.to_string(),
+            chain_src: BridgeChain::Ethereum,
+            from: String::from("0x1"),
+            to: String::from("0x2"),
+            collection_src: String::from("0x3"),
+            collection_dst: String::from("0x4"),
+            content: String::new(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transaction() {
+        let store = MemoryStore::new();
+
+        let tx = store.begin_transaction().await.unwrap();
+        tx.insert_req(request("0x10")).await.unwrap();
+        tx.abort_transaction().await.unwrap();
+        assert!(store.req_by_hash("0x10").await.unwrap().is_none());
+
+        let tx = store.begin_transaction().await.unwrap();
+        tx.insert_req(request("0x11")).await.unwrap();
+        tx.insert_req(request("0x11")).await.unwrap();
+        tx.commit_transaction().await.unwrap();
+        assert!(store.req_by_hash("0x11").await.unwrap().is_some());
+        let filter = RequestFilter::default();
+        assert_eq!(store.reqs_by_wallet("0x1", &filter).await.unwrap().len(), 1);
+        assert_eq!(store.reqs_by_wallet("0x2", &filter).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reqs_by_wallet_pages() {
+        let store = MemoryStore::new();
+        for (hash, timestamp) in [("0x12", 20), ("0x11", 10), ("0x13", 30), ("0x14", 20)] {
+            store
+                .insert_req(Request {
+                    timestamp,
+                    ..request(hash)
+                })
+                .await
+                .unwrap();
+        }
+
+        let hashes = |reqs: Vec<Request>| reqs.into_iter().map(|r| r.hash).collect::<Vec<_>>();
+
+        let mut filter = RequestFilter {
+            order: SortOrder::Asc,
+            ..Default::default()
+        };
+        let reqs = store.reqs_by_wallet("0x1", &filter).await.unwrap();
+        assert_eq!(hashes(reqs), vec!["0x11", "0x12", "0x14", "0x13"]);
+
+        filter.order = SortOrder::Desc;
+        filter.limit = Some(2);
+        let reqs = store.reqs_by_wallet("0x1", &filter).await.unwrap();
+        assert_eq!(hashes(reqs), vec!["0x13", "0x14"]);
+
+        filter.after = Some((20, String::from("0x14")));
+        let reqs = store.reqs_by_wallet("0x1", &filter).await.unwrap();
+        assert_eq!(hashes(reqs), vec!["0x12", "0x11"]);
+
+        filter.after = None;
+        filter.from_timestamp = Some(15);
+        filter.to_timestamp = Some(25);
+        let reqs = store.reqs_by_wallet("0x1", &filter).await.unwrap();
+        assert_eq!(hashes(reqs), vec!["0x14", "0x12"]);
+    }
+
+    #[tokio::test]
+    async fn test_indexed_ranges() {
+        let store = MemoryStore::new();
+        let chain = BridgeChain::Starknet;
+
+        store.insert_indexed_range(chain, 0, 9).await.unwrap();
+        store.insert_indexed_range(chain, 10, 19).await.unwrap();
+        store.insert_indexed_range(chain, 30, 39).await.unwrap();
+
+        let r = store.indexed_range(chain, 5).await.unwrap().unwrap();
+        assert_eq!((r.from_block, r.to_block), (0, 19));
+        assert!(store.indexed_range(chain, 25).await.unwrap().is_none());
+        assert!(store
+            .indexed_range(BridgeChain::Ethereum, 5)
+            .await
+            .unwrap()
+            .is_none());
+
+        store.remove_blocks_from(chain, 15).await.unwrap();
+        let r = store.indexed_range(chain, 5).await.unwrap().unwrap();
+        assert_eq!((r.from_block, r.to_block), (0, 14));
+        assert!(store.indexed_range(chain, 35).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_multicall_keys() {
+        let store = MemoryStore::new();
+
+        // Two requests deposited in the same transaction.
+        for (req_hash, log_index) in [("0xa", 0), ("0xb", 1), ("0xa", 0)] {
+            store
+                .insert_event(Event {
+                    req_hash: req_hash.to_string(),
+                    label: EventLabel::DepositInitiatedL2,
+                    block_timestamp: 0,
+                    block_number: 0,
+                    tx_hash: String::from("0x1"),
+                    price: None,
+                    finality: None,
+                    log_index: Some(log_index),
+                })
+                .await
+                .unwrap();
+            store
+                .insert_pending_withdraw(PendingWithdraw {
+                    req_hash: req_hash.to_string(),
+                    tx_hash: String::from("0x1"),
+                    chain_src: BridgeChain::Starknet,
+                    timestamp: 0,
+                    message_hash: [0; 32],
+                })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(store.events_by_request("0xa").await.unwrap().len(), 1);
+        assert_eq!(store.events_by_request("0xb").await.unwrap().len(), 1);
+
+        let pendings = store.get_pending_withdraws().await.unwrap();
+        assert_eq!(pendings.len(), 2);
+
+        store
+            .remove_pending_withdraw(pendings[0].clone())
+            .await
+            .unwrap();
+        let pendings = store.get_pending_withdraws().await.unwrap();
+        assert_eq!(pendings.len(), 1);
+        assert_eq!(pendings[0].req_hash, "0xb");
+    }
+}
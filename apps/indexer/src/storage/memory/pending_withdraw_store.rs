@@ -0,0 +1,44 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::storage::{store::PendingWithdrawStore, PendingWithdraw};
+
+use super::MemoryStore;
+
+#[async_trait]
+impl PendingWithdrawStore for MemoryStore {
+    async fn insert_pending_withdraw(&self, pending_withdraw: PendingWithdraw) -> Result<()> {
+        self.with_data(|d| {
+            if !d.pending_withdraws.iter().any(|p| {
+                p.req_hash == pending_withdraw.req_hash && p.tx_hash == pending_withdraw.tx_hash
+            }) {
+                d.pending_withdraws.push(pending_withdraw);
+            }
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn get_pending_withdraws(&self) -> Result<Vec<PendingWithdraw>> {
+        Ok(self.with_data(|d| d.pending_withdraws.clone()).await)
+    }
+
+    async fn remove_pending_withdraw(&self, pending_withdraw: PendingWithdraw) -> Result<()> {
+        self.with_data(|d| {
+            d.pending_withdraws.retain(|p| {
+                p.req_hash != pending_withdraw.req_hash || p.tx_hash != pending_withdraw.tx_hash
+            })
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn remove_pending_withdraws_by_request(&self, req_hash: &str) -> Result<()> {
+        self.with_data(|d| d.pending_withdraws.retain(|p| p.req_hash != req_hash))
+            .await;
+
+        Ok(())
+    }
+}
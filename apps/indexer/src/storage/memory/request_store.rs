@@ -0,0 +1,72 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::MemoryStore;
+use crate::storage::{store::RequestStore, Request, RequestFilter, SortOrder};
+
+#[async_trait]
+impl RequestStore for MemoryStore {
+    async fn reqs_by_wallet(&self, address: &str, filter: &RequestFilter) -> Result<Vec<Request>> {
+        let mut reqs: Vec<Request> = self
+            .with_data(|d| {
+                d.requests
+                    .iter()
+                    .filter(|r| r.from == address || r.to == address)
+                    .filter(|r| filter.matches(r) && filter.is_after(r))
+                    .cloned()
+                    .collect()
+            })
+            .await;
+
+        reqs.sort_by(|a, b| (a.timestamp, &a.hash).cmp(&(b.timestamp, &b.hash)));
+        if filter.order == SortOrder::Desc {
+            reqs.reverse();
+        }
+
+        if let Some(limit) = filter.limit {
+            reqs.truncate(limit);
+        }
+
+        Ok(reqs)
+    }
+
+    async fn req_by_hash(&self, hash: &str) -> Result<Option<Request>> {
+        Ok(self
+            .with_data(|d| d.requests.iter().find(|r| r.hash == hash).cloned())
+            .await)
+    }
+
+    async fn reqs_by_token(&self, collection: &str, token_id: &str) -> Result<Vec<Request>> {
+        Ok(self
+            .with_data(|d| {
+                d.requests
+                    .iter()
+                    .filter(|r| {
+                        (r.collection_src == collection || r.collection_dst == collection)
+                            && r.tokens.iter().any(|t| t.token_id == token_id)
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .await)
+    }
+
+    async fn insert_req(&self, req: Request) -> Result<()> {
+        self.with_data(
+            |d| match d.requests.iter_mut().find(|r| r.hash == req.hash) {
+                Some(r) => *r = req,
+                None => d.requests.push(req),
+            },
+        )
+        .await;
+
+        Ok(())
+    }
+
+    async fn remove_req(&self, hash: &str) -> Result<()> {
+        self.with_data(|d| d.requests.retain(|r| r.hash != hash))
+            .await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::MemoryStore;
+use crate::storage::{
+    protocol::ProtocolParser, store::StarknetBridgeRequestStore, Request, StarknetBridgeRequest,
+};
+
+#[async_trait]
+impl StarknetBridgeRequestStore for MemoryStore {
+    async fn insert_request(&self, tx_hash: String, req: Request) -> Result<()> {
+        let token_ids = req.get_token_ids()?;
+
+        let starknet_bridge_request = StarknetBridgeRequest {
+            req_hash: req.hash,
+            collection_src: req.collection_src,
+            from: req.from,
+            to: req.to,
+            is_checked: false,
+            token_ids,
+            tx_hash,
+        };
+
+        self.with_data(|d| {
+            if !d.starknet_bridge_requests.iter().any(|r| {
+                r.tx_hash == starknet_bridge_request.tx_hash
+                    && r.req_hash == starknet_bridge_request.req_hash
+            }) {
+                d.starknet_bridge_requests.push(starknet_bridge_request);
+            }
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn remove_request(&self, tx_hash: &str, req_hash: &str) -> Result<()> {
+        self.with_data(|d| {
+            d.starknet_bridge_requests
+                .retain(|r| r.tx_hash != tx_hash || r.req_hash != req_hash)
+        })
+        .await;
+
+        Ok(())
+    }
+}
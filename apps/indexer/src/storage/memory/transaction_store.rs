@@ -0,0 +1,43 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex as AsyncMutex;
+
+use super::{MemoryStore, MemoryTransaction};
+use crate::storage::store::TransactionStore;
+
+#[async_trait]
+impl TransactionStore for MemoryStore {
+    async fn begin_transaction(&self) -> Result<MemoryStore> {
+        let guard = Arc::clone(&self.data).lock_owned().await;
+        let snapshot = guard.clone();
+
+        Ok(MemoryStore {
+            data: Arc::clone(&self.data),
+            transaction: Some(Arc::new(AsyncMutex::new(MemoryTransaction {
+                guard: Some(guard),
+                snapshot,
+            }))),
+        })
+    }
+
+    async fn commit_transaction(&self) -> Result<()> {
+        if let Some(t) = &self.transaction {
+            t.lock().await.guard.take();
+        }
+
+        Ok(())
+    }
+
+    async fn abort_transaction(&self) -> Result<()> {
+        if let Some(t) = &self.transaction {
+            let mut t = t.lock().await;
+            let snapshot = t.snapshot.clone();
+            if let Some(mut guard) = t.guard.take() {
+                *guard = snapshot;
+            }
+        }
+
+        Ok(())
+    }
+}
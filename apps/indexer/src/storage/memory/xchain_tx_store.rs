@@ -0,0 +1,125 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::MemoryStore;
+use crate::storage::{
+    store::CrossChainTxStore, BridgeChain, CrossChainTx, CrossChainTxKind, CrossChainTxStatus,
+};
+
+#[async_trait]
+impl CrossChainTxStore for MemoryStore {
+    async fn insert_tx(&self, tx: CrossChainTx) -> Result<()> {
+        self.with_data(|d| {
+            if !d
+                .xchain_txs
+                .iter()
+                .any(|t| t.req_hash == tx.req_hash && t.kind == tx.kind)
+            {
+                d.xchain_txs.push(tx);
+            }
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn update_tx(&self, tx: CrossChainTx) -> Result<()> {
+        self.with_data(|d| {
+            if let Some(t) = d
+                .xchain_txs
+                .iter_mut()
+                .find(|t| t.req_hash == tx.req_hash && t.kind == tx.kind)
+            {
+                *t = tx;
+            }
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn set_tx_as_sent(
+        &self,
+        req_hash: String,
+        kind: CrossChainTxKind,
+        tx_hash: String,
+    ) -> Result<()> {
+        self.with_data(|d| {
+            if let Some(t) = d
+                .xchain_txs
+                .iter_mut()
+                .find(|t| t.req_hash == req_hash && t.kind == kind)
+            {
+                t.tx_hash = tx_hash;
+                t.status = CrossChainTxStatus::Confirmed;
+            }
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn list_xtxs(&self, chain: BridgeChain) -> Result<Vec<CrossChainTx>> {
+        Ok(self
+            .with_data(|d| {
+                d.xchain_txs
+                    .iter()
+                    .filter(|t| t.chain == chain)
+                    .cloned()
+                    .collect()
+            })
+            .await)
+    }
+
+    async fn pending_xtxs(&self, chain: BridgeChain) -> Result<Vec<CrossChainTx>> {
+        Ok(self
+            .with_data(|d| {
+                d.xchain_txs
+                    .iter()
+                    .filter(|t| {
+                        t.chain == chain
+                            && matches!(
+                                t.status,
+                                CrossChainTxStatus::Queued
+                                    | CrossChainTxStatus::Submitted
+                                    | CrossChainTxStatus::Failed
+                            )
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .await)
+    }
+
+    async fn tx_from_request_kind(
+        &self,
+        req_hash: &str,
+        kind: CrossChainTxKind,
+    ) -> Result<Option<CrossChainTx>> {
+        Ok(self
+            .with_data(|d| {
+                d.xchain_txs
+                    .iter()
+                    .find(|t| t.req_hash == req_hash && t.kind == kind)
+                    .cloned()
+            })
+            .await)
+    }
+
+    async fn remove_txs_by_hash(&self, tx_hash: &str) -> Result<()> {
+        self.with_data(|d| d.xchain_txs.retain(|t| t.tx_hash != tx_hash))
+            .await;
+
+        Ok(())
+    }
+
+    async fn remove_tx(&self, req_hash: &str, kind: CrossChainTxKind) -> Result<()> {
+        self.with_data(|d| {
+            d.xchain_txs
+                .retain(|t| t.req_hash != req_hash || t.kind != kind)
+        })
+        .await;
+
+        Ok(())
+    }
+}
@@ -1,14 +1,21 @@
-//! For now only mongo store is used locally,
+//! Mongo store is used by default, a SQL store (PostgreSQL or embedded SQLite)
+//! is available with a relational schema, and an in-memory store is available
+//! for tests and local runs.
 //! Any new store can be added (i.e. dynamodb) implementing
 //! the traits in store.rs.
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
+pub mod memory;
 pub mod mongo;
 pub mod protocol;
+pub mod relayer;
+pub mod sql;
+pub mod status;
 pub mod store;
 
-///
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum BridgeChain {
     #[default]
     #[serde(rename = "sn")]
@@ -17,18 +24,29 @@ pub enum BridgeChain {
     Ethereum,
 }
 
-///
-impl ToString for BridgeChain {
-    fn to_string(&self) -> String {
-        match self {
-            BridgeChain::Starknet => String::from("sn"),
-            BridgeChain::Ethereum => String::from("eth"),
+impl fmt::Display for BridgeChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            BridgeChain::Starknet => "sn",
+            BridgeChain::Ethereum => "eth",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for BridgeChain {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sn" => Ok(BridgeChain::Starknet),
+            "eth" => Ok(BridgeChain::Ethereum),
+            _ => Err(anyhow::anyhow!("Unknown bridge chain {:?}", s)),
         }
     }
 }
 
-///
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum CrossChainTxKind {
     #[default]
     #[serde(rename = "withdraw_auto")]
@@ -37,46 +55,312 @@ pub enum CrossChainTxKind {
     BurnAuto,
 }
 
-///
-impl ToString for CrossChainTxKind {
-    fn to_string(&self) -> String {
-        match self {
-            CrossChainTxKind::WithdrawAuto => String::from("withdraw_auto"),
-            CrossChainTxKind::BurnAuto => String::from("burn_auto"),
+impl fmt::Display for CrossChainTxKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            CrossChainTxKind::WithdrawAuto => "withdraw_auto",
+            CrossChainTxKind::BurnAuto => "burn_auto",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for CrossChainTxKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "withdraw_auto" => Ok(CrossChainTxKind::WithdrawAuto),
+            "burn_auto" => Ok(CrossChainTxKind::BurnAuto),
+            _ => Err(anyhow::anyhow!("Unknown cross chain tx kind {:?}", s)),
         }
     }
 }
 
-///
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// Status of a cross chain transaction in the relayer queue.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CrossChainTxStatus {
+    // Waiting to be sent.
+    #[default]
+    #[serde(rename = "queued")]
+    Queued,
+    // Sent, waiting for the receipt.
+    #[serde(rename = "submitted")]
+    Submitted,
+    // Executed with success.
+    #[serde(rename = "confirmed")]
+    Confirmed,
+    // The last attempt failed, the transaction is sent again after a backoff.
+    #[serde(rename = "failed")]
+    Failed,
+    // The retry budget is exhausted, the transaction is no longer sent.
+    #[serde(rename = "abandoned")]
+    Abandoned,
+    // The fee paid by the user doesn't cover the transaction, it is not sent.
+    #[serde(rename = "unprofitable")]
+    Unprofitable,
+}
+
+impl fmt::Display for CrossChainTxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            CrossChainTxStatus::Queued => "queued",
+            CrossChainTxStatus::Submitted => "submitted",
+            CrossChainTxStatus::Confirmed => "confirmed",
+            CrossChainTxStatus::Failed => "failed",
+            CrossChainTxStatus::Abandoned => "abandoned",
+            CrossChainTxStatus::Unprofitable => "unprofitable",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for CrossChainTxStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(CrossChainTxStatus::Queued),
+            "submitted" => Ok(CrossChainTxStatus::Submitted),
+            "confirmed" => Ok(CrossChainTxStatus::Confirmed),
+            "failed" => Ok(CrossChainTxStatus::Failed),
+            "abandoned" => Ok(CrossChainTxStatus::Abandoned),
+            "unprofitable" => Ok(CrossChainTxStatus::Unprofitable),
+            _ => Err(anyhow::anyhow!("Unknown cross chain tx status {:?}", s)),
+        }
+    }
+}
+
+/// Decision of the fee policy for a cross chain transaction.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RelayDecision {
+    // The fee paid covers the transaction cost.
+    #[serde(rename = "relay")]
+    Relay,
+    // The transaction is too expensive for now, it is evaluated again later.
+    #[serde(rename = "defer")]
+    Defer,
+    // The transaction stayed too expensive, it is not sent.
+    #[serde(rename = "unprofitable")]
+    Unprofitable,
+}
+
+impl fmt::Display for RelayDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RelayDecision::Relay => "relay",
+            RelayDecision::Defer => "defer",
+            RelayDecision::Unprofitable => "unprofitable",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for RelayDecision {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "relay" => Ok(RelayDecision::Relay),
+            "defer" => Ok(RelayDecision::Defer),
+            "unprofitable" => Ok(RelayDecision::Unprofitable),
+            _ => Err(anyhow::anyhow!("Unknown relay decision {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct CrossChainTx {
     pub chain: BridgeChain,
     pub kind: CrossChainTxKind,
     pub req_hash: String,
     pub req_content: String,
+    // Hash of the last submitted transaction, empty until the transaction is sent.
     pub tx_hash: String,
+    #[serde(default)]
+    pub status: CrossChainTxStatus,
+    // Number of failed or stuck attempts.
+    #[serde(default)]
+    pub attempts: u32,
+    // Nonce of the last submitted transaction (ethereum only).
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    // Gas price in wei (decimal) of the last submitted transaction (ethereum only).
+    #[serde(default)]
+    pub gas_price: Option<String>,
+    // Error of the last failed attempt.
+    #[serde(default)]
+    pub last_error: Option<String>,
+    // Timestamp before which the transaction is not sent again.
+    #[serde(default)]
+    pub next_attempt_at: u64,
+    // Timestamp of the last submission.
+    #[serde(default)]
+    pub submitted_at: Option<u64>,
+    // Last decision of the fee policy (ethereum only).
+    #[serde(default)]
+    pub relay_decision: Option<RelayDecision>,
+    // Estimated cost in wei (decimal) of the transaction at the last decision.
+    #[serde(default)]
+    pub estimated_fee: Option<String>,
+    // Fee in wei (decimal) paid by the user, from the bridge minimum gas fee.
+    #[serde(default)]
+    pub paid_fee: Option<String>,
+    // Timestamp of the first deferral.
+    #[serde(default)]
+    pub deferred_since: Option<u64>,
 }
 
-///
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct BlockIndex {
     pub chain: BridgeChain,
     pub block_number: u64,
     pub insert_timestamp: u64,
+    // Hash of the block, used to detect reorgs.
+    pub block_hash: Option<String>,
+    // Hash of the parent block.
+    pub parent_hash: Option<String>,
+}
+
+/// Highest block of a chain up to which all the blocks have been
+/// processed without gap, used to resume indexing after a restart.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IndexCursor {
+    pub chain: BridgeChain,
+    pub block_number: u64,
+    pub update_timestamp: u64,
+}
+
+/// Range of blocks (bounds included) for which all the events
+/// have been fetched and processed.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IndexedRange {
+    pub chain: BridgeChain,
+    pub from_block: u64,
+    pub to_block: u64,
+}
+
+/// Order of the requests by creation time.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Filter on the requests of a wallet, and position of the page.
+///
+/// The requests are sorted by creation time, then by hash.
+#[derive(Debug, Default, Clone)]
+pub struct RequestFilter {
+    // Source chain of the requests.
+    pub chain_src: Option<BridgeChain>,
+    // Address of the collection, on the source or the destination chain.
+    pub collection: Option<String>,
+    // Time range (included) of the requests creation.
+    pub from_timestamp: Option<u64>,
+    pub to_timestamp: Option<u64>,
+    pub order: SortOrder,
+    // Creation time and hash of the last request of the previous page.
+    pub after: Option<(u64, String)>,
+    // Maximum number of requests returned, all if not set.
+    pub limit: Option<usize>,
+}
+
+impl RequestFilter {
+    /// Returns true if the request matches the filter, regardless of the page.
+    pub fn matches(&self, req: &Request) -> bool {
+        self.chain_src.is_none_or(|c| req.chain_src == c)
+            && self
+                .collection
+                .as_ref()
+                .is_none_or(|c| &req.collection_src == c || &req.collection_dst == c)
+            && self.from_timestamp.is_none_or(|t| req.timestamp >= t)
+            && self.to_timestamp.is_none_or(|t| req.timestamp <= t)
+    }
+
+    /// Returns true if the request is after the position of the previous page.
+    pub fn is_after(&self, req: &Request) -> bool {
+        match &self.after {
+            Some((timestamp, hash)) => {
+                let key = (req.timestamp, &req.hash);
+                match self.order {
+                    SortOrder::Asc => key > (*timestamp, hash),
+                    SortOrder::Desc => key < (*timestamp, hash),
+                }
+            }
+            None => true,
+        }
+    }
 }
 
-/// TODO: Need better data structure for this one...
-///       we may also want the details of tokens, extracted
-///       from the request content.
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// Collection known by the bridge, with its address on both chains.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct CollectionContract {
+    // Chain where the collection was originally deployed.
     pub chain_src: BridgeChain,
+    // Address of the collection on the source chain.
     pub address_src: String,
+    // Address of the collection on the destination chain.
     pub address_dst: String,
+    // Hash of the request that deployed the collection on the destination chain.
+    // None if the mapping was set by the bridge admin.
+    pub req_hash: Option<String>,
+    // Timestamp of the block where the collection was deployed or mapped.
+    pub timestamp: u64,
+    // Chain and block of the event which registered the collection.
+    #[serde(default)]
+    pub chain: BridgeChain,
+    #[serde(default)]
+    pub block_number: u64,
+}
+
+/// Interface of a bridged collection, from the request header.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionType {
+    #[default]
+    #[serde(rename = "erc721")]
+    Erc721,
+    #[serde(rename = "erc1155")]
+    Erc1155,
+}
+
+impl fmt::Display for CollectionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            CollectionType::Erc721 => "erc721",
+            CollectionType::Erc1155 => "erc1155",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for CollectionType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "erc721" => Ok(CollectionType::Erc721),
+            "erc1155" => Ok(CollectionType::Erc1155),
+            _ => Err(anyhow::anyhow!("Unknown collection type {:?}", s)),
+        }
+    }
+}
+
+/// Token moved by a request.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RequestToken {
+    // Token id, in decimal.
+    pub token_id: String,
+    // Amount of tokens moved, in decimal (always 1 for ERC721).
+    pub amount: String,
+    // URI of the token, if provided in the request.
+    pub uri: Option<String>,
 }
 
 /// Request sent on the bridge.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Request {
     // Request's hash, unique identifier through source and destination chains.
     pub hash: String,
@@ -92,6 +376,21 @@ pub struct Request {
     pub collection_dst: String,
     // Raw content of the request (JSON) in it's serialized form.
     pub content: String,
+    // Creation time of the request: timestamp of the block of its first event.
+    #[serde(default)]
+    pub timestamp: u64,
+    // The fields below are decoded from the content at index time,
+    // and are empty for requests indexed before the decoding was introduced.
+    #[serde(default)]
+    pub collection_type: CollectionType,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub symbol: String,
+    #[serde(default)]
+    pub base_uri: String,
+    #[serde(default)]
+    pub tokens: Vec<RequestToken>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -115,10 +414,50 @@ pub struct Event {
     pub tx_hash: String,
     // Transaction price
     pub price: Option<EventPrice>,
+    // Finality status of the block containing the event (only tracked on starknet).
+    #[serde(default)]
+    pub finality: Option<FinalityStatus>,
+    // Index of the log (ethereum) or of the event (starknet) in the block,
+    // none for the events inserted by the indexer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_index: Option<u64>,
+}
+
+/// Finality status of a block on starknet.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FinalityStatus {
+    #[serde(rename = "accepted_on_l2")]
+    AcceptedOnL2,
+    #[serde(rename = "accepted_on_l1")]
+    AcceptedOnL1,
+}
+
+impl fmt::Display for FinalityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            FinalityStatus::AcceptedOnL2 => "accepted_on_l2",
+            FinalityStatus::AcceptedOnL1 => "accepted_on_l1",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for FinalityStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "accepted_on_l2" => Ok(FinalityStatus::AcceptedOnL2),
+            "accepted_on_l1" => Ok(FinalityStatus::AcceptedOnL1),
+            _ => Err(anyhow::anyhow!("Unknown finality status {:?}", s)),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StarknetBridgeRequest {
+    #[serde(default)]
+    pub req_hash: String,
     pub collection_src: String,
     pub token_ids: Vec<String>,
     pub from: String,
@@ -127,7 +466,6 @@ pub struct StarknetBridgeRequest {
     pub is_checked: bool,
 }
 
-///
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Eq, Ord, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum EventLabel {
@@ -144,26 +482,143 @@ pub enum EventLabel {
     #[serde(rename = "withdraw_completed_l2")]
     WithdrawCompletedL2,
 
+    #[serde(rename = "cancel_started_l1")]
+    CancelStartedL1,
+    #[serde(rename = "cancel_completed_l1")]
+    CancelCompletedL1,
+
     #[serde(rename = "transit_error_l1_l2")]
     TransitErrorL1L2,
     #[serde(rename = "transit_error_l2_l1")]
     TransitErrorL2L1,
 }
 
-///
-impl ToString for EventLabel {
-    fn to_string(&self) -> String {
+impl fmt::Display for EventLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            EventLabel::DepositInitiatedL1 => "deposit_initiated_l1",
+            EventLabel::WithdrawAvailableL1 => "withdraw_available_l1",
+            EventLabel::WithdrawCompletedL1 => "withdraw_completed_l1",
+            EventLabel::TransitErrorL1L2 => "transit_error_l1_l2",
+            EventLabel::CancelStartedL1 => "cancel_started_l1",
+            EventLabel::CancelCompletedL1 => "cancel_completed_l1",
+
+            EventLabel::DepositInitiatedL2 => "deposit_initiated_l2",
+            EventLabel::WithdrawCompletedL2 => "withdraw_completed_l2",
+            EventLabel::TransitErrorL2L1 => "transit_error_l2_l1",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for EventLabel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deposit_initiated_l1" => Ok(EventLabel::DepositInitiatedL1),
+            "withdraw_available_l1" => Ok(EventLabel::WithdrawAvailableL1),
+            "withdraw_completed_l1" => Ok(EventLabel::WithdrawCompletedL1),
+            "transit_error_l1_l2" => Ok(EventLabel::TransitErrorL1L2),
+            "cancel_started_l1" => Ok(EventLabel::CancelStartedL1),
+            "cancel_completed_l1" => Ok(EventLabel::CancelCompletedL1),
+            "deposit_initiated_l2" => Ok(EventLabel::DepositInitiatedL2),
+            "withdraw_completed_l2" => Ok(EventLabel::WithdrawCompletedL2),
+            "transit_error_l2_l1" => Ok(EventLabel::TransitErrorL2L1),
+            _ => Err(anyhow::anyhow!("Unknown event label {:?}", s)),
+        }
+    }
+}
+
+impl EventLabel {
+    /// Returns the chain on which the event is emitted.
+    pub fn chain(&self) -> BridgeChain {
         match self {
-            EventLabel::DepositInitiatedL1 => String::from("deposit_initiated_l1"),
-            EventLabel::WithdrawAvailableL1 => String::from("withdraw_available_l1"),
-            EventLabel::WithdrawCompletedL1 => String::from("withdraw_completed_l1"),
-            EventLabel::TransitErrorL1L2 => String::from("transit_error_l1_l2"),
-
-            EventLabel::DepositInitiatedL2 => String::from("deposit_initiated_l2"),
-            EventLabel::WithdrawCompletedL2 => String::from("withdraw_completed_l2"),
-            EventLabel::TransitErrorL2L1 => String::from("transit_error_l2_l1"),
+            EventLabel::DepositInitiatedL1
+            | EventLabel::WithdrawAvailableL1
+            | EventLabel::WithdrawCompletedL1
+            | EventLabel::CancelStartedL1
+            | EventLabel::CancelCompletedL1
+            | EventLabel::TransitErrorL1L2 => BridgeChain::Ethereum,
+
+            EventLabel::DepositInitiatedL2
+            | EventLabel::WithdrawCompletedL2
+            | EventLabel::TransitErrorL2L1 => BridgeChain::Starknet,
         }
     }
+
+    /// Returns all the labels of events emitted on the given chain.
+    pub fn labels_for_chain(chain: BridgeChain) -> Vec<EventLabel> {
+        [
+            EventLabel::DepositInitiatedL1,
+            EventLabel::DepositInitiatedL2,
+            EventLabel::WithdrawAvailableL1,
+            EventLabel::WithdrawCompletedL1,
+            EventLabel::WithdrawCompletedL2,
+            EventLabel::CancelStartedL1,
+            EventLabel::CancelCompletedL1,
+            EventLabel::TransitErrorL1L2,
+            EventLabel::TransitErrorL2L1,
+        ]
+        .into_iter()
+        .filter(|l| l.chain() == chain)
+        .collect()
+    }
+}
+
+/// Administration event emitted by a bridge contract.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdminEvent {
+    // Chain where the event was emitted.
+    pub chain: BridgeChain,
+    // The decoded event.
+    pub kind: AdminEventKind,
+    // The block number associated with the event emission.
+    pub block_number: u64,
+    // Index of the event in the block, to replay the events in order.
+    pub event_index: u64,
+    // Block timestamp containing the event.
+    pub block_timestamp: u64,
+    // Transaction hash of the transaction which triggered the event.
+    pub tx_hash: String,
+}
+
+/// Bridge configuration changes, emitted by the L1 and/or the L2 bridge.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AdminEventKind {
+    // L2 only.
+    BridgeEnabled {
+        enabled: bool,
+    },
+    // Emitted as `WhiteListUpdated` on L1.
+    WhiteListEnabled {
+        enabled: bool,
+    },
+    CollectionWhiteListUpdated {
+        collection: String,
+        enabled: bool,
+    },
+    // L1 only.
+    MinimumGasFeeUpdated {
+        gas_fee: String,
+    },
+    OwnershipTransferred {
+        previous_owner: String,
+        new_owner: String,
+    },
+    // Implementation address on L1, class hash on L2.
+    Upgraded {
+        implementation: String,
+    },
+    // L2 only.
+    BridgeL1AddressUpdated {
+        address: String,
+    },
+    // L2 only.
+    Erc721ClassHashUpdated {
+        class_hash: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
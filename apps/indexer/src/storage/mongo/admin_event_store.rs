@@ -0,0 +1,51 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use mongodb::{bson::doc, options::FindOptions};
+
+use super::MongoStore;
+use crate::storage::{store::AdminEventStore, AdminEvent, BridgeChain};
+
+#[async_trait]
+impl AdminEventStore for MongoStore {
+    async fn insert_admin_event(&self, event: AdminEvent) -> Result<()> {
+        let filter = doc! {
+            "chain": event.chain,
+            "block_number": event.block_number as i64,
+            "event_index": event.event_index as i64,
+        };
+
+        self.upsert_one(&self.admin_events, filter, event).await
+    }
+
+    async fn admin_events(&self, chain: BridgeChain) -> Result<Vec<AdminEvent>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "block_number": 1, "event_index": 1 })
+            .build();
+
+        self.find(&self.admin_events, doc! { "chain": chain }, options)
+            .await
+    }
+
+    async fn remove_admin_events_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<()> {
+        self.delete_many(
+            &self.admin_events,
+            doc! {
+                "chain": chain,
+                "block_number": { "$gte": block_number as i64 },
+            },
+        )
+        .await
+    }
+
+    async fn remove_block_admin_events(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.delete_many(
+            &self.admin_events,
+            doc! { "chain": chain, "block_number": block_number as i64 },
+        )
+        .await
+    }
+}
@@ -1,35 +1,158 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use mongodb::bson::doc;
+use mongodb::{
+    bson::doc,
+    options::{FindOneOptions, FindOptions, UpdateOptions},
+};
 
 use super::MongoStore;
-use crate::storage::{store::BlockStore, BlockIndex, BridgeChain};
+use crate::storage::{store::BlockStore, BlockIndex, BridgeChain, IndexCursor, IndexedRange};
+use crate::utils;
 
 #[async_trait]
 impl BlockStore for MongoStore {
-    ///
     async fn insert_block(&self, block: BlockIndex) -> Result<()> {
-        self.blocks.insert_one(block, None).await?;
-        Ok(())
+        let filter = doc! {
+            "chain": block.chain,
+            "block_number": block.block_number as i64,
+        };
+
+        self.upsert_one(&self.blocks, filter, block).await
     }
 
-    ///
     async fn block_by_number(
         &self,
         chain: BridgeChain,
         block_number: u64,
     ) -> Result<Option<BlockIndex>> {
-        let b = self
-            .blocks
-            .find_one(
+        self.find_one(
+            &self.blocks,
+            doc! {
+                "chain": chain,
+                "block_number": block_number as i64,
+            },
+            None,
+        )
+        .await
+    }
+
+    async fn latest_blocks(&self, chain: BridgeChain, count: u64) -> Result<Vec<BlockIndex>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "block_number": -1 })
+            .limit(count as i64)
+            .build();
+
+        self.find(&self.blocks, doc! { "chain": chain }, options)
+            .await
+    }
+
+    async fn remove_blocks_from(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.delete_many(
+            &self.blocks,
+            doc! {
+                "chain": chain,
+                "block_number": { "$gte": block_number as i64 },
+            },
+        )
+        .await?;
+
+        self.delete_many(
+            &self.indexed_ranges,
+            doc! {
+                "chain": chain,
+                "from_block": { "$gte": block_number as i64 },
+            },
+        )
+        .await?;
+
+        self.update_many(
+            &self.indexed_ranges,
+            doc! {
+                "chain": chain,
+                "to_block": { "$gte": block_number as i64 },
+            },
+            doc! { "$set": { "to_block": block_number as i64 - 1 } },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_indexed_range(
+        &self,
+        chain: BridgeChain,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<()> {
+        let merged = self
+            .update_one(
+                &self.indexed_ranges,
                 doc! {
                     "chain": chain,
-                    "block_number": block_number as i64,
+                    "to_block": from_block as i64 - 1,
                 },
+                doc! { "$set": { "to_block": to_block as i64 } },
                 None,
             )
             .await?;
 
-        Ok(b)
+        if merged.matched_count > 0 {
+            return Ok(());
+        }
+
+        self.insert_one(
+            &self.indexed_ranges,
+            IndexedRange {
+                chain,
+                from_block,
+                to_block,
+            },
+        )
+        .await
+    }
+
+    async fn indexed_range(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Option<IndexedRange>> {
+        let options = FindOneOptions::builder()
+            .sort(doc! { "to_block": -1 })
+            .build();
+
+        self.find_one(
+            &self.indexed_ranges,
+            doc! {
+                "chain": chain,
+                "from_block": { "$lte": block_number as i64 },
+                "to_block": { "$gte": block_number as i64 },
+            },
+            options,
+        )
+        .await
+    }
+
+    async fn cursor(&self, chain: BridgeChain) -> Result<Option<IndexCursor>> {
+        self.find_one(&self.cursors, doc! { "chain": chain }, None)
+            .await
+    }
+
+    async fn set_cursor(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        let options = UpdateOptions::builder().upsert(true).build();
+
+        self.update_one(
+            &self.cursors,
+            doc! { "chain": chain },
+            doc! {
+                "$set": {
+                    "block_number": block_number as i64,
+                    "update_timestamp": utils::utc_now_seconds() as i64,
+                }
+            },
+            options,
+        )
+        .await?;
+
+        Ok(())
     }
 }
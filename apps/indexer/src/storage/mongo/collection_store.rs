@@ -0,0 +1,58 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use mongodb::{bson::doc, options::FindOneOptions};
+
+use super::MongoStore;
+use crate::storage::{store::CollectionStore, BridgeChain, CollectionContract};
+
+#[async_trait]
+impl CollectionStore for MongoStore {
+    async fn insert_collection(&self, collection: CollectionContract) -> Result<()> {
+        let filter = doc! {
+            "address_src": &collection.address_src,
+            "address_dst": &collection.address_dst,
+        };
+
+        self.insert_if_absent(&self.collections, filter, collection)
+            .await
+    }
+
+    async fn collection_by_address(&self, address: &str) -> Result<Option<CollectionContract>> {
+        let filter = doc! {
+            "$or": [
+                {"address_src": address},
+                {"address_dst": address},
+            ]
+        };
+
+        // A mapping may be updated, the latest one is the current one.
+        let options = FindOneOptions::builder()
+            .sort(doc! { "timestamp": -1 })
+            .build();
+
+        self.find_one(&self.collections, filter, options).await
+    }
+
+    async fn remove_collections_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<()> {
+        self.delete_many(
+            &self.collections,
+            doc! {
+                "chain": chain,
+                "block_number": { "$gte": block_number as i64 },
+            },
+        )
+        .await
+    }
+
+    async fn remove_block_collections(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.delete_many(
+            &self.collections,
+            doc! { "chain": chain, "block_number": block_number as i64 },
+        )
+        .await
+    }
+}
@@ -1,26 +1,22 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::TryStreamExt;
-use mongodb::{bson::doc, options::AggregateOptions};
+use mongodb::bson::{doc, from_document, to_document, Bson, Document};
 
 use super::MongoStore;
-use crate::storage::{store::EventStore, Event};
+use crate::storage::{store::EventStore, BridgeChain, Event, EventLabel, FinalityStatus};
 
 #[async_trait]
 impl EventStore for MongoStore {
-    ///
     async fn events_by_request(&self, req_hash: &str) -> Result<Vec<Event>> {
         let filter = doc! { "req_hash": req_hash };
 
-        let mut cursor = self.events.find(filter, None).await?;
-
-        let mut events: Vec<Event> = vec![];
+        self.find(&self.events, filter, None).await
+    }
 
-        while let Some(e) = cursor.try_next().await? {
-            events.push(e);
-        }
+    async fn events_by_requests(&self, req_hashes: &[String]) -> Result<Vec<Event>> {
+        let filter = doc! { "req_hash": { "$in": req_hashes } };
 
-        Ok(events)
+        self.find(&self.events, filter, None).await
     }
 
     async fn get_total_tokens_bridged_on_starknet(
@@ -46,13 +42,12 @@ impl EventStore for MongoStore {
             },
         ];
 
-        let mut cursor = self
-            .starknet_bridge_requests
-            .aggregate(pipeline, AggregateOptions::default())
+        let docs = self
+            .aggregate(&self.starknet_bridge_requests, pipeline)
             .await?;
 
         let mut total_tokens: u64 = 0;
-        while let Some(doc) = cursor.try_next().await? {
+        for doc in docs {
             if let Ok(total) = doc.get_i32("total_tokens") {
                 total_tokens += total as u64;
             };
@@ -61,18 +56,150 @@ impl EventStore for MongoStore {
         Ok(total_tokens)
     }
 
-    ///
     async fn event_by_tx(&self, tx_hash: &str) -> Result<Option<Event>> {
-        Ok(self
-            .events
-            .find_one(doc! { "tx_hash": tx_hash }, None)
-            .await?)
+        self.find_one(&self.events, doc! { "tx_hash": tx_hash }, None)
+            .await
     }
 
-    ///
     async fn insert_event(&self, event: Event) -> Result<()> {
-        self.events.insert_one(event, None).await?;
+        // Events inserted by the indexer itself are unique per request.
+        let by_request = doc! {
+            "req_hash": &event.req_hash,
+            "label": event.label,
+            "tx_hash": &event.tx_hash,
+        };
+
+        let chain = event.label.chain();
+        let filter = match event.log_index {
+            // The events indexed before the log index are replaced.
+            Some(log_index) => doc! {
+                "$or": [
+                    {
+                        "chain": chain,
+                        "tx_hash": &event.tx_hash,
+                        "log_index": log_index as i64,
+                        "label": event.label,
+                    },
+                    { "$and": [by_request, { "log_index": { "$exists": false } }] },
+                ]
+            },
+            None => by_request,
+        };
+
+        // The chain is only stored for the unique index.
+        let mut document = to_document(&event)?;
+        document.insert("chain", chain);
+
+        self.upsert_one(&self.events.clone_with_type::<Document>(), filter, document)
+            .await
+    }
+
+    async fn remove_events_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Vec<Event>> {
+        let labels: Vec<Bson> = EventLabel::labels_for_chain(chain)
+            .into_iter()
+            .map(Bson::from)
+            .collect();
+
+        let filter = doc! {
+            "label": { "$in": labels },
+            "block_number": { "$gte": block_number as i64 },
+        };
+
+        let events = self.find(&self.events, filter.clone(), None).await?;
+
+        self.delete_many(&self.events, filter).await?;
+
+        Ok(events)
+    }
+
+    async fn remove_block_events(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Vec<Event>> {
+        let labels: Vec<Bson> = EventLabel::labels_for_chain(chain)
+            .into_iter()
+            .map(Bson::from)
+            .collect();
+
+        let filter = doc! {
+            "label": { "$in": labels },
+            "block_number": block_number as i64,
+        };
+
+        let events = self.find(&self.events, filter.clone(), None).await?;
+
+        self.delete_many(&self.events, filter).await?;
+
+        Ok(events)
+    }
+
+    async fn event_blocks_by_finality(&self, finality: FinalityStatus) -> Result<Vec<u64>> {
+        let values = self
+            .distinct(&self.events, "block_number", doc! { "finality": finality })
+            .await?;
+
+        let mut blocks: Vec<u64> = values
+            .iter()
+            .filter_map(|v| v.as_i64().map(|n| n as u64))
+            .collect();
+
+        blocks.sort();
+
+        Ok(blocks)
+    }
+
+    async fn finalize_events(&self, block_number: u64) -> Result<()> {
+        self.update_many(
+            &self.events,
+            doc! {
+                "finality": FinalityStatus::AcceptedOnL2,
+                "block_number": { "$lte": block_number as i64 },
+            },
+            doc! { "$set": { "finality": FinalityStatus::AcceptedOnL1 } },
+        )
+        .await?;
 
         Ok(())
     }
+
+    async fn events_without_followup(
+        &self,
+        label: EventLabel,
+        followups: &[EventLabel],
+        max_timestamp: u64,
+    ) -> Result<Vec<Event>> {
+        let followups: Vec<Bson> = followups.iter().map(|l| Bson::from(*l)).collect();
+
+        // The followups are looked up per event, using the index on `req_hash`.
+        let pipeline = vec![
+            doc! { "$match": {
+                "label": label,
+                "block_timestamp": { "$lte": max_timestamp as i64 },
+            } },
+            doc! { "$lookup": {
+                "from": self.events.name(),
+                "localField": "req_hash",
+                "foreignField": "req_hash",
+                "pipeline": [
+                    { "$match": { "label": { "$in": followups } } },
+                    { "$limit": 1 },
+                    { "$project": { "_id": 1 } },
+                ],
+                "as": "followups",
+            } },
+            doc! { "$match": { "followups": { "$size": 0 } } },
+            doc! { "$project": { "followups": 0 } },
+        ];
+
+        self.aggregate(&self.events, pipeline)
+            .await?
+            .into_iter()
+            .map(|d| Ok(from_document(d)?))
+            .collect()
+    }
 }
@@ -2,16 +2,26 @@ use super::PendingWithdraw;
 use super::StarknetBridgeRequest;
 
 use crate::storage::{
-    BlockIndex, BridgeChain, CrossChainTx, CrossChainTxKind, Event, EventLabel, Request,
+    AdminEvent, BlockIndex, BridgeChain, CollectionContract, CrossChainTx, CrossChainTxKind,
+    CrossChainTxStatus, Event, EventLabel, FinalityStatus, IndexCursor, IndexedRange, Request,
 };
 use anyhow::Result;
-use mongodb::{bson::Bson, options::ClientOptions, Client, Collection};
+use mongodb::{
+    bson::{doc, Bson, Document},
+    options::{ClientOptions, IndexOptions},
+    Client, ClientSession, Collection, IndexModel,
+};
+use std::sync::Arc;
+use tokio::sync::Mutex as AsyncMutex;
 
+mod admin_event_store;
 mod block_store;
+mod collection_store;
 mod event_store;
 mod pending_withdraw_store;
 mod request_store;
 mod starknet_bridge_request_store;
+mod transaction_store;
 mod xchain_tx_store;
 
 /// Mongo db abstraction.
@@ -22,16 +32,25 @@ mod xchain_tx_store;
 /// to have a type of mongo only (MongoRequest), and implementing
 /// `From<Request> for MongoRequest` among others.
 /// The same applies for events.
+#[derive(Clone)]
 pub struct MongoStore {
+    client: Client,
+    // Session of the current transaction, if any.
+    session: Option<Arc<AsyncMutex<ClientSession>>>,
+    // Transactions are only supported by replica sets and sharded clusters.
+    transactions_supported: bool,
     requests: Collection<Request>,
     events: Collection<Event>,
     blocks: Collection<BlockIndex>,
+    indexed_ranges: Collection<IndexedRange>,
+    cursors: Collection<IndexCursor>,
     xchain_txs: Collection<CrossChainTx>,
     starknet_bridge_requests: Collection<StarknetBridgeRequest>,
     pending_withdraws: Collection<PendingWithdraw>,
+    collections: Collection<CollectionContract>,
+    admin_events: Collection<AdminEvent>,
 }
 
-///
 impl MongoStore {
     /// Initializes a new mongo store for the given database.
     ///
@@ -42,33 +61,163 @@ impl MongoStore {
         let client = Client::with_options(client_options)?;
         let db = client.database(db_name);
 
+        let hello = db.run_command(doc! { "isMaster": 1 }, None).await?;
+        let transactions_supported =
+            hello.contains_key("setName") || hello.get_str("msg") == Ok("isdbgrid");
+        if !transactions_supported {
+            log::warn!("MongoDB is not a replica set, the writes of a block are not atomic");
+        }
+
         let requests = db.collection::<Request>("requests");
         let events = db.collection::<Event>("events");
         let blocks = db.collection::<BlockIndex>("blocks");
+        let indexed_ranges = db.collection::<IndexedRange>("indexed_ranges");
+        let cursors = db.collection::<IndexCursor>("cursors");
         let xchain_txs = db.collection::<CrossChainTx>("xchain_txs");
         let starknet_bridge_requests =
             db.collection::<StarknetBridgeRequest>("starknet_bridge_requests");
         let pending_withdraws = db.collection::<PendingWithdraw>("pending_withdraws");
+        let collections = db.collection::<CollectionContract>("collections");
+        let admin_events = db.collection::<AdminEvent>("admin_events");
 
-        Ok(MongoStore {
+        let store = MongoStore {
+            client,
+            session: None,
+            transactions_supported,
             requests,
             events,
             blocks,
+            indexed_ranges,
+            cursors,
             xchain_txs,
             starknet_bridge_requests,
             pending_withdraws,
-        })
+            collections,
+            admin_events,
+        };
+
+        store.create_indexes().await;
+        store.set_requests_timestamp().await;
+
+        Ok(store)
+    }
+}
+
+impl MongoStore {
+    /// Creates the unique indexes ensuring that replaying a block range
+    /// doesn't duplicate any document.
+    async fn create_indexes(&self) {
+        create_unique_index(&self.requests, doc! { "hash": 1 }).await;
+        // Requests of a wallet, sorted by creation time.
+        for wallet in ["from", "to"] {
+            create_index(
+                &self.requests,
+                doc! { wallet: 1, "timestamp": 1, "hash": 1 },
+                IndexOptions::default(),
+            )
+            .await;
+        }
+        // Events emitted by a log, the events inserted by the indexer
+        // (without log index) being unique per request and label.
+        create_partial_unique_index(
+            &self.events,
+            doc! { "chain": 1, "tx_hash": 1, "log_index": 1, "label": 1 },
+            doc! { "log_index": { "$exists": true } },
+        )
+        .await;
+        create_unique_index(
+            &self.events,
+            doc! { "req_hash": 1, "label": 1, "tx_hash": 1 },
+        )
+        .await;
+        create_unique_index(&self.blocks, doc! { "chain": 1, "block_number": 1 }).await;
+        create_unique_index(&self.cursors, doc! { "chain": 1 }).await;
+        create_unique_index(&self.xchain_txs, doc! { "req_hash": 1, "kind": 1 }).await;
+        create_unique_index(
+            &self.starknet_bridge_requests,
+            doc! { "tx_hash": 1, "req_hash": 1 },
+        )
+        .await;
+        // One transaction may deposit several requests.
+        create_unique_index(
+            &self.pending_withdraws,
+            doc! { "req_hash": 1, "tx_hash": 1 },
+        )
+        .await;
+        create_unique_index(
+            &self.collections,
+            doc! { "address_src": 1, "address_dst": 1 },
+        )
+        .await;
+        create_unique_index(
+            &self.admin_events,
+            doc! { "chain": 1, "block_number": 1, "event_index": 1 },
+        )
+        .await;
+    }
+}
+
+impl MongoStore {
+    /// Sets the creation time of the requests indexed before it was stored,
+    /// from the timestamp of their first event.
+    async fn set_requests_timestamp(&self) {
+        let pipeline = vec![
+            doc! { "$match": { "timestamp": { "$exists": false } } },
+            doc! { "$lookup": {
+                "from": self.events.name(),
+                "localField": "hash",
+                "foreignField": "req_hash",
+                "as": "events",
+            } },
+            doc! { "$project": {
+                "timestamp": { "$ifNull": [{ "$min": "$events.block_timestamp" }, 0] },
+            } },
+            doc! { "$merge": {
+                "into": self.requests.name(),
+                "on": "_id",
+                "whenMatched": "merge",
+                "whenNotMatched": "discard",
+            } },
+        ];
+
+        if let Err(e) = self.aggregate(&self.requests, pipeline).await {
+            log::error!("Failed to set the creation time of the requests: {:?}", e);
+        }
+    }
+}
+
+/// Creates a unique index on the given keys.
+/// The creation fails if the collection already contains duplicates, which
+/// must be removed manually. The indexer still works without the index, but
+/// concurrent writes may then duplicate documents.
+async fn create_unique_index<T>(coll: &Collection<T>, keys: Document) {
+    create_index(coll, keys, IndexOptions::builder().unique(true).build()).await
+}
+
+/// Creates a unique index on the given keys, only for the documents matching `filter`.
+async fn create_partial_unique_index<T>(coll: &Collection<T>, keys: Document, filter: Document) {
+    let options = IndexOptions::builder()
+        .unique(true)
+        .partial_filter_expression(filter)
+        .build();
+
+    create_index(coll, keys, options).await
+}
+
+async fn create_index<T>(coll: &Collection<T>, keys: Document, options: IndexOptions) {
+    let index = IndexModel::builder().keys(keys).options(options).build();
+
+    if let Err(e) = coll.create_index(index, None).await {
+        log::error!("Failed to create index on {}: {:?}", coll.name(), e);
     }
 }
 
-///
 impl From<BridgeChain> for Bson {
     fn from(v: BridgeChain) -> Bson {
         Bson::String(v.to_string())
     }
 }
 
-///
 impl From<Bson> for BridgeChain {
     fn from(v: Bson) -> BridgeChain {
         match v {
@@ -82,21 +231,22 @@ impl From<Bson> for BridgeChain {
     }
 }
 
-///
 impl From<EventLabel> for Bson {
     fn from(v: EventLabel) -> Bson {
         Bson::String(v.to_string())
     }
 }
 
-///
 impl From<Bson> for EventLabel {
     fn from(v: Bson) -> EventLabel {
         match v {
             Bson::String(s) => match s.as_str() {
                 "deposit_initiated_l1" => EventLabel::DepositInitiatedL1,
+                "withdraw_available_l1" => EventLabel::WithdrawAvailableL1,
                 "withdraw_completed_l1" => EventLabel::WithdrawCompletedL1,
                 "transit_error_l1_l2" => EventLabel::TransitErrorL1L2,
+                "cancel_started_l1" => EventLabel::CancelStartedL1,
+                "cancel_completed_l1" => EventLabel::CancelCompletedL1,
                 "deposit_initiated_l2" => EventLabel::DepositInitiatedL2,
                 "withdraw_completed_l2" => EventLabel::WithdrawCompletedL2,
                 "transit_error_l2_l1" => EventLabel::TransitErrorL2L1,
@@ -107,14 +257,12 @@ impl From<Bson> for EventLabel {
     }
 }
 
-///
 impl From<CrossChainTxKind> for Bson {
     fn from(v: CrossChainTxKind) -> Bson {
         Bson::String(v.to_string())
     }
 }
 
-///
 impl From<Bson> for CrossChainTxKind {
     fn from(v: Bson) -> CrossChainTxKind {
         match v {
@@ -127,3 +275,39 @@ impl From<Bson> for CrossChainTxKind {
         }
     }
 }
+
+impl From<CrossChainTxStatus> for Bson {
+    fn from(v: CrossChainTxStatus) -> Bson {
+        Bson::String(v.to_string())
+    }
+}
+
+impl From<Bson> for CrossChainTxStatus {
+    fn from(v: Bson) -> CrossChainTxStatus {
+        match v {
+            Bson::String(s) => s
+                .parse()
+                .unwrap_or_else(|_| panic!("Unknown xchain tx status {:?}", s)),
+            _ => panic!("Unsupported Bson value {:?}", v),
+        }
+    }
+}
+
+impl From<FinalityStatus> for Bson {
+    fn from(v: FinalityStatus) -> Bson {
+        Bson::String(v.to_string())
+    }
+}
+
+impl From<Bson> for FinalityStatus {
+    fn from(v: Bson) -> FinalityStatus {
+        match v {
+            Bson::String(s) => match s.as_str() {
+                "accepted_on_l2" => FinalityStatus::AcceptedOnL2,
+                "accepted_on_l1" => FinalityStatus::AcceptedOnL1,
+                &_ => panic!("Unknown finality status {:?}", s),
+            },
+            _ => panic!("Unsupported Bson value {:?}", v),
+        }
+    }
+}
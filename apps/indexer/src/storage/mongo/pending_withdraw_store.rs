@@ -1,6 +1,5 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::TryStreamExt;
 
 use mongodb::bson::doc;
 
@@ -11,28 +10,32 @@ use super::MongoStore;
 #[async_trait]
 impl PendingWithdrawStore for MongoStore {
     async fn insert_pending_withdraw(&self, pending_withdraw: PendingWithdraw) -> Result<()> {
-        self.pending_withdraws
-            .insert_one(pending_withdraw, None)
-            .await?;
-        Ok(())
+        let filter = doc! {
+            "req_hash": &pending_withdraw.req_hash,
+            "tx_hash": &pending_withdraw.tx_hash,
+        };
+
+        self.insert_if_absent(&self.pending_withdraws, filter, pending_withdraw)
+            .await
     }
 
     async fn get_pending_withdraws(&self) -> Result<Vec<PendingWithdraw>> {
-        let mut cursor = self.pending_withdraws.find(None, None).await?;
-        let mut output: Vec<PendingWithdraw> = vec![];
-
-        while let Some(p) = cursor.try_next().await? {
-            output.push(p);
-        }
-
-        Ok(output)
+        self.find(&self.pending_withdraws, doc! {}, None).await
     }
 
     async fn remove_pending_withdraw(&self, pending_withdraw: PendingWithdraw) -> Result<()> {
-        // tx_hash shall be unique
-        self.pending_withdraws
-            .delete_one(doc! { "tx_hash": pending_withdraw.tx_hash}, None)
-            .await?;
-        Ok(())
+        self.delete_one(
+            &self.pending_withdraws,
+            doc! {
+                "req_hash": pending_withdraw.req_hash,
+                "tx_hash": pending_withdraw.tx_hash,
+            },
+        )
+        .await
+    }
+
+    async fn remove_pending_withdraws_by_request(&self, req_hash: &str) -> Result<()> {
+        self.delete_many(&self.pending_withdraws, doc! { "req_hash": req_hash })
+            .await
     }
 }
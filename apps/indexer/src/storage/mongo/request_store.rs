@@ -1,41 +1,88 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::TryStreamExt;
-use mongodb::bson::doc;
+use mongodb::{bson::doc, options::FindOptions};
 
 use super::MongoStore;
-use crate::storage::{store::RequestStore, Request};
+use crate::storage::{store::RequestStore, Request, RequestFilter, SortOrder};
 
 #[async_trait]
 impl RequestStore for MongoStore {
-    ///
-    async fn reqs_by_wallet(&self, address: &str) -> Result<Vec<Request>> {
-        let filter = doc! {
+    async fn reqs_by_wallet(&self, address: &str, filter: &RequestFilter) -> Result<Vec<Request>> {
+        let mut conditions = vec![doc! {
             "$or": [
+                {"from": address},
                 {"to": address},
             ]
-        };
+        }];
+
+        if let Some(chain) = filter.chain_src {
+            conditions.push(doc! { "chain_src": chain });
+        }
+
+        if let Some(collection) = &filter.collection {
+            conditions.push(doc! {
+                "$or": [
+                    {"collection_src": collection},
+                    {"collection_dst": collection},
+                ]
+            });
+        }
 
-        let mut cursor = self.requests.find(filter, None).await?;
+        if let Some(from) = filter.from_timestamp {
+            conditions.push(doc! { "timestamp": { "$gte": from as i64 } });
+        }
 
-        let mut reqs: Vec<Request> = vec![];
+        if let Some(to) = filter.to_timestamp {
+            conditions.push(doc! { "timestamp": { "$lte": to as i64 } });
+        }
+
+        let (after, direction) = match filter.order {
+            SortOrder::Asc => ("$gt", 1),
+            SortOrder::Desc => ("$lt", -1),
+        };
 
-        while let Some(r) = cursor.try_next().await? {
-            reqs.push(r);
+        if let Some((timestamp, hash)) = &filter.after {
+            conditions.push(doc! {
+                "$or": [
+                    { "timestamp": { after: *timestamp as i64 } },
+                    { "timestamp": *timestamp as i64, "hash": { after: hash } },
+                ]
+            });
         }
 
-        Ok(reqs)
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": direction, "hash": direction })
+            .limit(filter.limit.map(|l| l as i64))
+            .build();
+
+        self.find(&self.requests, doc! { "$and": conditions }, options)
+            .await
     }
 
-    ///
     async fn req_by_hash(&self, hash: &str) -> Result<Option<Request>> {
-        Ok(self.requests.find_one(doc! { "hash": hash }, None).await?)
+        self.find_one(&self.requests, doc! { "hash": hash }, None)
+            .await
+    }
+
+    async fn reqs_by_token(&self, collection: &str, token_id: &str) -> Result<Vec<Request>> {
+        let filter = doc! {
+            "$or": [
+                {"collection_src": collection},
+                {"collection_dst": collection},
+            ],
+            "tokens.token_id": token_id,
+        };
+
+        self.find(&self.requests, filter, None).await
     }
 
-    ///
     async fn insert_req(&self, req: Request) -> Result<()> {
-        self.requests.insert_one(req, None).await?;
+        let filter = doc! { "hash": &req.hash };
+
+        self.upsert_one(&self.requests, filter, req).await
+    }
 
-        Ok(())
+    async fn remove_req(&self, hash: &str) -> Result<()> {
+        self.delete_one(&self.requests, doc! { "hash": hash }).await
     }
 }
@@ -9,11 +9,13 @@ use crate::storage::{
 
 #[async_trait]
 impl StarknetBridgeRequestStore for MongoStore {
-    ///
     async fn insert_request(&self, tx_hash: String, req: Request) -> Result<()> {
-        let token_ids = req.get_token_ids();
+        let token_ids = req.get_token_ids()?;
+
+        let filter = doc! { "tx_hash": &tx_hash, "req_hash": &req.hash };
 
         let starknet_bridge_request = StarknetBridgeRequest {
+            req_hash: req.hash,
             collection_src: req.collection_src,
             from: req.from,
             to: req.to,
@@ -22,10 +24,19 @@ impl StarknetBridgeRequestStore for MongoStore {
             tx_hash,
         };
 
-        self.starknet_bridge_requests
-            .insert_one(starknet_bridge_request, None)
-            .await?;
+        self.insert_if_absent(
+            &self.starknet_bridge_requests,
+            filter,
+            starknet_bridge_request,
+        )
+        .await
+    }
 
-        Ok(())
+    async fn remove_request(&self, tx_hash: &str, req_hash: &str) -> Result<()> {
+        self.delete_many(
+            &self.starknet_bridge_requests,
+            doc! { "tx_hash": tx_hash, "req_hash": req_hash },
+        )
+        .await
     }
 }
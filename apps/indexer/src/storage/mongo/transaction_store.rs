@@ -0,0 +1,254 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, to_document, Bson, Document},
+    options::{FindOneOptions, FindOptions, ReplaceOptions, UpdateOptions},
+    results::UpdateResult,
+    Collection,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex as AsyncMutex;
+
+use super::MongoStore;
+use crate::storage::store::TransactionStore;
+
+#[async_trait]
+impl TransactionStore for MongoStore {
+    async fn begin_transaction(&self) -> Result<MongoStore> {
+        if !self.transactions_supported {
+            log::debug!("Transactions not supported by the database, writes are not atomic");
+            return Ok(self.clone());
+        }
+
+        let mut session = self.client.start_session(None).await?;
+        session.start_transaction(None).await?;
+
+        Ok(MongoStore {
+            session: Some(Arc::new(AsyncMutex::new(session))),
+            ..self.clone()
+        })
+    }
+
+    async fn commit_transaction(&self) -> Result<()> {
+        if let Some(s) = &self.session {
+            s.lock().await.commit_transaction().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn abort_transaction(&self) -> Result<()> {
+        if let Some(s) = &self.session {
+            s.lock().await.abort_transaction().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Operations on the collections, executed in the session
+/// of the current transaction if any.
+impl MongoStore {
+    pub(super) async fn insert_one<T>(&self, coll: &Collection<T>, doc: T) -> Result<()>
+    where
+        T: Serialize + Send + Sync,
+    {
+        match &self.session {
+            Some(s) => coll
+                .insert_one_with_session(doc, None, &mut *s.lock().await)
+                .await
+                .map(|_| ())?,
+            None => coll.insert_one(doc, None).await.map(|_| ())?,
+        };
+
+        Ok(())
+    }
+
+    /// Replaces the document matching `filter` by `doc`,
+    /// or inserts `doc` if no document matches.
+    pub(super) async fn upsert_one<T>(
+        &self,
+        coll: &Collection<T>,
+        filter: Document,
+        doc: T,
+    ) -> Result<()>
+    where
+        T: Serialize + Send + Sync,
+    {
+        let options = ReplaceOptions::builder().upsert(true).build();
+
+        match &self.session {
+            Some(s) => coll
+                .replace_one_with_session(filter, doc, options, &mut *s.lock().await)
+                .await
+                .map(|_| ())?,
+            None => coll.replace_one(filter, doc, options).await.map(|_| ())?,
+        };
+
+        Ok(())
+    }
+
+    /// Inserts `doc` only if no document matches `filter`.
+    pub(super) async fn insert_if_absent<T>(
+        &self,
+        coll: &Collection<T>,
+        filter: Document,
+        doc: T,
+    ) -> Result<()>
+    where
+        T: Serialize + Send + Sync,
+    {
+        let options = UpdateOptions::builder().upsert(true).build();
+        let update = doc! { "$setOnInsert": to_document(&doc)? };
+
+        self.update_one(coll, filter, update, options).await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn find_one<T>(
+        &self,
+        coll: &Collection<T>,
+        filter: Document,
+        options: impl Into<Option<FindOneOptions>>,
+    ) -> Result<Option<T>>
+    where
+        T: DeserializeOwned + Unpin + Send + Sync,
+    {
+        let r = match &self.session {
+            Some(s) => {
+                coll.find_one_with_session(filter, options, &mut *s.lock().await)
+                    .await?
+            }
+            None => coll.find_one(filter, options).await?,
+        };
+
+        Ok(r)
+    }
+
+    pub(super) async fn find<T>(
+        &self,
+        coll: &Collection<T>,
+        filter: Document,
+        options: impl Into<Option<FindOptions>>,
+    ) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned + Unpin + Send + Sync,
+    {
+        let r = match &self.session {
+            Some(s) => {
+                let mut session = s.lock().await;
+                let mut cursor = coll
+                    .find_with_session(filter, options, &mut session)
+                    .await?;
+                let r: Vec<T> = cursor.stream(&mut session).try_collect().await?;
+                r
+            }
+            None => coll.find(filter, options).await?.try_collect().await?,
+        };
+
+        Ok(r)
+    }
+
+    pub(super) async fn aggregate<T>(
+        &self,
+        coll: &Collection<T>,
+        pipeline: Vec<Document>,
+    ) -> Result<Vec<Document>> {
+        let r = match &self.session {
+            Some(s) => {
+                let mut session = s.lock().await;
+                let mut cursor = coll
+                    .aggregate_with_session(pipeline, None, &mut session)
+                    .await?;
+                let r: Vec<Document> = cursor.stream(&mut session).try_collect().await?;
+                r
+            }
+            None => coll.aggregate(pipeline, None).await?.try_collect().await?,
+        };
+
+        Ok(r)
+    }
+
+    pub(super) async fn distinct<T>(
+        &self,
+        coll: &Collection<T>,
+        field_name: &str,
+        filter: Document,
+    ) -> Result<Vec<Bson>> {
+        let r = match &self.session {
+            Some(s) => {
+                coll.distinct_with_session(field_name, filter, None, &mut *s.lock().await)
+                    .await?
+            }
+            None => coll.distinct(field_name, filter, None).await?,
+        };
+
+        Ok(r)
+    }
+
+    pub(super) async fn update_one<T>(
+        &self,
+        coll: &Collection<T>,
+        filter: Document,
+        update: Document,
+        options: impl Into<Option<UpdateOptions>>,
+    ) -> Result<UpdateResult> {
+        let r = match &self.session {
+            Some(s) => {
+                coll.update_one_with_session(filter, update, options, &mut *s.lock().await)
+                    .await?
+            }
+            None => coll.update_one(filter, update, options).await?,
+        };
+
+        Ok(r)
+    }
+
+    pub(super) async fn update_many<T>(
+        &self,
+        coll: &Collection<T>,
+        filter: Document,
+        update: Document,
+    ) -> Result<UpdateResult> {
+        let r = match &self.session {
+            Some(s) => {
+                coll.update_many_with_session(filter, update, None, &mut *s.lock().await)
+                    .await?
+            }
+            None => coll.update_many(filter, update, None).await?,
+        };
+
+        Ok(r)
+    }
+
+    pub(super) async fn delete_one<T>(&self, coll: &Collection<T>, filter: Document) -> Result<()> {
+        match &self.session {
+            Some(s) => coll
+                .delete_one_with_session(filter, None, &mut *s.lock().await)
+                .await
+                .map(|_| ())?,
+            None => coll.delete_one(filter, None).await.map(|_| ())?,
+        };
+
+        Ok(())
+    }
+
+    pub(super) async fn delete_many<T>(
+        &self,
+        coll: &Collection<T>,
+        filter: Document,
+    ) -> Result<()> {
+        match &self.session {
+            Some(s) => coll
+                .delete_many_with_session(filter, None, &mut *s.lock().await)
+                .await
+                .map(|_| ())?,
+            None => coll.delete_many(filter, None).await.map(|_| ())?,
+        };
+
+        Ok(())
+    }
+}
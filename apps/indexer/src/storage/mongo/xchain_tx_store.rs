@@ -1,62 +1,62 @@
 use super::MongoStore;
-use crate::storage::{store::CrossChainTxStore, BridgeChain, CrossChainTx, CrossChainTxKind};
+use crate::storage::{
+    store::CrossChainTxStore, BridgeChain, CrossChainTx, CrossChainTxKind, CrossChainTxStatus,
+};
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::TryStreamExt;
 use mongodb::bson::doc;
 
 #[async_trait]
 impl CrossChainTxStore for MongoStore {
-    ///
     async fn insert_tx(&self, tx: CrossChainTx) -> Result<()> {
-        self.xchain_txs.insert_one(tx, None).await?;
-        Ok(())
+        // A transaction already sent must not be reset.
+        let filter = doc! { "req_hash": &tx.req_hash, "kind": tx.kind.to_string() };
+
+        self.insert_if_absent(&self.xchain_txs, filter, tx).await
     }
 
-    ///
-    async fn set_tx_as_sent(&self, req_hash: String, tx_hash: String) -> Result<()> {
-        self.xchain_txs
-            .update_one(
-                doc! { "req_hash": req_hash },
-                doc! { "$set": { "tx_hash": tx_hash }},
-                None,
-            )
-            .await?;
+    async fn update_tx(&self, tx: CrossChainTx) -> Result<()> {
+        let filter = doc! { "req_hash": &tx.req_hash, "kind": tx.kind.to_string() };
 
-        Ok(())
+        self.upsert_one(&self.xchain_txs, filter, tx).await
     }
 
-    ///
-    async fn pending_xtxs(&self, chain: BridgeChain) -> Result<Vec<CrossChainTx>> {
-        let filter = doc! { "chain": chain.to_string(), "tx_hash": "" };
-
-        let mut cursor = self.xchain_txs.find(filter, None).await?;
+    async fn set_tx_as_sent(
+        &self,
+        req_hash: String,
+        kind: CrossChainTxKind,
+        tx_hash: String,
+    ) -> Result<()> {
+        self.update_one(
+            &self.xchain_txs,
+            doc! { "req_hash": req_hash, "kind": kind.to_string() },
+            doc! { "$set": { "tx_hash": tx_hash, "status": CrossChainTxStatus::Confirmed }},
+            None,
+        )
+        .await?;
 
-        let mut txs: Vec<CrossChainTx> = vec![];
+        Ok(())
+    }
 
-        while let Some(tx) = cursor.try_next().await? {
-            txs.push(tx);
-        }
+    async fn pending_xtxs(&self, chain: BridgeChain) -> Result<Vec<CrossChainTx>> {
+        // Transactions stored before the statuses are pending until sent.
+        let filter = doc! {
+            "chain": chain.to_string(),
+            "$or": [
+                { "status": { "$in": ["queued", "submitted", "failed"] } },
+                { "status": { "$exists": false }, "tx_hash": "" },
+            ],
+        };
 
-        Ok(txs)
+        self.find(&self.xchain_txs, filter, None).await
     }
 
-    ///
     async fn list_xtxs(&self, chain: BridgeChain) -> Result<Vec<CrossChainTx>> {
         let filter = doc! { "chain": chain.to_string() };
 
-        let mut cursor = self.xchain_txs.find(filter, None).await?;
-
-        let mut txs: Vec<CrossChainTx> = vec![];
-
-        while let Some(tx) = cursor.try_next().await? {
-            txs.push(tx);
-        }
-
-        Ok(txs)
+        self.find(&self.xchain_txs, filter, None).await
     }
 
-    ///
     async fn tx_from_request_kind(
         &self,
         req_hash: &str,
@@ -64,6 +64,19 @@ impl CrossChainTxStore for MongoStore {
     ) -> Result<Option<CrossChainTx>> {
         let filter = doc! { "req_hash": req_hash, "kind": kind.to_string() };
 
-        Ok(self.xchain_txs.find_one(filter, None).await?)
+        self.find_one(&self.xchain_txs, filter, None).await
+    }
+
+    async fn remove_txs_by_hash(&self, tx_hash: &str) -> Result<()> {
+        self.delete_many(&self.xchain_txs, doc! { "tx_hash": tx_hash })
+            .await
+    }
+
+    async fn remove_tx(&self, req_hash: &str, kind: CrossChainTxKind) -> Result<()> {
+        self.delete_many(
+            &self.xchain_txs,
+            doc! { "req_hash": req_hash, "kind": kind.to_string() },
+        )
+        .await
     }
 }
@@ -1,67 +1,358 @@
+//! Decoding of the requests serialized by the bridge contracts,
+//! following the v1 layout of `Protocol.sol` and `request.cairo`.
+use anyhow::{anyhow, Result};
 use num_bigint::BigUint;
+use serde::Serialize;
 use sha3::{Digest, Keccak256};
 
 use starknet::core::types::{FieldElement, MsgToL1};
 
-use super::Request;
+use super::{BridgeChain, CollectionType, EventLabel, Request, RequestToken};
+use crate::utils::normalize_hex;
+
+// Byte 0 of the header: version.
+const HEADER_VERSION_SHIFT: u32 = 0;
+const HEADER_V1: u8 = 0x01;
+
+// Byte 1 of the header: collection type.
+const HEADER_COLLECTION_TYPE_SHIFT: u32 = 8;
+const HEADER_ERC721_TYPE: u8 = 0x01;
+const HEADER_ERC1155_TYPE: u8 = 0x02;
+
+// Byte 2 of the header: deposit config.
+const HEADER_BURN_AUTO_SHIFT: u32 = 16;
+
+// Byte 3 of the header: withdraw config.
+const HEADER_WITHDRAW_AUTO_SHIFT: u32 = 24;
+
+// Number of bytes in a full word of a cairo string.
+const CAIRO_STR_WORD_LEN: usize = 31;
 
 pub type MessageHash = [u8; 32];
 
-pub trait ProtocolParser {
-    fn get_token_ids_offset(&self) -> usize;
-    fn get_token_ids(&self) -> Vec<String>;
-    fn message_to_l1_hash(&self, from: &String, to: &String) -> MessageHash;
+/// Header of a request, with the protocol information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RequestHeader {
+    pub version: u8,
+    pub collection_type: CollectionType,
+    // The tokens are burnt on the source chain by the indexer.
+    pub burn_auto: bool,
+    // The withdraw is sent on the destination chain by the indexer.
+    pub withdraw_auto: bool,
 }
 
-impl ProtocolParser for Request {
-    fn get_token_ids_offset(&self) -> usize {
-        let content: Vec<String> = serde_json::from_str(&self.content).unwrap();
-        let mut offset = 7; // header version, hash low, hash high, collectionL1, collectionL2, ownerL1, ownerL2
-        for _ in 0..3 {
-            // 3 cairo strings: name, symbol and base_uri
-            offset +=
-                usize::from_str_radix(content[offset].trim_start_matches("0x"), 16).unwrap() + 3;
+impl RequestHeader {
+    /// Decodes the header from its felt, in hexadecimal.
+    pub fn from_hex(felt: &str) -> Result<RequestHeader> {
+        RequestHeader::from_felt(&parse_felt(felt)?)
+    }
+
+    fn from_felt(felt: &BigUint) -> Result<RequestHeader> {
+        let version = header_byte(felt, HEADER_VERSION_SHIFT);
+        if version != HEADER_V1 {
+            return Err(anyhow!("Unsupported request version {:#x}", version));
         }
-        offset
-    }
-
-    fn get_token_ids(&self) -> Vec<String> {
-        let content: Vec<String> = serde_json::from_str(&self.content).unwrap();
-        let mut offset = self.get_token_ids_offset();
-        let nb_tokens: usize =
-            usize::from_str_radix(content[offset].trim_start_matches("0x"), 16).unwrap();
-        offset += 1;
-        let mut output = Vec::new();
-        for _ in 0..nb_tokens {
-            let low = BigUint::parse_bytes(content[offset].trim_start_matches("0x").as_bytes(), 16)
-                .unwrap();
-            offset += 1;
-            let mut high =
-                BigUint::parse_bytes(content[offset].trim_start_matches("0x").as_bytes(), 16)
-                    .unwrap();
-            offset += 1;
-            high = high << 128;
-            high += low;
-            output.push(high.to_str_radix(10));
+
+        let collection_type = match header_byte(felt, HEADER_COLLECTION_TYPE_SHIFT) {
+            HEADER_ERC721_TYPE => CollectionType::Erc721,
+            HEADER_ERC1155_TYPE => CollectionType::Erc1155,
+            t => return Err(anyhow!("Unsupported collection type {:#x}", t)),
+        };
+
+        Ok(RequestHeader {
+            version,
+            collection_type,
+            burn_auto: header_byte(felt, HEADER_BURN_AUTO_SHIFT) & 0x01 == 0x01,
+            withdraw_auto: header_byte(felt, HEADER_WITHDRAW_AUTO_SHIFT) & 0x01 == 0x01,
+        })
+    }
+}
+
+/// Request decoded from its serialized content.
+///
+/// Addresses and the hash are normalized hexadecimal strings,
+/// token ids and values are in decimal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecodedRequest {
+    pub header: RequestHeader,
+    pub hash: String,
+    pub collection_l1: String,
+    pub collection_l2: String,
+    pub owner_l1: String,
+    pub owner_l2: String,
+    pub name: String,
+    pub symbol: String,
+    pub base_uri: String,
+    pub token_ids: Vec<String>,
+    pub token_values: Vec<String>,
+    pub token_uris: Vec<String>,
+    pub new_owners: Vec<String>,
+}
+
+impl DecodedRequest {
+    /// Decodes the request from its content, a JSON array of felts in hexadecimal.
+    pub fn from_content(content: &str) -> Result<DecodedRequest> {
+        let felts: Vec<String> = serde_json::from_str(content)?;
+        DecodedRequest::from_felts(&felts)
+    }
+
+    /// Decodes the request from its felts, in hexadecimal.
+    pub fn from_felts(felts: &[String]) -> Result<DecodedRequest> {
+        let mut reader = ContentReader::new(felts)?;
+
+        let header = RequestHeader::from_felt(&reader.next_felt()?)?;
+        let hash = format!("0x{:064x}", reader.next_u256()?);
+        let collection_l1 = reader.next_address()?;
+        let collection_l2 = reader.next_address()?;
+        let owner_l1 = reader.next_address()?;
+        let owner_l2 = reader.next_address()?;
+
+        let name = reader.next_string()?;
+        let symbol = reader.next_string()?;
+        let base_uri = reader.next_string()?;
+
+        let token_ids = reader.next_u256_array()?;
+        let token_values = reader.next_u256_array()?;
+
+        let nb_uris = reader.next_usize()?;
+        let token_uris = (0..nb_uris)
+            .map(|_| reader.next_string())
+            .collect::<Result<Vec<String>>>()?;
+
+        let nb_owners = reader.next_usize()?;
+        let new_owners = (0..nb_owners)
+            .map(|_| reader.next_address())
+            .collect::<Result<Vec<String>>>()?;
+
+        if header.collection_type == CollectionType::Erc1155
+            && !token_values.is_empty()
+            && token_values.len() != token_ids.len()
+        {
+            return Err(anyhow!(
+                "Expected {} token values, found {}",
+                token_ids.len(),
+                token_values.len()
+            ));
         }
-        output
+
+        Ok(DecodedRequest {
+            header,
+            hash,
+            collection_l1,
+            collection_l2,
+            owner_l1,
+            owner_l2,
+            name,
+            symbol,
+            base_uri,
+            token_ids,
+            token_values,
+            token_uris,
+            new_owners,
+        })
+    }
+
+    /// Returns the tokens moved by the request, with their amount and URI.
+    pub fn tokens(&self) -> Vec<RequestToken> {
+        self.token_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| RequestToken {
+                token_id: id.clone(),
+                amount: match (self.header.collection_type, self.token_values.get(i)) {
+                    (CollectionType::Erc1155, Some(v)) => v.clone(),
+                    _ => String::from("1"),
+                },
+                uri: self.token_uris.get(i).filter(|u| !u.is_empty()).cloned(),
+            })
+            .collect()
+    }
+}
+
+impl Request {
+    /// Builds the request carried by an event with the given label,
+    /// from its serialized content (felts in hexadecimal).
+    pub fn from_content(event_label: &EventLabel, content: &[String]) -> Result<Request> {
+        let decoded = DecodedRequest::from_felts(content)?;
+
+        // The source chain is Ethereum for deposits on L1 and withdraws on L2.
+        let chain_src = match event_label {
+            EventLabel::DepositInitiatedL1 | EventLabel::WithdrawCompletedL2 => {
+                BridgeChain::Ethereum
+            }
+            EventLabel::DepositInitiatedL2 | EventLabel::WithdrawCompletedL1 => {
+                BridgeChain::Starknet
+            }
+            _ => {
+                return Err(anyhow!(
+                    "EventLabel {:?} not supposed to generate a request",
+                    event_label
+                ))
+            }
+        };
+
+        let (collection_src, collection_dst, from, to) = match chain_src {
+            BridgeChain::Ethereum => (
+                decoded.collection_l1.clone(),
+                decoded.collection_l2.clone(),
+                decoded.owner_l1.clone(),
+                decoded.owner_l2.clone(),
+            ),
+            BridgeChain::Starknet => (
+                decoded.collection_l2.clone(),
+                decoded.collection_l1.clone(),
+                decoded.owner_l2.clone(),
+                decoded.owner_l1.clone(),
+            ),
+        };
+
+        Ok(Request {
+            hash: decoded.hash.clone(),
+            chain_src,
+            from,
+            to,
+            collection_src,
+            collection_dst,
+            content: serde_json::to_string(content)?,
+            timestamp: 0,
+            collection_type: decoded.header.collection_type,
+            tokens: decoded.tokens(),
+            name: decoded.name,
+            symbol: decoded.symbol,
+            base_uri: decoded.base_uri,
+        })
+    }
+
+    /// Decodes the content of the request.
+    pub fn decoded(&self) -> Result<DecodedRequest> {
+        DecodedRequest::from_content(&self.content)
+    }
+}
+
+pub trait ProtocolParser {
+    fn get_token_ids(&self) -> Result<Vec<String>>;
+    fn message_to_l1_hash(&self, from: &str, to: &str) -> Result<MessageHash>;
+}
+
+impl ProtocolParser for Request {
+    fn get_token_ids(&self) -> Result<Vec<String>> {
+        Ok(self.decoded()?.token_ids)
     }
 
-    fn message_to_l1_hash(&self, from: &String, to: &String) -> MessageHash {
-        let content: Vec<String> = serde_json::from_str(&self.content).unwrap();
-        let payload: Vec<FieldElement> = content
+    fn message_to_l1_hash(&self, from: &str, to: &str) -> Result<MessageHash> {
+        let content: Vec<String> = serde_json::from_str(&self.content)?;
+        let payload = content
             .iter()
-            .map(|v| FieldElement::from_hex_be(v).unwrap())
-            .collect();
+            .map(|v| FieldElement::from_hex_be(v))
+            .collect::<Result<Vec<FieldElement>, _>>()?;
         let msg = MsgToL1 {
-            from_address: FieldElement::from_hex_be(&from).unwrap(),
-            to_address: FieldElement::from_hex_be(&to).unwrap(),
+            from_address: FieldElement::from_hex_be(from)?,
+            to_address: FieldElement::from_hex_be(to)?,
             payload,
         };
-        hash(&msg)
+        Ok(hash(&msg))
     }
 }
 
+/// Sequential reader of the felts of a serialized request.
+struct ContentReader {
+    felts: Vec<BigUint>,
+    offset: usize,
+}
+
+impl ContentReader {
+    fn new(felts: &[String]) -> Result<ContentReader> {
+        let felts = felts
+            .iter()
+            .map(|f| parse_felt(f))
+            .collect::<Result<Vec<BigUint>>>()?;
+
+        Ok(ContentReader { felts, offset: 0 })
+    }
+
+    fn next_felt(&mut self) -> Result<BigUint> {
+        let felt = self
+            .felts
+            .get(self.offset)
+            .cloned()
+            .ok_or_else(|| anyhow!("Request content too short, offset {}", self.offset))?;
+        self.offset += 1;
+        Ok(felt)
+    }
+
+    fn next_usize(&mut self) -> Result<usize> {
+        let felt = self.next_felt()?;
+        usize::try_from(&felt).map_err(|_| anyhow!("Length {} out of range", felt))
+    }
+
+    fn next_address(&mut self) -> Result<String> {
+        normalize_hex(&format!("{:#x}", self.next_felt()?))
+    }
+
+    /// Reads a u256, serialized as low and high u128.
+    fn next_u256(&mut self) -> Result<BigUint> {
+        let low = self.next_felt()?;
+        let high = self.next_felt()?;
+        Ok((high << 128) + low)
+    }
+
+    /// Reads an array of u256, returned in decimal.
+    fn next_u256_array(&mut self) -> Result<Vec<String>> {
+        let len = self.next_usize()?;
+        (0..len)
+            .map(|_| Ok(self.next_u256()?.to_str_radix(10)))
+            .collect()
+    }
+
+    /// Reads a cairo string: the number of full words, the full words,
+    /// the pending word and the pending word length.
+    fn next_string(&mut self) -> Result<String> {
+        let nb_words = self.next_usize()?;
+        let mut bytes = vec![];
+        for _ in 0..nb_words {
+            bytes.extend(word_bytes(&self.next_felt()?, CAIRO_STR_WORD_LEN)?);
+        }
+
+        let pending_word = self.next_felt()?;
+        let pending_len = self.next_usize()?;
+        if pending_len >= CAIRO_STR_WORD_LEN {
+            return Err(anyhow!("Invalid pending word length {}", pending_len));
+        }
+        bytes.extend(word_bytes(&pending_word, pending_len)?);
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+fn parse_felt(felt: &str) -> Result<BigUint> {
+    BigUint::parse_bytes(felt.trim().trim_start_matches("0x").as_bytes(), 16)
+        .ok_or_else(|| anyhow!("Invalid felt {:?} in request content", felt))
+}
+
+/// Returns the byte at `shift` bits of the header.
+fn header_byte(header: &BigUint, shift: u32) -> u8 {
+    ((header >> shift) & BigUint::from(0xffu8))
+        .try_into()
+        .unwrap_or_default()
+}
+
+/// Returns the `len` bytes of the word, in big endian.
+fn word_bytes(word: &BigUint, len: usize) -> Result<Vec<u8>> {
+    let bytes: Vec<u8> = word
+        .to_bytes_be()
+        .into_iter()
+        .skip_while(|b| *b == 0)
+        .collect();
+
+    if bytes.len() > len {
+        return Err(anyhow!("Word {:#x} longer than {} bytes", word, len));
+    }
+
+    let mut padded = vec![0; len - bytes.len()];
+    padded.extend(bytes);
+    Ok(padded)
+}
+
 /// Calculates the message hash based on the algorithm documented here:
 ///
 /// https://docs.starknet.io/documentation/architecture_and_concepts/Network_Architecture/messaging-mechanism/#structure_and_hashing_l2-l1
@@ -93,7 +384,94 @@ fn hash(msg: &MsgToL1) -> MessageHash {
 mod tests {
     use starknet::core::types::{FieldElement, MsgToL1};
 
-    use crate::storage::protocol::hash;
+    use super::*;
+
+    #[test]
+    fn test_decode_request() {
+        let content: Vec<String> = [
+            // ERC1155 header with withdraw auto, hash, collections and owners.
+            "0x1000201",
+            "0x2",
+            "0x3",
+            "0x4",
+            "0x5",
+            "0x6",
+            "0x7",
+            // name "ab", empty symbol, and base_uri of one full word and "c".
+            "0x0",
+            "0x6162",
+            "0x2",
+            "0x0",
+            "0x0",
+            "0x0",
+            "0x1",
+            "0x61616161616161616161616161616161616161616161616161616161616161",
+            "0x63",
+            "0x1",
+            // ids 1 and 2^128, values 10 and 20.
+            "0x2",
+            "0x1",
+            "0x0",
+            "0x0",
+            "0x1",
+            "0x2",
+            "0xa",
+            "0x0",
+            "0x14",
+            "0x0",
+            // one uri "u", one new owner.
+            "0x1",
+            "0x0",
+            "0x75",
+            "0x1",
+            "0x1",
+            "0x8",
+        ]
+        .iter()
+        .map(|f| f.to_string())
+        .collect();
+
+        let decoded = DecodedRequest::from_felts(&content).unwrap();
+        assert_eq!(
+            decoded.header,
+            RequestHeader {
+                version: 1,
+                collection_type: CollectionType::Erc1155,
+                burn_auto: false,
+                withdraw_auto: true,
+            }
+        );
+        assert_eq!(
+            decoded.hash,
+            "0x0000000000000000000000000000000300000000000000000000000000000002"
+        );
+        assert_eq!(decoded.name, "ab");
+        assert_eq!(decoded.symbol, "");
+        assert_eq!(decoded.base_uri, format!("{}c", "a".repeat(31)));
+        assert_eq!(
+            decoded.token_ids,
+            vec!["1", "340282366920938463463374607431768211456"]
+        );
+        assert_eq!(decoded.token_values, vec!["10", "20"]);
+        assert_eq!(decoded.new_owners.len(), 1);
+
+        let req = Request::from_content(&EventLabel::WithdrawCompletedL1, &content).unwrap();
+        assert_eq!(req.chain_src, BridgeChain::Starknet);
+        assert_eq!(req.collection_src, decoded.collection_l2);
+        assert_eq!(req.from, decoded.owner_l2);
+        assert_eq!(req.tokens.len(), 2);
+        assert_eq!(req.tokens[0].amount, "10");
+        assert_eq!(req.tokens[0].uri, Some(String::from("u")));
+        assert_eq!(req.tokens[1].uri, None);
+        assert_eq!(req.decoded().unwrap(), decoded);
+
+        // Truncated content.
+        assert!(DecodedRequest::from_felts(&content[..20]).is_err());
+        // Unknown collection type and version.
+        assert!(RequestHeader::from_hex("0x301").is_err());
+        assert!(RequestHeader::from_hex("0x102").is_err());
+        assert!(DecodedRequest::from_content("[\"0xzz\"]").is_err());
+    }
 
     #[test]
     fn test_msg_to_l1_hash() {
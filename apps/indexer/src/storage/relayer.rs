@@ -0,0 +1,120 @@
+//! State transitions of the cross chain transactions sent by the relayer.
+use super::{CrossChainTx, CrossChainTxStatus};
+
+/// Maximum number of failed or stuck attempts before a transaction is abandoned.
+pub const MAX_ATTEMPTS: u32 = 5;
+
+// Backoff in seconds after the first failed attempt, doubled on each attempt.
+const BACKOFF_BASE: u64 = 30;
+// Maximum backoff in seconds.
+const BACKOFF_MAX: u64 = 3600;
+
+impl CrossChainTx {
+    /// Returns true if the transaction is waiting to be sent at `now`.
+    pub fn is_due(&self, now: u64) -> bool {
+        matches!(
+            self.status,
+            CrossChainTxStatus::Queued | CrossChainTxStatus::Failed
+        ) && now >= self.next_attempt_at
+    }
+
+    /// Puts the transaction back in the queue, to be sent again from scratch.
+    pub fn queued(&mut self) {
+        self.status = CrossChainTxStatus::Queued;
+        self.tx_hash = String::new();
+        self.attempts = 0;
+        self.nonce = None;
+        self.gas_price = None;
+        self.last_error = None;
+        self.next_attempt_at = 0;
+        self.submitted_at = None;
+    }
+
+    /// Records the submission of the transaction.
+    pub fn submitted(
+        &mut self,
+        tx_hash: String,
+        nonce: Option<u64>,
+        gas_price: Option<String>,
+        now: u64,
+    ) {
+        self.status = CrossChainTxStatus::Submitted;
+        self.tx_hash = tx_hash;
+        self.nonce = nonce;
+        self.gas_price = gas_price;
+        self.submitted_at = Some(now);
+    }
+
+    /// Records the successful execution of the transaction.
+    pub fn confirmed(&mut self) {
+        self.status = CrossChainTxStatus::Confirmed;
+        self.last_error = None;
+    }
+
+    /// Records a failed attempt. The transaction is sent again after an exponential
+    /// backoff, or abandoned once `MAX_ATTEMPTS` is reached.
+    pub fn failed(&mut self, error: String, now: u64) {
+        self.attempts += 1;
+        self.last_error = Some(error);
+
+        if self.attempts >= MAX_ATTEMPTS {
+            self.status = CrossChainTxStatus::Abandoned;
+        } else {
+            self.status = CrossChainTxStatus::Failed;
+            self.next_attempt_at = now + backoff(self.attempts);
+        }
+    }
+
+    /// Records a submitted transaction not executed in time, and returns true
+    /// if it can be replaced (with a higher gas price), false if it is abandoned.
+    pub fn stuck(&mut self) -> bool {
+        self.attempts += 1;
+
+        if self.attempts >= MAX_ATTEMPTS {
+            self.status = CrossChainTxStatus::Abandoned;
+            self.last_error = Some(format!("Transaction {} stuck", self.tx_hash));
+            false
+        } else {
+            true
+        }
+    }
+}
+
+/// Returns the backoff in seconds after the given number of attempts.
+fn backoff(attempts: u32) -> u64 {
+    BACKOFF_BASE
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(BACKOFF_MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retries() {
+        let mut tx = CrossChainTx::default();
+        assert!(tx.is_due(0));
+
+        tx.submitted(String::from("0x1"), Some(3), None, 10);
+        assert_eq!(tx.status, CrossChainTxStatus::Submitted);
+        assert!(!tx.is_due(10));
+
+        tx.failed(String::from("reverted"), 20);
+        assert_eq!(tx.status, CrossChainTxStatus::Failed);
+        assert_eq!(tx.next_attempt_at, 20 + BACKOFF_BASE);
+        assert!(!tx.is_due(20));
+        assert!(tx.is_due(20 + BACKOFF_BASE));
+
+        tx.failed(String::from("reverted"), 100);
+        assert_eq!(tx.next_attempt_at, 100 + 2 * BACKOFF_BASE);
+
+        assert!(tx.stuck());
+        assert!(tx.stuck());
+        assert!(!tx.stuck());
+        assert_eq!(tx.status, CrossChainTxStatus::Abandoned);
+        assert!(!tx.is_due(u64::MAX));
+
+        assert_eq!(backoff(20), BACKOFF_MAX);
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{any::AnyRow, Row};
+
+use super::SqlStore;
+use crate::storage::{store::AdminEventStore, AdminEvent, BridgeChain};
+
+fn admin_event_from_row(row: &AnyRow) -> Result<AdminEvent> {
+    Ok(AdminEvent {
+        chain: row.try_get::<&str, _>("chain")?.parse()?,
+        kind: serde_json::from_str(row.try_get("kind")?)?,
+        block_number: row.try_get::<i64, _>("block_number")? as u64,
+        event_index: row.try_get::<i64, _>("event_index")? as u64,
+        block_timestamp: row.try_get::<i64, _>("block_timestamp")? as u64,
+        tx_hash: row.try_get("tx_hash")?,
+    })
+}
+
+#[async_trait]
+impl AdminEventStore for SqlStore {
+    async fn insert_admin_event(&self, event: AdminEvent) -> Result<()> {
+        self.execute(
+            sqlx::query(
+                "INSERT INTO admin_events
+                 (chain, block_number, event_index, kind, block_timestamp, tx_hash)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (chain, block_number, event_index) DO UPDATE SET
+                 kind = EXCLUDED.kind,
+                 block_timestamp = EXCLUDED.block_timestamp,
+                 tx_hash = EXCLUDED.tx_hash",
+            )
+            .bind(event.chain.to_string())
+            .bind(event.block_number as i64)
+            .bind(event.event_index as i64)
+            .bind(serde_json::to_string(&event.kind)?)
+            .bind(event.block_timestamp as i64)
+            .bind(event.tx_hash),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn admin_events(&self, chain: BridgeChain) -> Result<Vec<AdminEvent>> {
+        let rows = self
+            .fetch_all(
+                sqlx::query(
+                    "SELECT * FROM admin_events WHERE chain = $1
+                     ORDER BY block_number, event_index",
+                )
+                .bind(chain.to_string()),
+            )
+            .await?;
+
+        rows.iter().map(admin_event_from_row).collect()
+    }
+
+    async fn remove_admin_events_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<()> {
+        self.execute(
+            sqlx::query("DELETE FROM admin_events WHERE chain = $1 AND block_number >= $2")
+                .bind(chain.to_string())
+                .bind(block_number as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_block_admin_events(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.execute(
+            sqlx::query("DELETE FROM admin_events WHERE chain = $1 AND block_number = $2")
+                .bind(chain.to_string())
+                .bind(block_number as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
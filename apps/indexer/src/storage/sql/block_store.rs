@@ -0,0 +1,203 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{any::AnyRow, Row};
+
+use super::{try_get_opt, SqlStore};
+use crate::storage::{store::BlockStore, BlockIndex, BridgeChain, IndexCursor, IndexedRange};
+use crate::utils;
+
+fn block_from_row(row: &AnyRow) -> Result<BlockIndex> {
+    Ok(BlockIndex {
+        chain: row.try_get::<&str, _>("chain")?.parse()?,
+        block_number: row.try_get::<i64, _>("block_number")? as u64,
+        insert_timestamp: row.try_get::<i64, _>("insert_timestamp")? as u64,
+        block_hash: try_get_opt(row, "block_hash")?,
+        parent_hash: try_get_opt(row, "parent_hash")?,
+    })
+}
+
+fn range_from_row(row: &AnyRow) -> Result<IndexedRange> {
+    Ok(IndexedRange {
+        chain: row.try_get::<&str, _>("chain")?.parse()?,
+        from_block: row.try_get::<i64, _>("from_block")? as u64,
+        to_block: row.try_get::<i64, _>("to_block")? as u64,
+    })
+}
+
+fn cursor_from_row(row: &AnyRow) -> Result<IndexCursor> {
+    Ok(IndexCursor {
+        chain: row.try_get::<&str, _>("chain")?.parse()?,
+        block_number: row.try_get::<i64, _>("block_number")? as u64,
+        update_timestamp: row.try_get::<i64, _>("update_timestamp")? as u64,
+    })
+}
+
+#[async_trait]
+impl BlockStore for SqlStore {
+    async fn insert_block(&self, block: BlockIndex) -> Result<()> {
+        self.execute(
+            sqlx::query(
+                "INSERT INTO blocks
+                 (chain, block_number, insert_timestamp, block_hash, parent_hash)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (chain, block_number) DO UPDATE SET
+                 insert_timestamp = EXCLUDED.insert_timestamp,
+                 block_hash = EXCLUDED.block_hash,
+                 parent_hash = EXCLUDED.parent_hash",
+            )
+            .bind(block.chain.to_string())
+            .bind(block.block_number as i64)
+            .bind(block.insert_timestamp as i64)
+            .bind(block.block_hash)
+            .bind(block.parent_hash),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn block_by_number(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Option<BlockIndex>> {
+        let row = self
+            .fetch_optional(
+                sqlx::query("SELECT * FROM blocks WHERE chain = $1 AND block_number = $2")
+                    .bind(chain.to_string())
+                    .bind(block_number as i64),
+            )
+            .await?;
+
+        row.as_ref().map(block_from_row).transpose()
+    }
+
+    async fn latest_blocks(&self, chain: BridgeChain, count: u64) -> Result<Vec<BlockIndex>> {
+        let rows = self
+            .fetch_all(
+                sqlx::query(
+                    "SELECT * FROM blocks WHERE chain = $1
+                     ORDER BY block_number DESC LIMIT $2",
+                )
+                .bind(chain.to_string())
+                .bind(count as i64),
+            )
+            .await?;
+
+        rows.iter().map(block_from_row).collect()
+    }
+
+    async fn remove_blocks_from(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.execute(
+            sqlx::query("DELETE FROM blocks WHERE chain = $1 AND block_number >= $2")
+                .bind(chain.to_string())
+                .bind(block_number as i64),
+        )
+        .await?;
+
+        self.execute(
+            sqlx::query("DELETE FROM indexed_ranges WHERE chain = $1 AND from_block >= $2")
+                .bind(chain.to_string())
+                .bind(block_number as i64),
+        )
+        .await?;
+
+        self.execute(
+            sqlx::query(
+                "UPDATE indexed_ranges SET to_block = $2 - 1 WHERE chain = $1 AND to_block >= $2",
+            )
+            .bind(chain.to_string())
+            .bind(block_number as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_indexed_range(
+        &self,
+        chain: BridgeChain,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<()> {
+        let merged = self
+            .execute(
+                sqlx::query(
+                    "UPDATE indexed_ranges SET to_block = $3
+                     WHERE chain = $1 AND to_block = $2 - 1",
+                )
+                .bind(chain.to_string())
+                .bind(from_block as i64)
+                .bind(to_block as i64),
+            )
+            .await?;
+
+        if merged.rows_affected() > 0 {
+            return Ok(());
+        }
+
+        self.execute(
+            sqlx::query(
+                "INSERT INTO indexed_ranges (chain, from_block, to_block)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (chain, from_block) DO UPDATE SET
+                 to_block = CASE WHEN indexed_ranges.to_block > EXCLUDED.to_block
+                 THEN indexed_ranges.to_block ELSE EXCLUDED.to_block END",
+            )
+            .bind(chain.to_string())
+            .bind(from_block as i64)
+            .bind(to_block as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn indexed_range(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Option<IndexedRange>> {
+        let row = self
+            .fetch_optional(
+                sqlx::query(
+                    "SELECT * FROM indexed_ranges
+                     WHERE chain = $1 AND from_block <= $2 AND to_block >= $2
+                     ORDER BY to_block DESC LIMIT 1",
+                )
+                .bind(chain.to_string())
+                .bind(block_number as i64),
+            )
+            .await?;
+
+        row.as_ref().map(range_from_row).transpose()
+    }
+
+    async fn cursor(&self, chain: BridgeChain) -> Result<Option<IndexCursor>> {
+        let row = self
+            .fetch_optional(
+                sqlx::query("SELECT * FROM cursors WHERE chain = $1").bind(chain.to_string()),
+            )
+            .await?;
+
+        row.as_ref().map(cursor_from_row).transpose()
+    }
+
+    async fn set_cursor(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.execute(
+            sqlx::query(
+                "INSERT INTO cursors (chain, block_number, update_timestamp)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (chain) DO UPDATE SET
+                 block_number = EXCLUDED.block_number,
+                 update_timestamp = EXCLUDED.update_timestamp",
+            )
+            .bind(chain.to_string())
+            .bind(block_number as i64)
+            .bind(utils::utc_now_seconds() as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
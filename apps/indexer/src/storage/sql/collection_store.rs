@@ -0,0 +1,88 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{any::AnyRow, Row};
+
+use super::{try_get_opt, SqlStore};
+use crate::storage::{store::CollectionStore, BridgeChain, CollectionContract};
+
+fn collection_from_row(row: &AnyRow) -> Result<CollectionContract> {
+    Ok(CollectionContract {
+        chain_src: row.try_get::<&str, _>("chain_src")?.parse()?,
+        address_src: row.try_get("address_src")?,
+        address_dst: row.try_get("address_dst")?,
+        req_hash: try_get_opt(row, "req_hash")?,
+        timestamp: row.try_get::<i64, _>("timestamp")? as u64,
+        // Unknown for the collections registered before the block was recorded.
+        chain: try_get_opt::<String>(row, "chain")?
+            .map(|c| c.parse())
+            .transpose()?
+            .unwrap_or_default(),
+        block_number: try_get_opt::<i64>(row, "block_number")?.unwrap_or_default() as u64,
+    })
+}
+
+#[async_trait]
+impl CollectionStore for SqlStore {
+    async fn insert_collection(&self, collection: CollectionContract) -> Result<()> {
+        self.execute(
+            sqlx::query(
+                "INSERT INTO collections (address_src, address_dst, chain_src, req_hash, timestamp,
+                 chain, block_number)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(collection.address_src)
+            .bind(collection.address_dst)
+            .bind(collection.chain_src.to_string())
+            .bind(collection.req_hash)
+            .bind(collection.timestamp as i64)
+            .bind(collection.chain.to_string())
+            .bind(collection.block_number as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn collection_by_address(&self, address: &str) -> Result<Option<CollectionContract>> {
+        // A mapping may be updated, the latest one is the current one.
+        let row = self
+            .fetch_optional(
+                sqlx::query(
+                    "SELECT * FROM collections
+                     WHERE address_src = $1 OR address_dst = $1
+                     ORDER BY timestamp DESC LIMIT 1",
+                )
+                .bind(address),
+            )
+            .await?;
+
+        row.as_ref().map(collection_from_row).transpose()
+    }
+
+    async fn remove_collections_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<()> {
+        self.execute(
+            sqlx::query("DELETE FROM collections WHERE chain = $1 AND block_number >= $2")
+                .bind(chain.to_string())
+                .bind(block_number as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_block_collections(&self, chain: BridgeChain, block_number: u64) -> Result<()> {
+        self.execute(
+            sqlx::query("DELETE FROM collections WHERE chain = $1 AND block_number = $2")
+                .bind(chain.to_string())
+                .bind(block_number as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
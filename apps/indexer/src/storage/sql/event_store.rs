@@ -0,0 +1,216 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{any::AnyRow, Row};
+
+use super::{list_placeholders, try_get_opt, SqlStore};
+use crate::storage::{
+    store::EventStore, BridgeChain, Event, EventLabel, EventPrice, FinalityStatus,
+};
+
+fn event_from_row(row: &AnyRow) -> Result<Event> {
+    let price_gas: Option<i64> = try_get_opt(row, "price_gas")?;
+    let price_usd: Option<String> = try_get_opt(row, "price_usd")?;
+    let finality: Option<&str> = try_get_opt(row, "finality")?;
+
+    Ok(Event {
+        req_hash: row.try_get("req_hash")?,
+        label: row.try_get::<&str, _>("label")?.parse()?,
+        block_timestamp: row.try_get::<i64, _>("block_timestamp")? as u64,
+        block_number: row.try_get::<i64, _>("block_number")? as u64,
+        tx_hash: row.try_get("tx_hash")?,
+        price: match (price_gas, price_usd) {
+            (Some(gas), Some(usd_price)) => Some(EventPrice {
+                gas: gas as u64,
+                usd_price,
+            }),
+            _ => None,
+        },
+        finality: finality.map(str::parse).transpose()?,
+        log_index: try_get_opt::<i64>(row, "log_index")?.map(|i| i as u64),
+    })
+}
+
+#[async_trait]
+impl EventStore for SqlStore {
+    async fn insert_event(&self, event: Event) -> Result<()> {
+        let (price_gas, price_usd) = match event.price {
+            Some(p) => (Some(p.gas as i64), Some(p.usd_price)),
+            None => (None, None),
+        };
+
+        self.execute(
+            sqlx::query(
+                "INSERT INTO events
+                 (req_hash, label, chain, block_timestamp, block_number, tx_hash,
+                  price_gas, price_usd, finality, log_index)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                 ON CONFLICT (req_hash, label, tx_hash) DO UPDATE SET
+                 block_timestamp = EXCLUDED.block_timestamp,
+                 block_number = EXCLUDED.block_number,
+                 price_gas = EXCLUDED.price_gas,
+                 price_usd = EXCLUDED.price_usd,
+                 finality = EXCLUDED.finality,
+                 log_index = EXCLUDED.log_index",
+            )
+            .bind(event.req_hash)
+            .bind(event.label.to_string())
+            .bind(event.label.chain().to_string())
+            .bind(event.block_timestamp as i64)
+            .bind(event.block_number as i64)
+            .bind(event.tx_hash)
+            .bind(price_gas)
+            .bind(price_usd)
+            .bind(event.finality.map(|f| f.to_string()))
+            .bind(event.log_index.map(|i| i as i64)),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn events_by_request(&self, req_hash: &str) -> Result<Vec<Event>> {
+        let rows = self
+            .fetch_all(sqlx::query("SELECT * FROM events WHERE req_hash = $1").bind(req_hash))
+            .await?;
+
+        rows.iter().map(event_from_row).collect()
+    }
+
+    async fn events_by_requests(&self, req_hashes: &[String]) -> Result<Vec<Event>> {
+        let sql = format!(
+            "SELECT * FROM events WHERE req_hash IN ({})",
+            list_placeholders(1, req_hashes.len())
+        );
+
+        let mut query = sqlx::query(&sql);
+        for h in req_hashes {
+            query = query.bind(h);
+        }
+
+        let rows = self.fetch_all(query).await?;
+
+        rows.iter().map(event_from_row).collect()
+    }
+
+    async fn get_total_tokens_bridged_on_starknet(&self, contract_address: &str) -> Result<u64> {
+        let row = self
+            .fetch_optional(
+                sqlx::query(
+                    "SELECT COUNT(*) AS total FROM bridged_tokens WHERE collection_src = $1",
+                )
+                .bind(contract_address),
+            )
+            .await?;
+
+        Ok(match row {
+            Some(r) => r.try_get::<i64, _>("total")? as u64,
+            None => 0,
+        })
+    }
+
+    async fn event_by_tx(&self, tx_hash: &str) -> Result<Option<Event>> {
+        let row = self
+            .fetch_optional(
+                sqlx::query("SELECT * FROM events WHERE tx_hash = $1 LIMIT 1").bind(tx_hash),
+            )
+            .await?;
+
+        row.as_ref().map(event_from_row).transpose()
+    }
+
+    async fn remove_events_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Vec<Event>> {
+        let rows = self
+            .fetch_all(
+                sqlx::query(
+                    "DELETE FROM events WHERE chain = $1 AND block_number >= $2 RETURNING *",
+                )
+                .bind(chain.to_string())
+                .bind(block_number as i64),
+            )
+            .await?;
+
+        rows.iter().map(event_from_row).collect()
+    }
+
+    async fn remove_block_events(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Vec<Event>> {
+        let rows = self
+            .fetch_all(
+                sqlx::query(
+                    "DELETE FROM events WHERE chain = $1 AND block_number = $2 RETURNING *",
+                )
+                .bind(chain.to_string())
+                .bind(block_number as i64),
+            )
+            .await?;
+
+        rows.iter().map(event_from_row).collect()
+    }
+
+    async fn event_blocks_by_finality(&self, finality: FinalityStatus) -> Result<Vec<u64>> {
+        let rows = self
+            .fetch_all(
+                sqlx::query(
+                    "SELECT DISTINCT block_number FROM events
+                     WHERE finality = $1 ORDER BY block_number",
+                )
+                .bind(finality.to_string()),
+            )
+            .await?;
+
+        rows.iter()
+            .map(|r| Ok(r.try_get::<i64, _>("block_number")? as u64))
+            .collect()
+    }
+
+    async fn finalize_events(&self, block_number: u64) -> Result<()> {
+        self.execute(
+            sqlx::query(
+                "UPDATE events SET finality = $1 WHERE finality = $2 AND block_number <= $3",
+            )
+            .bind(FinalityStatus::AcceptedOnL1.to_string())
+            .bind(FinalityStatus::AcceptedOnL2.to_string())
+            .bind(block_number as i64),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn events_without_followup(
+        &self,
+        label: EventLabel,
+        followups: &[EventLabel],
+        max_timestamp: u64,
+    ) -> Result<Vec<Event>> {
+        let followups: Vec<String> = followups.iter().map(|l| l.to_string()).collect();
+
+        let sql = format!(
+            "SELECT * FROM events e
+             WHERE e.label = $1 AND e.block_timestamp <= $2
+             AND NOT EXISTS (
+                 SELECT 1 FROM events f
+                 WHERE f.req_hash = e.req_hash AND f.label IN ({})
+             )",
+            list_placeholders(3, followups.len())
+        );
+
+        let mut query = sqlx::query(&sql)
+            .bind(label.to_string())
+            .bind(max_timestamp as i64);
+        for l in followups {
+            query = query.bind(l);
+        }
+
+        let rows = self.fetch_all(query).await?;
+
+        rows.iter().map(event_from_row).collect()
+    }
+}
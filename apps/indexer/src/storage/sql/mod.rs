@@ -0,0 +1,478 @@
+//! SQL store, shared by PostgreSQL and embedded SQLite databases through
+//! the sqlx `Any` driver, with the schema created by the migrations
+//! in `migrations/sql`.
+//!
+//! The queries only use SQL understood by both databases: lists are bound
+//! value by value, and the column types are the ones common to both.
+use anyhow::{anyhow, Result};
+use sqlx::{
+    any::{AnyArguments, AnyPoolOptions, AnyQueryResult, AnyRow},
+    query::Query,
+    Any, AnyPool, Decode, Executor, Row, Transaction, Type, TypeInfo, ValueRef,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex as AsyncMutex;
+
+mod admin_event_store;
+mod block_store;
+mod collection_store;
+mod event_store;
+mod pending_withdraw_store;
+mod request_store;
+mod starknet_bridge_request_store;
+mod transaction_store;
+mod xchain_tx_store;
+
+const SQLITE_SCHEME: &str = "sqlite:";
+
+// Time a SQLite connection waits for the database lock held by another writer
+// (the ethereum and starknet indexers write concurrently).
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Transaction in progress, taken when committed or aborted.
+type SqlTransaction = Arc<AsyncMutex<Option<Transaction<'static, Any>>>>;
+
+type SqlQuery<'q> = Query<'q, Any, AnyArguments<'q>>;
+
+/// PostgreSQL and SQLite abstraction.
+///
+/// Enums are stored as text using their `Display` representation,
+/// and block numbers and timestamps as `BIGINT`.
+#[derive(Clone)]
+pub struct SqlStore {
+    pool: AnyPool,
+    // Transaction in which the queries are executed, if any.
+    transaction: Option<SqlTransaction>,
+}
+
+impl SqlStore {
+    /// Connects to the database and applies the pending migrations.
+    ///
+    /// A SQLite database file is created if missing, and is opened in WAL mode
+    /// so the indexers can read while another one writes.
+    /// An in-memory SQLite database (`sqlite::memory:`) only lives in one
+    /// connection, so the pool is limited to one connection in this case.
+    pub async fn new(connection_string: &str) -> Result<SqlStore> {
+        sqlx::any::install_default_drivers();
+
+        // The migrations already applied must not change, each backend keeps its own.
+        let pool = if connection_string.starts_with(SQLITE_SCHEME) {
+            let pool = SqlStore::sqlite_pool(connection_string).await?;
+            sqlx::migrate!("./migrations/sqlite").run(&pool).await?;
+            pool
+        } else {
+            let pool = AnyPoolOptions::new().connect(connection_string).await?;
+            sqlx::migrate!("./migrations/postgres").run(&pool).await?;
+            pool
+        };
+
+        Ok(SqlStore {
+            pool,
+            transaction: None,
+        })
+    }
+
+    async fn sqlite_pool(connection_string: &str) -> Result<AnyPool> {
+        if connection_string.contains(":memory:") {
+            return Ok(AnyPoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect(connection_string)
+                .await?);
+        }
+
+        let url = if connection_string.contains("mode=") {
+            connection_string.to_string()
+        } else if connection_string.contains('?') {
+            format!("{}&mode=rwc", connection_string)
+        } else {
+            format!("{}?mode=rwc", connection_string)
+        };
+
+        let pragmas = format!(
+            "PRAGMA journal_mode = WAL; PRAGMA busy_timeout = {};",
+            SQLITE_BUSY_TIMEOUT.as_millis()
+        );
+
+        Ok(AnyPoolOptions::new()
+            .after_connect(move |conn, _| {
+                let pragmas = pragmas.clone();
+                Box::pin(async move {
+                    conn.execute(pragmas.as_str()).await?;
+                    Ok(())
+                })
+            })
+            .connect(&url)
+            .await?)
+    }
+}
+
+/// Placeholders of a list of `count` values bound one by one, starting
+/// at the parameter `first` (`$3, $4, $5`), or `NULL` if the list is empty.
+fn list_placeholders(first: usize, count: usize) -> String {
+    if count == 0 {
+        return String::from("NULL");
+    }
+
+    (first..first + count)
+        .map(|i| format!("${}", i))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Reads a nullable column.
+///
+/// The `Any` driver never reports a value as null when decoding an `Option`,
+/// so the type of the value is checked first.
+fn try_get_opt<'r, T>(row: &'r AnyRow, column: &str) -> Result<Option<T>>
+where
+    T: Decode<'r, Any> + Type<Any>,
+{
+    if row.try_get_raw(column)?.type_info().name() == "NULL" {
+        return Ok(None);
+    }
+
+    Ok(Some(row.try_get(column)?))
+}
+
+/// Queries execution, in the current transaction if any.
+impl SqlStore {
+    async fn execute(&self, query: SqlQuery<'_>) -> Result<AnyQueryResult> {
+        match &self.transaction {
+            Some(t) => {
+                let mut t = t.lock().await;
+                let t = t.as_mut().ok_or(anyhow!("Transaction already ended"))?;
+                Ok(query.execute(&mut **t).await?)
+            }
+            None => Ok(query.execute(&self.pool).await?),
+        }
+    }
+
+    async fn fetch_all(&self, query: SqlQuery<'_>) -> Result<Vec<AnyRow>> {
+        match &self.transaction {
+            Some(t) => {
+                let mut t = t.lock().await;
+                let t = t.as_mut().ok_or(anyhow!("Transaction already ended"))?;
+                Ok(query.fetch_all(&mut **t).await?)
+            }
+            None => Ok(query.fetch_all(&self.pool).await?),
+        }
+    }
+
+    async fn fetch_optional(&self, query: SqlQuery<'_>) -> Result<Option<AnyRow>> {
+        match &self.transaction {
+            Some(t) => {
+                let mut t = t.lock().await;
+                let t = t.as_mut().ok_or(anyhow!("Transaction already ended"))?;
+                Ok(query.fetch_optional(&mut **t).await?)
+            }
+            None => Ok(query.fetch_optional(&self.pool).await?),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{
+        store::{
+            BlockStore, CollectionStore, CrossChainTxStore, EventStore, PendingWithdrawStore,
+            RequestStore, StarknetBridgeRequestStore, TransactionStore,
+        },
+        BridgeChain, CollectionContract, CrossChainTx, CrossChainTxKind, CrossChainTxStatus, Event,
+        EventLabel, PendingWithdraw, RelayDecision, Request, RequestFilter, SortOrder,
+    };
+
+    fn request(hash: &str) -> Request {
+        // ERC721 header, hash, collections, owners, empty name, symbol and base_uri,
+        // two tokens and empty values, uris and new owners.
+        let content = [
+            "0x101", "0x2", "0x3", "0x4", "0x5", "0x6", "0x7", "0x0", "0x0", "0x0", "0x0", "0x0",
+            "0x0", "0x0", "0x0", "0x0", "0x2", "0x1", "0x0", "0x2", "0x0", "0x0", "0x0", "0x0",
+        ];
+
+        let content: Vec<String> = content.iter().map(|f| f.to_string()).collect();
+
+        Request {
+            hash: hash.to_string(),
+            ..Request::from_content(&EventLabel::DepositInitiatedL1, &content).unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transaction() {
+        let store = SqlStore::new("sqlite::memory:").await.unwrap();
+
+        let tx = store.begin_transaction().await.unwrap();
+        tx.insert_req(request("0x10")).await.unwrap();
+        tx.abort_transaction().await.unwrap();
+        assert!(store.req_by_hash("0x10").await.unwrap().is_none());
+
+        let tx = store.begin_transaction().await.unwrap();
+        tx.insert_req(request("0x11")).await.unwrap();
+        tx.insert_req(request("0x11")).await.unwrap();
+        tx.commit_transaction().await.unwrap();
+        let req = store.req_by_hash("0x11").await.unwrap().unwrap();
+        assert_eq!(req.tokens.len(), 2);
+        assert_eq!(req.tokens[1].token_id, "2");
+
+        let filter = RequestFilter::default();
+        assert_eq!(
+            store
+                .reqs_by_wallet(&req.from, &filter)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            store.reqs_by_wallet(&req.to, &filter).await.unwrap().len(),
+            1
+        );
+
+        let filter = RequestFilter {
+            chain_src: Some(BridgeChain::Starknet),
+            ..Default::default()
+        };
+        assert!(store
+            .reqs_by_wallet(&req.to, &filter)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let filter = RequestFilter {
+            chain_src: Some(BridgeChain::Ethereum),
+            collection: Some(req.collection_dst.clone()),
+            ..Default::default()
+        };
+        let reqs = store.reqs_by_wallet(&req.to, &filter).await.unwrap();
+        assert_eq!(reqs.len(), 1);
+        assert_eq!(reqs[0].tokens.len(), 2);
+
+        store
+            .insert_req(Request {
+                timestamp: 5,
+                ..request("0x12")
+            })
+            .await
+            .unwrap();
+        let filter = RequestFilter {
+            limit: Some(1),
+            ..Default::default()
+        };
+        let reqs = store.reqs_by_wallet(&req.to, &filter).await.unwrap();
+        assert_eq!(reqs[0].hash, "0x12");
+        assert_eq!(reqs[0].timestamp, 5);
+
+        let filter = RequestFilter {
+            after: Some((5, String::from("0x12"))),
+            ..filter
+        };
+        let reqs = store.reqs_by_wallet(&req.to, &filter).await.unwrap();
+        assert_eq!(reqs[0].hash, "0x11");
+
+        let filter = RequestFilter {
+            order: SortOrder::Asc,
+            after: None,
+            from_timestamp: Some(1),
+            ..filter
+        };
+        let reqs = store.reqs_by_wallet(&req.to, &filter).await.unwrap();
+        assert_eq!(reqs[0].hash, "0x12");
+    }
+
+    #[tokio::test]
+    async fn test_total_tokens_bridged() {
+        let store = SqlStore::new("sqlite::memory:").await.unwrap();
+
+        let tx_hash = String::from("0xaa");
+        store
+            .insert_request(tx_hash.clone(), request("0x10"))
+            .await
+            .unwrap();
+        store
+            .insert_request(tx_hash, request("0x10"))
+            .await
+            .unwrap();
+        store
+            .insert_request(String::from("0xbb"), request("0x11"))
+            .await
+            .unwrap();
+
+        let total = store
+            .get_total_tokens_bridged_on_starknet(&request("0x10").collection_src)
+            .await
+            .unwrap();
+        assert_eq!(total, 4);
+    }
+
+    #[tokio::test]
+    async fn test_events_by_requests() {
+        let store = SqlStore::new("sqlite::memory:").await.unwrap();
+
+        for (req_hash, tx_hash) in [("0x10", "0xa1"), ("0x11", "0xa2"), ("0x12", "0xa3")] {
+            store
+                .insert_event(Event {
+                    req_hash: req_hash.to_string(),
+                    label: EventLabel::DepositInitiatedL1,
+                    block_timestamp: 1,
+                    block_number: 1,
+                    tx_hash: tx_hash.to_string(),
+                    price: None,
+                    finality: None,
+                    log_index: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let hashes = vec![String::from("0x10"), String::from("0x12")];
+        let events = store.events_by_requests(&hashes).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| hashes.contains(&e.req_hash)));
+    }
+
+    #[tokio::test]
+    async fn test_xchain_tx_status() {
+        let store = SqlStore::new("sqlite::memory:").await.unwrap();
+
+        let mut tx = CrossChainTx {
+            chain: BridgeChain::Ethereum,
+            kind: CrossChainTxKind::WithdrawAuto,
+            req_hash: String::from("0x10"),
+            ..Default::default()
+        };
+        store.insert_tx(tx.clone()).await.unwrap();
+
+        tx.relay_decision = Some(RelayDecision::Relay);
+        tx.deferred_since = Some(5);
+        tx.submitted(
+            String::from("0xa1"),
+            Some(7),
+            Some(String::from("1000")),
+            10,
+        );
+        store.update_tx(tx.clone()).await.unwrap();
+
+        let pending = store.pending_xtxs(BridgeChain::Ethereum).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].status, CrossChainTxStatus::Submitted);
+        assert_eq!(pending[0].nonce, Some(7));
+        assert_eq!(pending[0].gas_price, Some(String::from("1000")));
+        assert_eq!(pending[0].submitted_at, Some(10));
+        assert_eq!(pending[0].relay_decision, Some(RelayDecision::Relay));
+        assert_eq!(pending[0].deferred_since, Some(5));
+
+        store
+            .set_tx_as_sent(tx.req_hash, tx.kind, String::from("0xa1"))
+            .await
+            .unwrap();
+        assert!(store
+            .pending_xtxs(BridgeChain::Ethereum)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ranges_and_followups() {
+        let store = SqlStore::new("sqlite::memory:").await.unwrap();
+
+        let chain = BridgeChain::Ethereum;
+        store.insert_indexed_range(chain, 10, 20).await.unwrap();
+        store.insert_indexed_range(chain, 10, 15).await.unwrap();
+        let range = store.indexed_range(chain, 18).await.unwrap().unwrap();
+        assert_eq!((range.from_block, range.to_block), (10, 20));
+
+        for (req_hash, label) in [
+            ("0x10", EventLabel::DepositInitiatedL2),
+            ("0x11", EventLabel::DepositInitiatedL2),
+            ("0x11", EventLabel::WithdrawAvailableL1),
+        ] {
+            store
+                .insert_event(Event {
+                    req_hash: req_hash.to_string(),
+                    label,
+                    block_timestamp: 1,
+                    block_number: 1,
+                    tx_hash: String::from("0xa1"),
+                    price: None,
+                    finality: None,
+                    log_index: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let events = store
+            .events_without_followup(
+                EventLabel::DepositInitiatedL2,
+                &[
+                    EventLabel::WithdrawAvailableL1,
+                    EventLabel::TransitErrorL2L1,
+                ],
+                1,
+            )
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].req_hash, "0x10");
+
+        let pending = PendingWithdraw {
+            req_hash: String::from("0x10"),
+            tx_hash: String::from("0xa1"),
+            chain_src: BridgeChain::Starknet,
+            timestamp: 1,
+            message_hash: [7; 32],
+        };
+        store
+            .insert_pending_withdraw(pending.clone())
+            .await
+            .unwrap();
+        store
+            .insert_pending_withdraw(PendingWithdraw {
+                req_hash: String::from("0x11"),
+                ..pending.clone()
+            })
+            .await
+            .unwrap();
+        let pendings = store.get_pending_withdraws().await.unwrap();
+        assert_eq!(pendings.len(), 2);
+        assert_eq!(pendings[0].message_hash, [7; 32]);
+
+        store.remove_pending_withdraw(pending).await.unwrap();
+        assert_eq!(store.get_pending_withdraws().await.unwrap().len(), 1);
+
+        let collection = CollectionContract {
+            chain_src: BridgeChain::Ethereum,
+            address_src: String::from("0x100"),
+            address_dst: String::from("0x200"),
+            chain,
+            block_number: 12,
+            ..Default::default()
+        };
+        store.insert_collection(collection).await.unwrap();
+        let c = store.collection_by_address("0x200").await.unwrap().unwrap();
+        assert_eq!((c.chain, c.block_number), (chain, 12));
+
+        store
+            .remove_collections_from_block(chain, 13)
+            .await
+            .unwrap();
+        assert!(store
+            .collection_by_address("0x200")
+            .await
+            .unwrap()
+            .is_some());
+        store
+            .remove_collections_from_block(chain, 12)
+            .await
+            .unwrap();
+        assert!(store
+            .collection_by_address("0x200")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use sqlx::{any::AnyRow, Row};
+
+use super::SqlStore;
+use crate::storage::{store::PendingWithdrawStore, PendingWithdraw};
+
+fn pending_withdraw_from_row(row: &AnyRow) -> Result<PendingWithdraw> {
+    let message_hash: Vec<u8> = row.try_get("message_hash")?;
+
+    Ok(PendingWithdraw {
+        req_hash: row.try_get("req_hash")?,
+        tx_hash: row.try_get("tx_hash")?,
+        chain_src: row.try_get::<&str, _>("chain_src")?.parse()?,
+        timestamp: row.try_get::<i64, _>("timestamp")? as u64,
+        message_hash: message_hash
+            .try_into()
+            .map_err(|_| anyhow!("Message hash shall be 32 bytes long"))?,
+    })
+}
+
+#[async_trait]
+impl PendingWithdrawStore for SqlStore {
+    async fn insert_pending_withdraw(&self, pending_withdraw: PendingWithdraw) -> Result<()> {
+        self.execute(
+            sqlx::query(
+                "INSERT INTO pending_withdraws (tx_hash, req_hash, chain_src, timestamp, message_hash)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(pending_withdraw.tx_hash)
+            .bind(pending_withdraw.req_hash)
+            .bind(pending_withdraw.chain_src.to_string())
+            .bind(pending_withdraw.timestamp as i64)
+            .bind(pending_withdraw.message_hash.to_vec()),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_pending_withdraws(&self) -> Result<Vec<PendingWithdraw>> {
+        let rows = self
+            .fetch_all(sqlx::query("SELECT * FROM pending_withdraws"))
+            .await?;
+
+        rows.iter().map(pending_withdraw_from_row).collect()
+    }
+
+    async fn remove_pending_withdraw(&self, pending_withdraw: PendingWithdraw) -> Result<()> {
+        self.execute(
+            sqlx::query("DELETE FROM pending_withdraws WHERE req_hash = $1 AND tx_hash = $2")
+                .bind(pending_withdraw.req_hash)
+                .bind(pending_withdraw.tx_hash),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_pending_withdraws_by_request(&self, req_hash: &str) -> Result<()> {
+        self.execute(
+            sqlx::query("DELETE FROM pending_withdraws WHERE req_hash = $1").bind(req_hash),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{any::AnyRow, Row};
+
+use super::{list_placeholders, try_get_opt, SqlStore};
+use crate::storage::{store::RequestStore, Request, RequestFilter, RequestToken, SortOrder};
+
+fn request_from_row(row: &AnyRow) -> Result<Request> {
+    Ok(Request {
+        hash: row.try_get("hash")?,
+        chain_src: row.try_get::<&str, _>("chain_src")?.parse()?,
+        from: row.try_get("from_address")?,
+        to: row.try_get("to_address")?,
+        collection_src: row.try_get("collection_src")?,
+        collection_dst: row.try_get("collection_dst")?,
+        content: row.try_get("content")?,
+        timestamp: row.try_get::<i64, _>("timestamp")? as u64,
+        collection_type: row.try_get::<&str, _>("collection_type")?.parse()?,
+        name: row.try_get("name")?,
+        symbol: row.try_get("symbol")?,
+        base_uri: row.try_get("base_uri")?,
+        tokens: vec![],
+    })
+}
+
+fn token_from_row(row: &AnyRow) -> Result<RequestToken> {
+    Ok(RequestToken {
+        token_id: row.try_get("token_id")?,
+        amount: row.try_get("amount")?,
+        uri: try_get_opt(row, "uri")?,
+    })
+}
+
+impl SqlStore {
+    /// Builds the requests from the rows, with their tokens.
+    async fn requests_with_tokens(&self, rows: &[AnyRow]) -> Result<Vec<Request>> {
+        let mut reqs = rows
+            .iter()
+            .map(request_from_row)
+            .collect::<Result<Vec<Request>>>()?;
+
+        if reqs.is_empty() {
+            return Ok(reqs);
+        }
+
+        let hashes: Vec<String> = reqs.iter().map(|r| r.hash.clone()).collect();
+        let sql = format!(
+            "SELECT * FROM request_tokens WHERE req_hash IN ({}) ORDER BY token_index",
+            list_placeholders(1, hashes.len())
+        );
+
+        let mut query = sqlx::query(&sql);
+        for h in hashes.iter() {
+            query = query.bind(h);
+        }
+
+        let token_rows = self.fetch_all(query).await?;
+
+        for row in token_rows.iter() {
+            let req_hash: &str = row.try_get("req_hash")?;
+            if let Some(r) = reqs.iter_mut().find(|r| r.hash == req_hash) {
+                r.tokens.push(token_from_row(row)?);
+            }
+        }
+
+        Ok(reqs)
+    }
+}
+
+#[async_trait]
+impl RequestStore for SqlStore {
+    async fn reqs_by_wallet(&self, address: &str, filter: &RequestFilter) -> Result<Vec<Request>> {
+        let (after, direction) = match filter.order {
+            SortOrder::Asc => (">", "ASC"),
+            SortOrder::Desc => ("<", "DESC"),
+        };
+
+        // Position before the first request when there is no previous page.
+        let (after_timestamp, after_hash) = match (&filter.after, filter.order) {
+            (Some((timestamp, hash)), _) => (*timestamp as i64, hash.clone()),
+            (None, SortOrder::Asc) => (-1, String::new()),
+            (None, SortOrder::Desc) => (i64::MAX, String::new()),
+        };
+
+        let mut sql = format!(
+            "SELECT * FROM requests
+             WHERE (from_address = $1 OR to_address = $1)
+             AND timestamp >= $2 AND timestamp <= $3
+             AND (timestamp, hash) {} ($4, $5)",
+            after
+        );
+
+        // Only the filters set are bound, as a null parameter has no text type
+        // on all the databases.
+        let mut params = vec![];
+
+        if let Some(chain_src) = filter.chain_src {
+            params.push(chain_src.to_string());
+            sql.push_str(&format!(" AND chain_src = ${}", params.len() + 6));
+        }
+
+        if let Some(collection) = &filter.collection {
+            params.push(collection.clone());
+            sql.push_str(&format!(
+                " AND (collection_src = ${0} OR collection_dst = ${0})",
+                params.len() + 6
+            ));
+        }
+
+        sql.push_str(&format!(
+            " ORDER BY timestamp {0}, hash {0} LIMIT $6",
+            direction
+        ));
+
+        let mut query = sqlx::query(&sql)
+            .bind(address)
+            .bind(filter.from_timestamp.unwrap_or(0) as i64)
+            .bind(filter.to_timestamp.map_or(i64::MAX, |t| t as i64))
+            .bind(after_timestamp)
+            .bind(after_hash)
+            .bind(filter.limit.map_or(i64::MAX, |l| l as i64));
+        for p in params {
+            query = query.bind(p);
+        }
+
+        let rows = self.fetch_all(query).await?;
+
+        self.requests_with_tokens(&rows).await
+    }
+
+    async fn req_by_hash(&self, hash: &str) -> Result<Option<Request>> {
+        let row = self
+            .fetch_optional(sqlx::query("SELECT * FROM requests WHERE hash = $1").bind(hash))
+            .await?;
+
+        match row {
+            Some(r) => Ok(self.requests_with_tokens(&[r]).await?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    async fn reqs_by_token(&self, collection: &str, token_id: &str) -> Result<Vec<Request>> {
+        let rows = self
+            .fetch_all(
+                sqlx::query(
+                    "SELECT * FROM requests
+                     WHERE (collection_src = $1 OR collection_dst = $1)
+                     AND hash IN (SELECT req_hash FROM request_tokens WHERE token_id = $2)",
+                )
+                .bind(collection)
+                .bind(token_id),
+            )
+            .await?;
+
+        self.requests_with_tokens(&rows).await
+    }
+
+    async fn insert_req(&self, req: Request) -> Result<()> {
+        self.execute(
+            sqlx::query(
+                "INSERT INTO requests
+                 (hash, chain_src, from_address, to_address, collection_src, collection_dst, content,
+                  collection_type, name, symbol, base_uri, timestamp)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                 ON CONFLICT (hash) DO UPDATE SET
+                 chain_src = EXCLUDED.chain_src,
+                 from_address = EXCLUDED.from_address,
+                 to_address = EXCLUDED.to_address,
+                 collection_src = EXCLUDED.collection_src,
+                 collection_dst = EXCLUDED.collection_dst,
+                 content = EXCLUDED.content,
+                 collection_type = EXCLUDED.collection_type,
+                 name = EXCLUDED.name,
+                 symbol = EXCLUDED.symbol,
+                 base_uri = EXCLUDED.base_uri,
+                 timestamp = EXCLUDED.timestamp",
+            )
+            .bind(&req.hash)
+            .bind(req.chain_src.to_string())
+            .bind(req.from)
+            .bind(req.to)
+            .bind(req.collection_src)
+            .bind(req.collection_dst)
+            .bind(req.content)
+            .bind(req.collection_type.to_string())
+            .bind(req.name)
+            .bind(req.symbol)
+            .bind(req.base_uri)
+            .bind(req.timestamp as i64),
+        )
+        .await?;
+
+        self.execute(sqlx::query("DELETE FROM request_tokens WHERE req_hash = $1").bind(&req.hash))
+            .await?;
+
+        for (index, token) in req.tokens.into_iter().enumerate() {
+            self.execute(
+                sqlx::query(
+                    "INSERT INTO request_tokens (req_hash, token_index, token_id, amount, uri)
+                     VALUES ($1, $2, $3, $4, $5)",
+                )
+                .bind(&req.hash)
+                .bind(index as i64)
+                .bind(token.token_id)
+                .bind(token.amount)
+                .bind(token.uri),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn remove_req(&self, hash: &str) -> Result<()> {
+        self.execute(sqlx::query("DELETE FROM request_tokens WHERE req_hash = $1").bind(hash))
+            .await?;
+
+        self.execute(sqlx::query("DELETE FROM requests WHERE hash = $1").bind(hash))
+            .await?;
+
+        Ok(())
+    }
+}
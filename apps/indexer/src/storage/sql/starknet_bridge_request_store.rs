@@ -0,0 +1,42 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::SqlStore;
+use crate::storage::{protocol::ProtocolParser, store::StarknetBridgeRequestStore, Request};
+
+#[async_trait]
+impl StarknetBridgeRequestStore for SqlStore {
+    /// Inserts one row per token bridged by the request.
+    async fn insert_request(&self, tx_hash: String, req: Request) -> Result<()> {
+        for token_id in req.get_token_ids()? {
+            self.execute(
+                sqlx::query(
+                    "INSERT INTO bridged_tokens
+                     (tx_hash, req_hash, token_id, collection_src, from_address, to_address, is_checked)
+                     VALUES ($1, $2, $3, $4, $5, $6, FALSE)
+                     ON CONFLICT DO NOTHING",
+                )
+                .bind(&tx_hash)
+                .bind(&req.hash)
+                .bind(token_id)
+                .bind(&req.collection_src)
+                .bind(&req.from)
+                .bind(&req.to),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn remove_request(&self, tx_hash: &str, req_hash: &str) -> Result<()> {
+        self.execute(
+            sqlx::query("DELETE FROM bridged_tokens WHERE tx_hash = $1 AND req_hash = $2")
+                .bind(tx_hash)
+                .bind(req_hash),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex as AsyncMutex;
+
+use super::SqlStore;
+use crate::storage::store::TransactionStore;
+
+#[async_trait]
+impl TransactionStore for SqlStore {
+    async fn begin_transaction(&self) -> Result<SqlStore> {
+        let transaction = self.pool.begin().await?;
+
+        Ok(SqlStore {
+            pool: self.pool.clone(),
+            transaction: Some(Arc::new(AsyncMutex::new(Some(transaction)))),
+        })
+    }
+
+    async fn commit_transaction(&self) -> Result<()> {
+        if let Some(t) = &self.transaction {
+            if let Some(t) = t.lock().await.take() {
+                t.commit().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn abort_transaction(&self) -> Result<()> {
+        if let Some(t) = &self.transaction {
+            if let Some(t) = t.lock().await.take() {
+                t.rollback().await?;
+            }
+        }
+
+        Ok(())
+    }
+}
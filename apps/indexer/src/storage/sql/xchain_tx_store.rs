@@ -0,0 +1,172 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{any::AnyRow, Row};
+
+use super::{try_get_opt, SqlStore};
+use crate::storage::{
+    store::CrossChainTxStore, BridgeChain, CrossChainTx, CrossChainTxKind, CrossChainTxStatus,
+};
+
+fn tx_from_row(row: &AnyRow) -> Result<CrossChainTx> {
+    Ok(CrossChainTx {
+        chain: row.try_get::<&str, _>("chain")?.parse()?,
+        kind: row.try_get::<&str, _>("kind")?.parse()?,
+        req_hash: row.try_get("req_hash")?,
+        req_content: row.try_get("req_content")?,
+        tx_hash: row.try_get("tx_hash")?,
+        status: row.try_get::<&str, _>("status")?.parse()?,
+        attempts: row.try_get::<i64, _>("attempts")? as u32,
+        nonce: try_get_opt::<i64>(row, "nonce")?.map(|n| n as u64),
+        gas_price: try_get_opt(row, "gas_price")?,
+        last_error: try_get_opt(row, "last_error")?,
+        next_attempt_at: row.try_get::<i64, _>("next_attempt_at")? as u64,
+        submitted_at: try_get_opt::<i64>(row, "submitted_at")?.map(|t| t as u64),
+        relay_decision: try_get_opt::<&str>(row, "relay_decision")?
+            .map(|d| d.parse())
+            .transpose()?,
+        estimated_fee: try_get_opt(row, "estimated_fee")?,
+        paid_fee: try_get_opt(row, "paid_fee")?,
+        deferred_since: try_get_opt::<i64>(row, "deferred_since")?.map(|t| t as u64),
+    })
+}
+
+#[async_trait]
+impl CrossChainTxStore for SqlStore {
+    async fn insert_tx(&self, tx: CrossChainTx) -> Result<()> {
+        // A transaction already sent must not be reset.
+        self.execute(
+            sqlx::query(
+                "INSERT INTO xchain_txs (req_hash, kind, chain, req_content, tx_hash, status,
+                 attempts, nonce, gas_price, last_error, next_attempt_at, submitted_at,
+                 relay_decision, estimated_fee, paid_fee, deferred_since)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(tx.req_hash)
+            .bind(tx.kind.to_string())
+            .bind(tx.chain.to_string())
+            .bind(tx.req_content)
+            .bind(tx.tx_hash)
+            .bind(tx.status.to_string())
+            .bind(tx.attempts as i64)
+            .bind(tx.nonce.map(|n| n as i64))
+            .bind(tx.gas_price)
+            .bind(tx.last_error)
+            .bind(tx.next_attempt_at as i64)
+            .bind(tx.submitted_at.map(|t| t as i64))
+            .bind(tx.relay_decision.map(|d| d.to_string()))
+            .bind(tx.estimated_fee)
+            .bind(tx.paid_fee)
+            .bind(tx.deferred_since.map(|t| t as i64)),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_tx(&self, tx: CrossChainTx) -> Result<()> {
+        self.execute(
+            sqlx::query(
+                "UPDATE xchain_txs SET tx_hash = $3, status = $4, attempts = $5, nonce = $6,
+                 gas_price = $7, last_error = $8, next_attempt_at = $9, submitted_at = $10,
+                 relay_decision = $11, estimated_fee = $12, paid_fee = $13, deferred_since = $14
+                 WHERE req_hash = $1 AND kind = $2",
+            )
+            .bind(tx.req_hash)
+            .bind(tx.kind.to_string())
+            .bind(tx.tx_hash)
+            .bind(tx.status.to_string())
+            .bind(tx.attempts as i64)
+            .bind(tx.nonce.map(|n| n as i64))
+            .bind(tx.gas_price)
+            .bind(tx.last_error)
+            .bind(tx.next_attempt_at as i64)
+            .bind(tx.submitted_at.map(|t| t as i64))
+            .bind(tx.relay_decision.map(|d| d.to_string()))
+            .bind(tx.estimated_fee)
+            .bind(tx.paid_fee)
+            .bind(tx.deferred_since.map(|t| t as i64)),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_tx_as_sent(
+        &self,
+        req_hash: String,
+        kind: CrossChainTxKind,
+        tx_hash: String,
+    ) -> Result<()> {
+        self.execute(
+            sqlx::query(
+                "UPDATE xchain_txs SET tx_hash = $3, status = $4 WHERE req_hash = $1 AND kind = $2",
+            )
+            .bind(req_hash)
+            .bind(kind.to_string())
+            .bind(tx_hash)
+            .bind(CrossChainTxStatus::Confirmed.to_string()),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn pending_xtxs(&self, chain: BridgeChain) -> Result<Vec<CrossChainTx>> {
+        let rows = self
+            .fetch_all(
+                sqlx::query(
+                    "SELECT * FROM xchain_txs
+                     WHERE chain = $1 AND status IN ('queued', 'submitted', 'failed')",
+                )
+                .bind(chain.to_string()),
+            )
+            .await?;
+
+        rows.iter().map(tx_from_row).collect()
+    }
+
+    async fn list_xtxs(&self, chain: BridgeChain) -> Result<Vec<CrossChainTx>> {
+        let rows = self
+            .fetch_all(
+                sqlx::query("SELECT * FROM xchain_txs WHERE chain = $1").bind(chain.to_string()),
+            )
+            .await?;
+
+        rows.iter().map(tx_from_row).collect()
+    }
+
+    async fn tx_from_request_kind(
+        &self,
+        req_hash: &str,
+        kind: CrossChainTxKind,
+    ) -> Result<Option<CrossChainTx>> {
+        let row = self
+            .fetch_optional(
+                sqlx::query("SELECT * FROM xchain_txs WHERE req_hash = $1 AND kind = $2")
+                    .bind(req_hash)
+                    .bind(kind.to_string()),
+            )
+            .await?;
+
+        row.as_ref().map(tx_from_row).transpose()
+    }
+
+    async fn remove_txs_by_hash(&self, tx_hash: &str) -> Result<()> {
+        self.execute(sqlx::query("DELETE FROM xchain_txs WHERE tx_hash = $1").bind(tx_hash))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn remove_tx(&self, req_hash: &str, kind: CrossChainTxKind) -> Result<()> {
+        self.execute(
+            sqlx::query("DELETE FROM xchain_txs WHERE req_hash = $1 AND kind = $2")
+                .bind(req_hash)
+                .bind(kind.to_string()),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,432 @@
+//! Status of a request, computed from its events.
+//!
+//! A request goes through the following states, depending on its direction:
+//!
+//! * L1 -> L2: `deposited` (never observed, the message is sent with the deposit),
+//!   `message_in_flight` until the withdraw on L2, then `completed`.
+//!   From `message_in_flight`, the request may end up `errored` if the message is never
+//!   consumed. The depositor may then cancel the message on L1: the request is
+//!   `cancel_pending` once the cancellation is started, and `cancelled` once completed,
+//!   unless the message is consumed in the meantime.
+//! * L2 -> L1: `deposited` until the L2 block is accepted on L1, `message_in_flight`
+//!   until the message is available in the Starknet core contract, `withdraw_available`,
+//!   then `completed`. The request is `errored` if the message never becomes available.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{store::EventStore, BridgeChain, Event, EventLabel, FinalityStatus, Request};
+
+// Estimated time (in seconds) for an L1->L2 message to be consumed on Starknet.
+const L1_L2_MESSAGE_DELAY: u64 = 600;
+// Estimated time (in seconds) for an L2 block to be proven on L1.
+const L2_L1_MESSAGE_DELAY: u64 = 14_400;
+// Delay (in seconds) of the Starknet core contract before a message cancellation
+// can be completed.
+const CANCEL_DELAY: u64 = 432_000;
+
+// Transaction hash of the transit error events, which are not emitted by a transaction.
+const TRANSIT_ERROR_TX_HASH: &str = "0x5452414e5349545f4552524f52"; // TRANSIT_ERROR
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestStatus {
+    Deposited,
+    MessageInFlight,
+    WithdrawAvailable,
+    Completed,
+    CancelPending,
+    Cancelled,
+    Errored,
+}
+
+/// Action expected from the user to move the request forward.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NextAction {
+    // Nothing to do, the request is in a final state.
+    None,
+    // The request moves forward without the user.
+    Wait,
+    // The user has to send the withdraw transaction on L1.
+    WithdrawOnL1,
+    // The user may start the cancellation of the message on L1.
+    StartCancelOnL1,
+    // The user has to complete the cancellation on L1.
+    CompleteCancelOnL1,
+}
+
+/// Status of a request, with the next action and the validation of its events.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RequestState {
+    pub status: RequestStatus,
+    pub next_action: NextAction,
+    // Estimated timestamp at which the next action will be possible, if waiting.
+    pub eta: Option<u64>,
+    // Description of the events which are not possible in the request lifecycle.
+    pub invalid_transitions: Vec<String>,
+}
+
+impl RequestState {
+    /// Computes the state of the request from its events, in any order.
+    pub fn from_events(req: &Request, events: &[Event]) -> RequestState {
+        let event = |label: EventLabel| events.iter().find(|e| e.label == label);
+        let has = |label: EventLabel| event(label).is_some();
+
+        let withdraw_auto = req
+            .decoded()
+            .map(|d| d.header.withdraw_auto)
+            .unwrap_or(false);
+
+        let (deposit_label, completed_label) = match req.chain_src {
+            BridgeChain::Ethereum => (
+                EventLabel::DepositInitiatedL1,
+                EventLabel::WithdrawCompletedL2,
+            ),
+            BridgeChain::Starknet => (
+                EventLabel::DepositInitiatedL2,
+                EventLabel::WithdrawCompletedL1,
+            ),
+        };
+
+        let invalid_transitions = invalid_transitions(req.chain_src, events);
+
+        let state = |status, next_action, eta| RequestState {
+            status,
+            next_action,
+            eta,
+            invalid_transitions: invalid_transitions.clone(),
+        };
+
+        // The message may still be consumed after the cancellation is started.
+        if has(completed_label) {
+            return state(RequestStatus::Completed, NextAction::None, None);
+        }
+
+        if has(EventLabel::CancelCompletedL1) {
+            return state(RequestStatus::Cancelled, NextAction::None, None);
+        }
+
+        if let Some(e) = event(EventLabel::CancelStartedL1) {
+            return state(
+                RequestStatus::CancelPending,
+                NextAction::CompleteCancelOnL1,
+                Some(e.block_timestamp + CANCEL_DELAY),
+            );
+        }
+
+        if has(EventLabel::TransitErrorL1L2) {
+            return state(RequestStatus::Errored, NextAction::StartCancelOnL1, None);
+        }
+
+        // The message may become available after the transit error was raised.
+        if has(EventLabel::WithdrawAvailableL1) {
+            let next_action = if withdraw_auto {
+                NextAction::Wait
+            } else {
+                NextAction::WithdrawOnL1
+            };
+            return state(RequestStatus::WithdrawAvailable, next_action, None);
+        }
+
+        if has(EventLabel::TransitErrorL2L1) {
+            return state(RequestStatus::Errored, NextAction::None, None);
+        }
+
+        match event(deposit_label) {
+            Some(e) if req.chain_src == BridgeChain::Ethereum => state(
+                RequestStatus::MessageInFlight,
+                NextAction::Wait,
+                Some(e.block_timestamp + L1_L2_MESSAGE_DELAY),
+            ),
+            Some(e) => {
+                let status = if e.finality == Some(FinalityStatus::AcceptedOnL1) {
+                    RequestStatus::MessageInFlight
+                } else {
+                    RequestStatus::Deposited
+                };
+                state(
+                    status,
+                    NextAction::Wait,
+                    Some(e.block_timestamp + L2_L1_MESSAGE_DELAY),
+                )
+            }
+            None => state(RequestStatus::Deposited, NextAction::Wait, None),
+        }
+    }
+}
+
+/// Returns the transit error events of the requests initiated on `chain_src`
+/// which didn't reach the other chain `timeout` seconds after the deposit.
+///
+/// The error events keep the block number of the deposit, to be removed
+/// with the deposit in case of a reorg.
+pub async fn transit_errors<T: EventStore>(
+    store: &T,
+    chain_src: BridgeChain,
+    timeout: u64,
+    now: u64,
+) -> Result<Vec<Event>> {
+    let (deposit_label, error_label, followups) = match chain_src {
+        BridgeChain::Ethereum => (
+            EventLabel::DepositInitiatedL1,
+            EventLabel::TransitErrorL1L2,
+            vec![
+                EventLabel::WithdrawCompletedL2,
+                EventLabel::CancelStartedL1,
+                EventLabel::CancelCompletedL1,
+                EventLabel::TransitErrorL1L2,
+            ],
+        ),
+        BridgeChain::Starknet => (
+            EventLabel::DepositInitiatedL2,
+            EventLabel::TransitErrorL2L1,
+            vec![
+                EventLabel::WithdrawAvailableL1,
+                EventLabel::WithdrawCompletedL1,
+                EventLabel::TransitErrorL2L1,
+            ],
+        ),
+    };
+
+    let deposits = store
+        .events_without_followup(deposit_label, &followups, now.saturating_sub(timeout))
+        .await?;
+
+    Ok(deposits
+        .into_iter()
+        .map(|d| Event {
+            req_hash: d.req_hash,
+            label: error_label,
+            block_timestamp: now,
+            block_number: d.block_number,
+            tx_hash: TRANSIT_ERROR_TX_HASH.to_string(),
+            price: None,
+            finality: None,
+            log_index: None,
+        })
+        .collect())
+}
+
+/// Returns the description of the events which can't happen
+/// for a request initiated on `chain_src`.
+fn invalid_transitions(chain_src: BridgeChain, events: &[Event]) -> Vec<String> {
+    let has = |label: EventLabel| events.iter().any(|e| e.label == label);
+    let mut invalid = vec![];
+
+    let allowed = match chain_src {
+        BridgeChain::Ethereum => [
+            EventLabel::DepositInitiatedL1,
+            EventLabel::WithdrawCompletedL2,
+            EventLabel::TransitErrorL1L2,
+            EventLabel::CancelStartedL1,
+            EventLabel::CancelCompletedL1,
+        ]
+        .to_vec(),
+        BridgeChain::Starknet => [
+            EventLabel::DepositInitiatedL2,
+            EventLabel::WithdrawAvailableL1,
+            EventLabel::WithdrawCompletedL1,
+            EventLabel::TransitErrorL2L1,
+        ]
+        .to_vec(),
+    };
+
+    for e in events {
+        if !allowed.contains(&e.label) {
+            invalid.push(format!(
+                "{:?} is not expected for a request from {:?}",
+                e.label, chain_src
+            ));
+        }
+    }
+
+    let deposited = has(EventLabel::DepositInitiatedL1) || has(EventLabel::DepositInitiatedL2);
+    let completed = has(EventLabel::WithdrawCompletedL1) || has(EventLabel::WithdrawCompletedL2);
+
+    if !deposited {
+        for e in events {
+            invalid.push(format!("{:?} without deposit", e.label));
+        }
+    }
+
+    if has(EventLabel::CancelCompletedL1) && !has(EventLabel::CancelStartedL1) {
+        invalid.push("CancelCompletedL1 without cancel started".to_string());
+    }
+
+    if completed && has(EventLabel::CancelCompletedL1) {
+        invalid.push("Request both withdrawn and cancelled".to_string());
+    }
+
+    invalid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::MemoryStore;
+
+    fn event(label: EventLabel, finality: Option<FinalityStatus>) -> Event {
+        Event {
+            req_hash: String::from("0x1"),
+            label,
+            block_timestamp: 100,
+            block_number: 1,
+            tx_hash: String::from("0x2"),
+            price: None,
+            finality,
+            log_index: None,
+        }
+    }
+
+    #[test]
+    fn test_l2_l1_states() {
+        let req = Request {
+            chain_src: BridgeChain::Starknet,
+            ..Default::default()
+        };
+
+        let mut events = vec![event(
+            EventLabel::DepositInitiatedL2,
+            Some(FinalityStatus::AcceptedOnL2),
+        )];
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::Deposited);
+        assert_eq!(s.next_action, NextAction::Wait);
+        assert_eq!(s.eta, Some(100 + L2_L1_MESSAGE_DELAY));
+        assert!(s.invalid_transitions.is_empty());
+
+        events.push(event(EventLabel::WithdrawAvailableL1, None));
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::WithdrawAvailable);
+        assert_eq!(s.next_action, NextAction::WithdrawOnL1);
+
+        events.push(event(EventLabel::WithdrawCompletedL1, None));
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::Completed);
+        assert_eq!(s.next_action, NextAction::None);
+        assert!(s.invalid_transitions.is_empty());
+    }
+
+    #[test]
+    fn test_l2_l1_late_message() {
+        let req = Request {
+            chain_src: BridgeChain::Starknet,
+            ..Default::default()
+        };
+
+        let mut events = vec![
+            event(
+                EventLabel::DepositInitiatedL2,
+                Some(FinalityStatus::AcceptedOnL1),
+            ),
+            event(EventLabel::TransitErrorL2L1, None),
+        ];
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::Errored);
+        assert_eq!(s.next_action, NextAction::None);
+
+        events.push(event(EventLabel::WithdrawAvailableL1, None));
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::WithdrawAvailable);
+        assert_eq!(s.next_action, NextAction::WithdrawOnL1);
+    }
+
+    #[test]
+    fn test_l1_l2_cancel() {
+        let req = Request {
+            chain_src: BridgeChain::Ethereum,
+            ..Default::default()
+        };
+
+        let mut events = vec![
+            event(EventLabel::DepositInitiatedL1, None),
+            event(EventLabel::CancelStartedL1, None),
+        ];
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::CancelPending);
+        assert_eq!(s.next_action, NextAction::CompleteCancelOnL1);
+        assert_eq!(s.eta, Some(100 + CANCEL_DELAY));
+
+        events.push(event(EventLabel::CancelCompletedL1, None));
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::Cancelled);
+        assert_eq!(s.next_action, NextAction::None);
+        assert!(s.invalid_transitions.is_empty());
+    }
+
+    #[test]
+    fn test_l1_l2_withdrawn_after_cancel_started() {
+        let req = Request {
+            chain_src: BridgeChain::Ethereum,
+            ..Default::default()
+        };
+
+        // The message is consumed on L2 before the cancellation is completed.
+        let events = vec![
+            event(EventLabel::DepositInitiatedL1, None),
+            event(EventLabel::CancelStartedL1, None),
+            event(EventLabel::WithdrawCompletedL2, None),
+        ];
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::Completed);
+        assert_eq!(s.next_action, NextAction::None);
+        assert!(s.invalid_transitions.is_empty());
+
+        // Same events, indexed in the other order.
+        let events: Vec<Event> = events.into_iter().rev().collect();
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::Completed);
+    }
+
+    #[test]
+    fn test_invalid_transitions() {
+        let req = Request {
+            chain_src: BridgeChain::Ethereum,
+            ..Default::default()
+        };
+
+        let events = vec![event(EventLabel::WithdrawCompletedL2, None)];
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::Completed);
+        assert_eq!(s.invalid_transitions.len(), 1);
+
+        let events = vec![
+            event(EventLabel::DepositInitiatedL1, None),
+            event(EventLabel::WithdrawCompletedL1, None),
+        ];
+        let s = RequestState::from_events(&req, &events);
+        assert_eq!(s.status, RequestStatus::MessageInFlight);
+        assert_eq!(s.invalid_transitions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transit_errors() {
+        let store = MemoryStore::new();
+
+        let mut deposit = event(EventLabel::DepositInitiatedL1, None);
+        store.insert_event(deposit.clone()).await.unwrap();
+
+        deposit.req_hash = String::from("0x3");
+        store.insert_event(deposit.clone()).await.unwrap();
+        let mut withdraw = event(EventLabel::WithdrawCompletedL2, None);
+        withdraw.req_hash = String::from("0x3");
+        store.insert_event(withdraw).await.unwrap();
+
+        let chain = BridgeChain::Ethereum;
+        assert!(transit_errors(&store, chain, 60, 150)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let errors = transit_errors(&store, chain, 60, 200).await.unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].req_hash, "0x1");
+        assert_eq!(errors[0].label, EventLabel::TransitErrorL1L2);
+
+        store.insert_event(errors[0].clone()).await.unwrap();
+        assert!(transit_errors(&store, chain, 60, 200)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
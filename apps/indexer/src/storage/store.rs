@@ -1,95 +1,203 @@
 //! Structs and traits related to data to be stored
 //! after indexing Starklane bridge events.
 use crate::storage::{
-    BlockIndex, BridgeChain, CrossChainTx, CrossChainTxKind, Event, PendingWithdraw, Request,
+    AdminEvent, BlockIndex, BridgeChain, CollectionContract, CrossChainTx, CrossChainTxKind, Event,
+    EventLabel, FinalityStatus, IndexCursor, IndexedRange, PendingWithdraw, Request, RequestFilter,
 };
 use anyhow::Result;
 use async_trait::async_trait;
 
+/// Store able to group several operations into an atomic transaction.
+#[async_trait]
+pub trait TransactionStore: Sized {
+    /// Starts a transaction, and returns a store on which all the operations
+    /// are part of this transaction.
+    async fn begin_transaction(&self) -> Result<Self>;
+
+    /// Commits all the operations done on the store since `begin_transaction`.
+    async fn commit_transaction(&self) -> Result<()>;
+
+    /// Discards all the operations done on the store since `begin_transaction`.
+    async fn abort_transaction(&self) -> Result<()>;
+}
+
 /// Store related to cross chain transactions.
 #[async_trait]
 pub trait CrossChainTxStore {
-    ///
+    /// Inserts the transaction, if no transaction of the same kind
+    /// exists for the request.
     async fn insert_tx(&self, tx: CrossChainTx) -> Result<()>;
 
-    ///
-    async fn set_tx_as_sent(&self, req_hash: String, tx_hash: String) -> Result<()>;
+    /// Replaces the relayer state of the transaction with the same request hash and kind.
+    async fn update_tx(&self, tx: CrossChainTx) -> Result<()>;
+
+    /// Marks the transaction of the given kind for the request as sent with `tx_hash`,
+    /// and confirmed.
+    async fn set_tx_as_sent(
+        &self,
+        req_hash: String,
+        kind: CrossChainTxKind,
+        tx_hash: String,
+    ) -> Result<()>;
 
-    ///
     async fn list_xtxs(&self, chain: BridgeChain) -> Result<Vec<CrossChainTx>>;
 
-    ///
+    /// Returns the transactions not yet confirmed, failed or abandoned.
     async fn pending_xtxs(&self, chain: BridgeChain) -> Result<Vec<CrossChainTx>>;
 
-    ///
     async fn tx_from_request_kind(
         &self,
         req_hash: &str,
         kind: CrossChainTxKind,
     ) -> Result<Option<CrossChainTx>>;
+
+    /// Removes the transactions marked as sent with the given tx hash.
+    async fn remove_txs_by_hash(&self, tx_hash: &str) -> Result<()>;
+
+    /// Removes the transaction of the given kind for the request.
+    async fn remove_tx(&self, req_hash: &str, kind: CrossChainTxKind) -> Result<()>;
 }
 
 #[async_trait]
 pub trait StarknetBridgeRequestStore {
-    ///
     async fn insert_request(&self, tx_hash: String, request: Request) -> Result<()>;
+
+    /// Removes the tokens inserted for the request withdrawn in the transaction `tx_hash`.
+    async fn remove_request(&self, tx_hash: &str, req_hash: &str) -> Result<()>;
 }
 
 /// Store related to the indexing state.
 #[async_trait]
 pub trait BlockStore {
-    ///
+    /// Inserts the block, or replaces the block already indexed with the same number.
     async fn insert_block(&self, block: BlockIndex) -> Result<()>;
 
-    ///
     async fn block_by_number(
         &self,
         chain: BridgeChain,
         block_number: u64,
     ) -> Result<Option<BlockIndex>>;
+
+    /// Returns the `count` highest indexed blocks, ordered from the highest.
+    async fn latest_blocks(&self, chain: BridgeChain, count: u64) -> Result<Vec<BlockIndex>>;
+
+    /// Removes all the blocks starting at `block_number` (included).
+    /// The indexed ranges are truncated accordingly.
+    async fn remove_blocks_from(&self, chain: BridgeChain, block_number: u64) -> Result<()>;
+
+    /// Marks the blocks `from_block..=to_block` as fully indexed.
+    /// The range is merged with an indexed range ending right before `from_block`.
+    async fn insert_indexed_range(
+        &self,
+        chain: BridgeChain,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<()>;
+
+    /// Returns the indexed range containing `block_number`, if any.
+    async fn indexed_range(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Option<IndexedRange>>;
+
+    /// Returns the indexing cursor of the given chain, if any.
+    async fn cursor(&self, chain: BridgeChain) -> Result<Option<IndexCursor>>;
+
+    /// Sets the indexing cursor of the given chain to `block_number`.
+    async fn set_cursor(&self, chain: BridgeChain, block_number: u64) -> Result<()>;
 }
 
 /// Store for the requests content.
 #[async_trait]
 pub trait RequestStore {
-    ///
-    async fn reqs_by_wallet(&self, address: &str) -> Result<Vec<Request>>;
+    /// Returns the requests sent or received by the wallet, matching the filter.
+    async fn reqs_by_wallet(&self, address: &str, filter: &RequestFilter) -> Result<Vec<Request>>;
 
-    ///
     async fn req_by_hash(&self, hash: &str) -> Result<Option<Request>>;
 
-    ///
+    /// Returns the requests which moved the token `token_id` (in decimal)
+    /// of the collection, given by its address on the source or the destination chain.
+    async fn reqs_by_token(&self, collection: &str, token_id: &str) -> Result<Vec<Request>>;
+
+    /// Inserts the request, or replaces the request with the same hash.
     async fn insert_req(&self, req: Request) -> Result<()>;
+
+    async fn remove_req(&self, hash: &str) -> Result<()>;
 }
 
 /// Store for events.
 #[async_trait]
 pub trait EventStore {
-    ///
+    /// Inserts the event, or replaces the event with the same request hash,
+    /// label and transaction hash.
     async fn insert_event(&self, event: Event) -> Result<()>;
 
-    ///
     async fn events_by_request(&self, req_hash: &str) -> Result<Vec<Event>>;
 
-    ///
+    /// Returns the events of all the given requests.
+    async fn events_by_requests(&self, req_hashes: &[String]) -> Result<Vec<Event>>;
+
     async fn get_total_tokens_bridged_on_starknet(&self, contract_address: &str) -> Result<u64>;
 
-    ///
     async fn event_by_tx(&self, tx_hash: &str) -> Result<Option<Event>>;
+
+    /// Removes all the events emitted on `chain` starting at `block_number` (included),
+    /// and returns the removed events.
+    async fn remove_events_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Vec<Event>>;
+
+    /// Removes the events emitted on `chain` in the block `block_number` only,
+    /// and returns the removed events.
+    async fn remove_block_events(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<Vec<Event>>;
+
+    /// Returns the block numbers, in ascending order, containing
+    /// events with the given finality status.
+    async fn event_blocks_by_finality(&self, finality: FinalityStatus) -> Result<Vec<u64>>;
+
+    /// Marks all the events accepted on L2 up to `block_number` (included)
+    /// as accepted on L1.
+    async fn finalize_events(&self, block_number: u64) -> Result<()>;
+
+    /// Returns the events with the given label emitted up to `max_timestamp` (included),
+    /// whose request has no event with one of the `followups` labels.
+    async fn events_without_followup(
+        &self,
+        label: EventLabel,
+        followups: &[EventLabel],
+        max_timestamp: u64,
+    ) -> Result<Vec<Event>>;
 }
 
 /// Store for bridged collections.
 #[async_trait]
 pub trait CollectionStore {
     /// Insert a collection as being bridged for the first time.
-    async fn insert_collection(
+    /// A collection already registered with the same addresses is not modified.
+    async fn insert_collection(&self, collection: CollectionContract) -> Result<()>;
+
+    /// Returns the latest registered collection with the given address,
+    /// on the source or the destination chain.
+    async fn collection_by_address(&self, address: &str) -> Result<Option<CollectionContract>>;
+
+    /// Removes all the collections registered by the events emitted on `chain`
+    /// starting at `block_number` (included).
+    async fn remove_collections_from_block(
         &self,
-        chain_src: &str,
-        address_src: &str,
-        address_dst: &str,
-        time: u64,
-        req_hash: &str,
+        chain: BridgeChain,
+        block_number: u64,
     ) -> Result<()>;
+
+    /// Removes the collections registered by the events emitted on `chain`
+    /// in the block `block_number` only.
+    async fn remove_block_collections(&self, chain: BridgeChain, block_number: u64) -> Result<()>;
 }
 
 #[async_trait]
@@ -99,4 +207,28 @@ pub trait PendingWithdrawStore {
     async fn get_pending_withdraws(&self) -> Result<Vec<PendingWithdraw>>;
 
     async fn remove_pending_withdraw(&self, pending_withdraw: PendingWithdraw) -> Result<()>;
+
+    async fn remove_pending_withdraws_by_request(&self, req_hash: &str) -> Result<()>;
+}
+
+/// Store for the bridge administration events.
+#[async_trait]
+pub trait AdminEventStore {
+    /// Inserts the event, or replaces the event with the same chain,
+    /// block number and event index.
+    async fn insert_admin_event(&self, event: AdminEvent) -> Result<()>;
+
+    /// Returns the administration events emitted on `chain`, in emission order.
+    async fn admin_events(&self, chain: BridgeChain) -> Result<Vec<AdminEvent>>;
+
+    /// Removes all the administration events emitted on `chain` starting
+    /// at `block_number` (included).
+    async fn remove_admin_events_from_block(
+        &self,
+        chain: BridgeChain,
+        block_number: u64,
+    ) -> Result<()>;
+
+    /// Removes the administration events emitted on `chain` in the block `block_number` only.
+    async fn remove_block_admin_events(&self, chain: BridgeChain, block_number: u64) -> Result<()>;
 }